@@ -5,8 +5,9 @@ use glium::{glutin::*, *};
 #[cfg(feature = "sound")]
 use crate::sound::{self, SoundBuffer};
 use crate::{
-    Camera, CanFail, Canvas, Context, ContextBuilder, Drawer, Event, FloatingScalar, KuleResult,
-    StateTracker, Window,
+    post::BloomPass, Camera, CanFail, Canvas, Context, ContextBuilder, CustomEvent, Drawer, Event,
+    FloatingScalar, KuleResult, RenderTarget, StateTracker, Texture, Trans, Transform, Vector2,
+    Window,
 };
 
 /**
@@ -43,6 +44,18 @@ pub trait Kule: Sized + 'static {
     fn event(event: Event, app: &mut Self, ctx: &mut Context<Self::Resources>) -> CanFail {
         Ok(())
     }
+    /**
+    Handle a custom event sent with an [`EventSender`](struct.EventSender.html)
+
+    Use `Box::downcast` to get the value that was sent.
+    */
+    fn custom_event(
+        event: CustomEvent,
+        app: &mut Self,
+        ctx: &mut Context<Self::Resources>,
+    ) -> CanFail {
+        Ok(())
+    }
     /// Called when the app is closed
     fn teardown(app: Self, ctx: &mut Context<Self::Resources>) {}
     #[cfg(feature = "sound")]
@@ -80,6 +93,14 @@ pub trait Kule: Sized + 'static {
             samples,
             automatic_close,
             update_frequency,
+            logical_pixels,
+            buffer_events,
+            input_buffer,
+            hot_reload_shaders,
+            srgb,
+            resolution,
+            bloom,
+            persistent,
             ..
         } = builder;
         // Init audio
@@ -87,29 +108,48 @@ pub trait Kule: Sized + 'static {
         let sink = sound::sink();
         // Build event loop and display
         #[cfg(not(test))]
-        let event_loop = event_loop::EventLoop::new();
+        let event_loop = event_loop::EventLoop::<CustomEvent>::with_user_event();
         #[cfg(test)]
         let event_loop = {
             #[cfg(unix)]
             use platform::unix::EventLoopExtUnix;
             #[cfg(windows)]
             use platform::windows::EventLoopExtWindows;
-            event_loop::EventLoop::<()>::new_any_thread()
+            event_loop::EventLoop::<CustomEvent>::new_any_thread()
         };
         let wb = window::WindowBuilder::new()
             .with_title(title)
             .with_window_icon(icon)
             .with_inner_size(dpi::LogicalSize::new(size[0], size[1]));
-        let cb = glutin::ContextBuilder::new()
-            .with_multisampling(samples)
-            .with_stencil_buffer(1);
-        let display = Display::new(wb, cb, &event_loop)?;
+        let cb = |samples| {
+            glutin::ContextBuilder::new()
+                .with_multisampling(samples)
+                .with_stencil_buffer(8)
+                .with_srgb(srgb)
+        };
+        let display = match Display::new(wb.clone(), cb(samples), &event_loop) {
+            Ok(display) => display,
+            // Fall back to no antialiasing if multisampling is not supported
+            Err(_) if samples > 0 => Display::new(wb, cb(0), &event_loop)?,
+            Err(e) => return Err(e.into()),
+        };
         let window_size = display.gl_window().window().inner_size();
+        let scale_factor = display.gl_window().window().scale_factor() as f32;
         let program = crate::default_shaders(&display);
+        let sdf_program = crate::sdf_shaders(&display);
+        let blank_texture = Texture::blank(&display)?;
+        let low_res = resolution
+            .map(|size| RenderTarget::new(&display, size))
+            .transpose()?;
+        let bloom = bloom
+            .map(|bloom| BloomPass::new(&display, bloom))
+            .transpose()?;
         let mut ctx = Context {
             program,
+            sdf_program,
             fonts: Default::default(),
             meshes: Default::default(),
+            shaders: Default::default(),
             #[cfg(feature = "sound")]
             mixer: sound::Mixer::new(&sink),
             #[cfg(feature = "sound")]
@@ -118,19 +158,60 @@ pub trait Kule: Sized + 'static {
             camera: Camera {
                 center: [0.0; 2],
                 zoom: 1.0,
+                projection: Trans::identity(),
                 window_size: window_size.into(),
+                scale: if logical_pixels { scale_factor } else { 1.0 },
             },
-            window: Window(display),
+            window: Window::new(display, logical_pixels),
             #[cfg(feature = "script")]
             scripts: crate::Scripts::load(script_env),
+            #[cfg(feature = "gamepad")]
+            gamepad_settings: Default::default(),
+            #[cfg(feature = "gamepad")]
+            gilrs: gilrs::Gilrs::new().ok(),
+            #[cfg(feature = "gamepad")]
+            rumbles: Vec::new(),
             should_close: false,
+            blank_texture,
+            low_res,
+            scene_target: Default::default(),
+            bloom,
+            persistent,
+            buffers: Default::default(),
+            srgb,
+            start_time: Instant::now(),
             update_timer: Instant::now(),
             fps_timer: Instant::now(),
+            recorder: None,
+            playback: None,
+            event_proxy: event_loop.create_proxy(),
         };
+        ctx.tracker.set_input_buffer(input_buffer);
+        // Track gamepads that were connected before the app started
+        #[cfg(feature = "gamepad")]
+        if let Some(gilrs) = &ctx.gilrs {
+            for (id, _) in gilrs.gamepads() {
+                ctx.tracker.gamepads.insert(id.into(), Default::default());
+            }
+        }
         // Run app setup
         let mut app = Some(Self::setup(&mut ctx)?);
+        // Input events held until the next update
+        let mut buffered: Vec<Event> = Vec::new();
         // Run the event loop
         event_loop.run(move |event, _, cf| {
+            // Handle custom events
+            let event = match event {
+                event::Event::UserEvent(custom) => {
+                    if let Some(app) = &mut app {
+                        if let Err(e) = Self::custom_event(custom, app, &mut ctx) {
+                            Self::handle_error(e, app, &mut ctx);
+                        }
+                    }
+                    event::Event::UserEvent(())
+                }
+                event => event.map_nonuser_event().unwrap_or_else(|_| unreachable!()),
+            };
             // Draw
             if let event::Event::RedrawEventsCleared = &event {
                 let now = Instant::now();
@@ -138,13 +219,47 @@ pub trait Kule: Sized + 'static {
                 ctx.fps_timer = now;
                 ctx.tracker.fps = ctx.tracker.fps.lerp(1.0 / dt, 0.1);
                 if let Some(app) = &mut app {
-                    if let Err(e) = ctx.draw(|drawer| Self::draw(drawer, app, &ctx)) {
+                    let res = if ctx.draws_offscreen() {
+                        ctx.draw_offscreen(|drawer| Self::draw(drawer, app, &ctx))
+                    } else {
+                        ctx.draw(|drawer| Self::draw(drawer, app, &ctx))
+                    };
+                    if let Err(e) = res {
                         Self::handle_error(e, app, &mut ctx)
                     }
                 }
             }
-            // Handle events
-            for event in Event::from_glutin(event, &mut ctx.tracker, &mut ctx.camera) {
+            // Handle events, ignoring real input while a recording is played back
+            let playing = ctx.playback.is_some();
+            let scroll = ctx.tracker.scroll();
+            #[allow(unused_mut)]
+            let mut events: Vec<Event> = if playing && Event::glutin_is_input(&event) {
+                Vec::new()
+            } else {
+                Event::from_glutin(event, &mut ctx.tracker, &mut ctx.camera).collect()
+            };
+            #[cfg(feature = "gamepad")]
+            if let (Some(gilrs), false) = (&mut ctx.gilrs, playing) {
+                events.extend(crate::gamepad::gamepad_events(
+                    gilrs,
+                    &ctx.gamepad_settings,
+                    &mut ctx.tracker,
+                ));
+            }
+            // Keep window space in logical pixels
+            if ctx.window.logical_pixels() {
+                for event in &events {
+                    if let Event::ScaleFactor(scale_factor) = *event {
+                        ctx.camera.scale = scale_factor;
+                    }
+                }
+            }
+            // Record input events
+            if let Some(recorder) = &mut ctx.recorder {
+                let scroll = ctx.tracker.scroll().sub(scroll);
+                recorder.record(&events, &ctx.tracker, scroll);
+            }
+            for event in events {
                 let automatic_close = event == Event::CloseRequest && automatic_close;
                 if automatic_close || ctx.should_close {
                     *cf = event_loop::ControlFlow::Exit;
@@ -153,21 +268,10 @@ pub trait Kule: Sized + 'static {
                     }
                     break;
                 } else if let Some(app) = &mut app {
-                    // Run app event method
-                    if let Err(e) = Self::event(event, app, &mut ctx) {
-                        Self::handle_error(e, app, &mut ctx);
-                    }
-                    // Run event scripts
-                    #[cfg(feature = "script")]
-                    if let Ok(scripts) = ctx.scripts() {
-                        if let Err(e) = scripts.batch_call("event", move |lua, t, f| {
-                            let mut ser = crate::LuaSerializer::new(lua);
-                            let event = ser.serialize(&event)?;
-                            f.call((t, event))?;
-                            Ok(())
-                        }) {
-                            Self::handle_error(e, app, &mut ctx);
-                        }
+                    if buffer_events && event.is_input() {
+                        buffered.push(event);
+                    } else {
+                        dispatch_event(event, app, &mut ctx);
                     }
                 }
             }
@@ -176,7 +280,31 @@ pub trait Kule: Sized + 'static {
             let dt = (now - ctx.update_timer).as_secs_f32();
             if dt >= 1.0 / update_frequency {
                 ctx.update_timer = now;
+                // Add fonts that finished loading
+                ctx.fonts.poll_loading();
+                // Play back the recorded events that were delivered before this update
+                let due = ctx
+                    .playback
+                    .as_mut()
+                    .map(|playback| playback.due())
+                    .unwrap_or_default();
+                for recorded in due {
+                    ctx.tracker.replay(&recorded);
+                    if let Some(app) = &mut app {
+                        dispatch_event(recorded.event, app, &mut ctx);
+                    }
+                }
                 if let Some(app) = &mut app {
+                    // Pass held input events
+                    for event in buffered.drain(..) {
+                        dispatch_event(event, app, &mut ctx);
+                    }
+                    // Reload changed shaders
+                    if hot_reload_shaders {
+                        if let Err(e) = ctx.shaders.reload_changed(&ctx.window) {
+                            Self::handle_error(e, app, &mut ctx);
+                        }
+                    }
                     // Run app update method
                     if let Err(e) = Self::update(dt, app, &mut ctx) {
                         Self::handle_error(e, app, &mut ctx);
@@ -185,18 +313,57 @@ pub trait Kule: Sized + 'static {
                     #[cfg(feature = "script")]
                     if let Ok(scripts) = ctx.scripts() {
                         if let Err(e) = scripts.batch_call("update", move |_, t, f| {
-                            f.call((t, dt))?;
+                            f.call::<_, ()>((t, dt))?;
                             Ok(())
                         }) {
                             Self::handle_error(e, app, &mut ctx);
                         }
                     }
                 }
+                ctx.tracker.end_update();
+                if let Some(recorder) = &mut ctx.recorder {
+                    recorder.end_update();
+                }
+                if let Some(playback) = &mut ctx.playback {
+                    playback.end_update();
+                    if playback.finished() {
+                        ctx.playback = None;
+                    }
+                }
             }
         })
     }
 }
 
+/// Pass an event to the app and its scripts
+fn dispatch_event<A>(event: Event, app: &mut A, ctx: &mut Context<A::Resources>)
+where
+    A: Kule,
+{
+    // Keep a locked cursor in place
+    if let Event::MouseAbsolute(pos) = event {
+        if let Err(e) = ctx.window.recenter_locked_cursor(pos) {
+            A::handle_error(e, app, ctx);
+        }
+    }
+    // Run app event method
+    if let Err(e) = A::event(event, app, ctx) {
+        A::handle_error(e, app, ctx);
+    }
+    // Run event scripts
+    #[cfg(feature = "script")]
+    if let Ok(scripts) = ctx.scripts() {
+        if let Err(e) = scripts.batch_call("event", move |lua, t, f| {
+            let mut ser = crate::LuaSerializer::new(lua);
+            let event = ser.serialize(&event)?;
+            f.call::<_, ()>((t, event))?;
+            Ok(())
+        }) {
+            A::handle_error(e, app, ctx);
+        }
+    }
+}
+
 /// Resource id types for an app
 pub trait Resources: Copy + Eq + Hash {
     /// The id used to identify fonts
@@ -205,12 +372,15 @@ pub trait Resources: Copy + Eq + Hash {
     type MeshId: ResourceId;
     /// The id used to identify sounds
     type SoundId: ResourceId;
+    /// The id used to identify custom shaders
+    type ShaderId: ResourceId;
 }
 
 impl Resources for () {
     type FontId = ();
     type MeshId = ();
     type SoundId = ();
+    type ShaderId = ();
 }
 
 /// An id for app resources
@@ -221,7 +391,8 @@ impl<T> ResourceId for T where T: Copy + Eq + Hash + Debug {}
 /**
 A generic resources type
 
-This type makes it easy to construct your own resources type
+This type makes it easy to construct your own resources type.
+The shader id type is optional and defaults to `()`.
 
 ```
 # use kule::*;
@@ -239,19 +410,22 @@ type MyRecs = GenericResources<FontId, MeshId, ()>;
 ```
 */
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct GenericResources<FontId, MeshId, SoundId>(
+pub struct GenericResources<FontId, MeshId, SoundId, ShaderId = ()>(
     PhantomData<FontId>,
     PhantomData<MeshId>,
     PhantomData<SoundId>,
+    PhantomData<ShaderId>,
 );
 
-impl<F, M, S> Resources for GenericResources<F, M, S>
+impl<F, M, S, Sh> Resources for GenericResources<F, M, S, Sh>
 where
     F: ResourceId,
     M: ResourceId,
     S: ResourceId,
+    Sh: ResourceId,
 {
     type FontId = F;
     type MeshId = M;
     type SoundId = S;
+    type ShaderId = Sh;
 }
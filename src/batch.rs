@@ -0,0 +1,270 @@
+use std::rc::Rc;
+
+use glium::{
+    backend::Facade,
+    draw_parameters,
+    index::{IndexBufferSlice, PrimitiveType},
+    vertex::VertexBufferSlice,
+    Blend, DrawParameters, IndexBuffer, Program, StencilOperation, StencilTest, VertexBuffer,
+};
+
+use crate::{BlendMode, Col, Texture, Trans, Uniform, Vertex};
+
+/// The stencil bit used for masks
+const MASK_BIT: u32 = 0x01;
+
+/// Which part of a mask can be drawn to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mask {
+    Inside,
+    Outside,
+}
+
+impl Mask {
+    /// Get the value of the mask bit where drawing is allowed
+    fn reference(self) -> u32 {
+        match self {
+            Mask::Inside => MASK_BIT,
+            Mask::Outside => 0,
+        }
+    }
+}
+
+/// How a batch uses the stencil buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StencilMode {
+    /// The stencil buffer is not used
+    Normal,
+    /// Write the shape of a mask
+    WriteMask,
+    /// Only draw where a mask allows
+    Masked(Mask),
+}
+
+/// Create stencil parameters that are the same for both faces
+fn stencil(
+    reference: u32,
+    test: StencilTest,
+    write_mask: u32,
+    operation: StencilOperation,
+) -> draw_parameters::Stencil {
+    draw_parameters::Stencil {
+        reference_value_clockwise: reference as i32,
+        reference_value_counter_clockwise: reference as i32,
+        test_clockwise: test,
+        test_counter_clockwise: test,
+        write_mask_clockwise: write_mask,
+        write_mask_counter_clockwise: write_mask,
+        depth_pass_operation_clockwise: operation,
+        depth_pass_operation_counter_clockwise: operation,
+        ..Default::default()
+    }
+}
+
+/// The uniforms that describe a single draw with a custom shader
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DrawUniforms {
+    pub transform: Trans,
+    pub color: Col,
+    pub tex_rect: [f32; 4],
+}
+
+/// The gpu state that all of the geometry in a batch shares
+#[derive(Clone)]
+pub(crate) struct BatchState<'ctx> {
+    pub program: &'ctx Program,
+    pub texture: Texture,
+    pub uniforms: Rc<Vec<(String, Uniform)>>,
+    pub blend_mode: BlendMode,
+    pub scissor: Option<glium::Rect>,
+    pub stencil: StencilMode,
+    pub params: DrawParameters<'ctx>,
+    /// Whether the draw parameters were set for a specific draw
+    ///
+    /// Draws with custom parameters are never batched with other draws
+    pub custom_params: bool,
+    /// The uniforms of a draw with a custom shader
+    ///
+    /// Custom shaders receive untransformed vertices, so these draws are never batched
+    pub draw_uniforms: Option<DrawUniforms>,
+}
+
+impl<'ctx> BatchState<'ctx> {
+    /// Check if geometry with another state can be added to a batch with this one
+    pub fn batches_with(&self, other: &Self) -> bool {
+        !self.custom_params
+            && !other.custom_params
+            && self.draw_uniforms.is_none()
+            && other.draw_uniforms.is_none()
+            && std::ptr::eq(self.program, other.program)
+            && self.texture == other.texture
+            && (Rc::ptr_eq(&self.uniforms, &other.uniforms) || self.uniforms == other.uniforms)
+            && self.blend_mode == other.blend_mode
+            && self.scissor == other.scissor
+            && self.stencil == other.stencil
+    }
+    /// Get the full draw parameters
+    pub fn draw_parameters(&self) -> DrawParameters<'ctx> {
+        let no_color = (false, false, false, false);
+        let (stencil, color_mask) = match self.stencil {
+            StencilMode::Normal => (self.params.stencil, self.params.color_mask),
+            StencilMode::WriteMask => (
+                stencil(
+                    MASK_BIT,
+                    StencilTest::AlwaysPass,
+                    MASK_BIT,
+                    StencilOperation::Replace,
+                ),
+                no_color,
+            ),
+            StencilMode::Masked(mask) => (
+                stencil(
+                    mask.reference(),
+                    StencilTest::IfEqual { mask: MASK_BIT },
+                    0,
+                    StencilOperation::Keep,
+                ),
+                self.params.color_mask,
+            ),
+        };
+        DrawParameters {
+            blend: Blend::from(self.blend_mode),
+            scissor: self.scissor,
+            stencil,
+            color_mask,
+            ..self.params.clone()
+        }
+    }
+}
+
+/**
+Geometry waiting to be drawn
+
+Consecutive draws that share the same state are accumulated
+here so that they can be drawn with a single draw call.
+*/
+#[derive(Default)]
+pub(crate) struct Batch<'ctx> {
+    pub state: Option<BatchState<'ctx>>,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl<'ctx> Batch<'ctx> {
+    /// Check if geometry with the given state can be added to the batch
+    pub fn accepts(&self, state: &BatchState<'ctx>) -> bool {
+        match &self.state {
+            Some(current) => current.batches_with(state),
+            None => true,
+        }
+    }
+    /// Remove all geometry and state from the batch, keeping its storage
+    pub fn clear(&mut self) {
+        self.state = None;
+        self.vertices.clear();
+        self.indices.clear();
+    }
+    /// Add geometry to the batch
+    ///
+    /// This does not check whether the batch accepts the state
+    pub fn push<I>(&mut self, state: BatchState<'ctx>, vertices: I, indices: &[u32])
+    where
+        I: IntoIterator<Item = Vertex>,
+    {
+        if self.state.is_none() {
+            self.state = Some(state);
+        }
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend(vertices);
+        self.indices.extend(indices.iter().map(|&i| i + offset));
+    }
+}
+
+/// The minimum number of elements in a stream buffer
+const MIN_STREAM_LEN: usize = 1024;
+
+/**
+Gpu buffers that batches are streamed into
+
+The buffers are kept between frames and only reallocated when a batch is
+too large to fit. Consecutive batches are written to consecutive parts of
+the buffers. When the end is reached, the buffers are orphaned so that
+writing does not have to wait for previous draws to finish.
+*/
+#[derive(Default)]
+pub(crate) struct StreamBuffers {
+    vertices: Option<VertexBuffer<Vertex>>,
+    indices: Option<IndexBuffer<u32>>,
+    vertex_offset: usize,
+    index_offset: usize,
+    /// The statistics of the current frame
+    pub stats: DrawStats,
+    /// The statistics of the last finished frame
+    pub last_stats: DrawStats,
+}
+
+/// Statistics about the rendering of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawStats {
+    /// The number of draw calls
+    pub draw_calls: usize,
+    /// The number of vertices drawn
+    pub vertices: usize,
+    /// The number of indices drawn
+    pub indices: usize,
+    /// The number of writes to gpu buffers
+    pub uploads: usize,
+    /// The number of gpu buffers that were created
+    pub allocations: usize,
+}
+
+impl StreamBuffers {
+    /// Write a batch's geometry and get the slices that contain it
+    pub fn write<F>(
+        &mut self,
+        facade: &F,
+        vertices: &[Vertex],
+        indices: &[u32],
+    ) -> (VertexBufferSlice<'_, Vertex>, IndexBufferSlice<'_, u32>)
+    where
+        F: Facade,
+    {
+        // Vertices
+        if !matches!(&self.vertices, Some(vb) if vb.len() >= vertices.len()) {
+            let len = vertices.len().next_power_of_two().max(MIN_STREAM_LEN);
+            self.vertices = Some(VertexBuffer::empty_dynamic(facade, len).unwrap());
+            self.vertex_offset = 0;
+            self.stats.allocations += 1;
+        }
+        let vb = self.vertices.as_ref().unwrap();
+        if self.vertex_offset + vertices.len() > vb.len() {
+            vb.invalidate();
+            self.vertex_offset = 0;
+        }
+        let vertex_range = self.vertex_offset..self.vertex_offset + vertices.len();
+        self.vertex_offset = vertex_range.end;
+        // Indices
+        if !matches!(&self.indices, Some(ib) if ib.len() >= indices.len()) {
+            let len = indices.len().next_power_of_two().max(MIN_STREAM_LEN);
+            self.indices = Some(
+                IndexBuffer::empty_dynamic(facade, PrimitiveType::TrianglesList, len).unwrap(),
+            );
+            self.index_offset = 0;
+            self.stats.allocations += 1;
+        }
+        let ib = self.indices.as_ref().unwrap();
+        if self.index_offset + indices.len() > ib.len() {
+            ib.invalidate();
+            self.index_offset = 0;
+        }
+        let index_range = self.index_offset..self.index_offset + indices.len();
+        self.index_offset = index_range.end;
+        // Write
+        let vertex_slice = vb.slice(vertex_range).unwrap();
+        vertex_slice.write(vertices);
+        let index_slice = ib.slice(index_range).unwrap();
+        index_slice.write(indices);
+        self.stats.uploads += 2;
+        (vertex_slice, index_slice)
+    }
+}
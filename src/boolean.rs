@@ -0,0 +1,301 @@
+use vector2math::*;
+
+use crate::Vec2;
+
+/// A boolean operation that combines two polygons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BooleanOp {
+    /// The area that is in either polygon
+    Union,
+    /// The area that is in both polygons
+    Intersection,
+    /// The area that is in the first polygon but not the second
+    Difference,
+}
+
+/// A vertex of a polygon in the clipping process
+#[derive(Debug, Clone, Copy)]
+struct ClipVertex {
+    pos: Vec2,
+    /// The index of the same intersection in the other polygon, if this is an intersection
+    neighbor: Option<usize>,
+    /// Whether following the polygon forward from this intersection enters the other polygon
+    entry: bool,
+    visited: bool,
+}
+
+impl ClipVertex {
+    fn new(pos: Vec2, neighbor: Option<usize>) -> Self {
+        ClipVertex {
+            pos,
+            neighbor,
+            entry: false,
+            visited: false,
+        }
+    }
+}
+
+/// Check if a point is inside a polygon
+fn point_in_polygon(p: Vec2, polygon: &[Vec2]) -> bool {
+    let mut inside = false;
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        if (a[1] > p[1]) != (b[1] > p[1])
+            && p[0] < a[0] + (p[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0])
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Get the positions along two segments at which they intersect, if they do
+fn segment_intersection(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> Option<[f32; 2]> {
+    let da = a2.sub(a1);
+    let db = b2.sub(b1);
+    let denom = da[0] * db[1] - da[1] * db[0];
+    if denom == 0.0 {
+        return None;
+    }
+    let diff = b1.sub(a1);
+    let ta = (diff[0] * db[1] - diff[1] * db[0]) / denom;
+    let tb = (diff[0] * da[1] - diff[1] * da[0]) / denom;
+    if ta > 0.0 && ta < 1.0 && tb > 0.0 && tb < 1.0 {
+        Some([ta, tb])
+    } else {
+        None
+    }
+}
+
+/// Build a polygon's vertex list with intersections inserted along its edges
+///
+/// `intersections` holds each intersection's edge index, position along the edge, and id
+fn build_ring(
+    polygon: &[Vec2],
+    mut intersections: Vec<(usize, f32, usize)>,
+) -> Vec<(Vec2, Option<usize>)> {
+    intersections.sort_by(|a, b| (a.0, a.1).partial_cmp(&(b.0, b.1)).unwrap());
+    let mut ring = Vec::with_capacity(polygon.len() + intersections.len());
+    let mut intersections = intersections.into_iter().peekable();
+    for (i, &pos) in polygon.iter().enumerate() {
+        ring.push((pos, None));
+        while let Some(&(edge, t, id)) = intersections.peek() {
+            if edge != i {
+                break;
+            }
+            let next = polygon[(i + 1) % polygon.len()];
+            ring.push((pos.lerp(next, t), Some(id)));
+            intersections.next();
+        }
+    }
+    ring
+}
+
+/**
+Combine two simple polygons with a boolean operation
+
+The result is a list of contours. If the result has holes, such as when
+a smaller polygon is subtracted from the middle of a larger one, the holes
+are included as contours as well. Fill the result with the
+[`FillRule::EvenOdd`](enum.FillRule.html) rule using
+[`Path::polygons`](struct.Path.html#method.polygons) to draw it correctly.
+
+Polygons that intersect themselves are not supported. Polygons that share
+vertices or have overlapping edges may give imprecise results.
+*/
+pub fn polygon_boolean(op: BooleanOp, a: &[Vec2], b: &[Vec2]) -> Vec<Vec<Vec2>> {
+    if a.len() < 3 || b.len() < 3 {
+        return match op {
+            BooleanOp::Union => vec![a, b]
+                .into_iter()
+                .filter(|p| p.len() >= 3)
+                .map(|p| p.to_vec())
+                .collect(),
+            BooleanOp::Intersection => Vec::new(),
+            BooleanOp::Difference if a.len() >= 3 => vec![a.to_vec()],
+            BooleanOp::Difference => Vec::new(),
+        };
+    }
+    // Find intersections
+    let mut a_intersections = Vec::new();
+    let mut b_intersections = Vec::new();
+    let mut id = 0;
+    for i in 0..a.len() {
+        let (a1, a2) = (a[i], a[(i + 1) % a.len()]);
+        for j in 0..b.len() {
+            let (b1, b2) = (b[j], b[(j + 1) % b.len()]);
+            if let Some([ta, tb]) = segment_intersection(a1, a2, b1, b2) {
+                a_intersections.push((i, ta, id));
+                b_intersections.push((j, tb, id));
+                id += 1;
+            }
+        }
+    }
+    // Handle polygons that do not intersect
+    if id == 0 {
+        let a_in_b = point_in_polygon(a[0], b);
+        let b_in_a = point_in_polygon(b[0], a);
+        let reversed = |p: &[Vec2]| p.iter().rev().copied().collect::<Vec<_>>();
+        return match op {
+            BooleanOp::Union if a_in_b => vec![b.to_vec()],
+            BooleanOp::Union if b_in_a => vec![a.to_vec()],
+            BooleanOp::Union => vec![a.to_vec(), b.to_vec()],
+            BooleanOp::Intersection if a_in_b => vec![a.to_vec()],
+            BooleanOp::Intersection if b_in_a => vec![b.to_vec()],
+            BooleanOp::Intersection => Vec::new(),
+            BooleanOp::Difference if a_in_b => Vec::new(),
+            BooleanOp::Difference if b_in_a => vec![a.to_vec(), reversed(b)],
+            BooleanOp::Difference => vec![a.to_vec()],
+        };
+    }
+    // Build the rings and link their intersections
+    let a_ring = build_ring(a, a_intersections);
+    let b_ring = build_ring(b, b_intersections);
+    let mut b_index_of_id = vec![0; id];
+    let mut a_index_of_id = vec![0; id];
+    for (k, (_, id)) in b_ring.iter().enumerate() {
+        if let Some(id) = id {
+            b_index_of_id[*id] = k;
+        }
+    }
+    for (k, (_, id)) in a_ring.iter().enumerate() {
+        if let Some(id) = id {
+            a_index_of_id[*id] = k;
+        }
+    }
+    let mut rings = [
+        a_ring
+            .iter()
+            .map(|&(pos, id)| ClipVertex::new(pos, id.map(|id| b_index_of_id[id])))
+            .collect::<Vec<_>>(),
+        b_ring
+            .iter()
+            .map(|&(pos, id)| ClipVertex::new(pos, id.map(|id| a_index_of_id[id])))
+            .collect::<Vec<_>>(),
+    ];
+    // Mark entries and exits
+    let (a_forward, b_forward) = match op {
+        BooleanOp::Union => (false, false),
+        BooleanOp::Intersection => (true, true),
+        BooleanOp::Difference => (false, true),
+    };
+    for (ring, (forward, other)) in rings.iter_mut().zip(vec![(a_forward, b), (b_forward, a)]) {
+        let mut entry = forward ^ point_in_polygon(ring[0].pos, other);
+        for vertex in ring.iter_mut().filter(|v| v.neighbor.is_some()) {
+            vertex.entry = entry;
+            entry = !entry;
+        }
+    }
+    // Trace the resulting contours
+    let mut contours = Vec::new();
+    while let Some(start) = rings[0]
+        .iter()
+        .position(|v| v.neighbor.is_some() && !v.visited)
+    {
+        let mut contour = vec![rings[0][start].pos];
+        let (mut r, mut k) = (0, start);
+        loop {
+            let neighbor = rings[r][k].neighbor.unwrap();
+            rings[r][k].visited = true;
+            rings[1 - r][neighbor].visited = true;
+            let forward = rings[r][k].entry;
+            let len = rings[r].len();
+            loop {
+                k = if forward {
+                    (k + 1) % len
+                } else {
+                    (k + len - 1) % len
+                };
+                contour.push(rings[r][k].pos);
+                if rings[r][k].neighbor.is_some() {
+                    break;
+                }
+            }
+            k = rings[r][k].neighbor.unwrap();
+            r = 1 - r;
+            if rings[r][k].visited {
+                break;
+            }
+        }
+        if contour.len() > 1 && contour.first() == contour.last() {
+            contour.pop();
+        }
+        contours.push(contour);
+    }
+    contours
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn square(left: f32, top: f32, size: f32) -> Vec<Vec2> {
+        vec![
+            [left, top],
+            [left + size, top],
+            [left + size, top + size],
+            [left, top + size],
+        ]
+    }
+
+    /// The signed area of a contour
+    fn area(contour: &[Vec2]) -> f32 {
+        (0..contour.len())
+            .map(|i| {
+                let [x1, y1] = contour[i];
+                let [x2, y2] = contour[(i + 1) % contour.len()];
+                x1 * y2 - x2 * y1
+            })
+            .sum::<f32>()
+            / 2.0
+    }
+
+    fn areas(op: BooleanOp, a: &[Vec2], b: &[Vec2]) -> Vec<f32> {
+        polygon_boolean(op, a, b)
+            .iter()
+            .map(|contour| area(contour).abs())
+            .collect()
+    }
+
+    #[test]
+    fn overlapping() {
+        let a = square(0.0, 0.0, 2.0);
+        let b = square(1.0, 1.0, 2.0);
+        assert_eq!(areas(BooleanOp::Union, &a, &b), vec![7.0]);
+        assert_eq!(areas(BooleanOp::Intersection, &a, &b), vec![1.0]);
+        assert_eq!(areas(BooleanOp::Difference, &a, &b), vec![3.0]);
+        assert_eq!(areas(BooleanOp::Difference, &b, &a), vec![3.0]);
+    }
+
+    #[test]
+    fn contained() {
+        let outer = square(0.0, 0.0, 3.0);
+        let inner = square(1.0, 1.0, 1.0);
+        assert_eq!(areas(BooleanOp::Union, &outer, &inner), vec![9.0]);
+        assert_eq!(areas(BooleanOp::Union, &inner, &outer), vec![9.0]);
+        assert_eq!(areas(BooleanOp::Intersection, &outer, &inner), vec![1.0]);
+        assert_eq!(areas(BooleanOp::Intersection, &inner, &outer), vec![1.0]);
+        assert!(areas(BooleanOp::Difference, &inner, &outer).is_empty());
+    }
+
+    #[test]
+    fn disjoint() {
+        let a = square(0.0, 0.0, 1.0);
+        let b = square(2.0, 0.0, 1.0);
+        assert_eq!(areas(BooleanOp::Union, &a, &b), vec![1.0, 1.0]);
+        assert!(areas(BooleanOp::Intersection, &a, &b).is_empty());
+        assert_eq!(polygon_boolean(BooleanOp::Difference, &a, &b), vec![a]);
+    }
+
+    #[test]
+    fn hole() {
+        let outer = square(0.0, 0.0, 3.0);
+        let inner = square(1.0, 1.0, 1.0);
+        let contours = polygon_boolean(BooleanOp::Difference, &outer, &inner);
+        assert_eq!(contours.len(), 2);
+        assert_eq!(contours[0], outer);
+        // The hole winds the other way
+        assert_eq!(area(&contours[1]), -1.0);
+        assert!(contours[1].iter().all(|v| inner.contains(v)));
+    }
+}
@@ -1,4 +1,4 @@
-use vector2math::*;
+use vector2math::{f32::Vec2, *};
 
 /// The standard color type
 pub type Col = [f32; 4];
@@ -275,3 +275,101 @@ impl Color for (f32, f32, f32) {
         1.0
     }
 }
+
+/**
+A color gradient defined by a list of color stops
+
+Each stop is a position paired with a color. Stops are kept sorted by position.
+Sampling the gradient between two stops linearly interpolates their colors.
+*/
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gradient {
+    stops: Vec<(f32, Col)>,
+}
+
+impl Gradient {
+    /// Create a new `Gradient` that goes from one color at `0.0` to another at `1.0`
+    pub fn new<A, B>(start: A, end: B) -> Self
+    where
+        A: Color,
+        B: Color,
+    {
+        Gradient::default().stop(0.0, start).stop(1.0, end)
+    }
+    /// Add a color stop
+    pub fn stop<C>(mut self, t: f32, color: C) -> Self
+    where
+        C: Color,
+    {
+        let i = self.stops.iter().take_while(|(s, _)| *s <= t).count();
+        self.stops.insert(i, (t, color.map()));
+        self
+    }
+    /// Get the color stops
+    pub fn stops(&self) -> &[(f32, Col)] {
+        &self.stops
+    }
+    /// Get the color at some position along the gradient
+    ///
+    /// Positions outside the range of the stops take the color of the nearest stop.
+    /// A gradient with no stops is transparent.
+    pub fn sample(&self, t: f32) -> Col {
+        let after = self.stops.iter().position(|(s, _)| *s > t);
+        match after {
+            Some(0) => self.stops[0].1,
+            Some(i) => {
+                let (s0, c0) = self.stops[i - 1];
+                let (s1, c1) = self.stops[i];
+                c0.lerp(c1, (t - s0) / (s1 - s0))
+            }
+            None => self
+                .stops
+                .last()
+                .map(|(_, c)| *c)
+                .unwrap_or_else(|| Col::black().with_alpha(0.0)),
+        }
+    }
+}
+
+/// The shape of a gradient fill
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientShape {
+    /// Colors change along the line from `start` to `end`
+    Linear {
+        /// The point at position `0.0`
+        start: Vec2,
+        /// The point at position `1.0`
+        end: Vec2,
+    },
+    /// Colors change with the distance from `center`
+    Radial {
+        /// The point at position `0.0`
+        center: Vec2,
+        /// The distance from the center at position `1.0`
+        radius: f32,
+    },
+}
+
+impl GradientShape {
+    /// Get the position along the gradient of a point
+    pub fn position(self, point: Vec2) -> f32 {
+        match self {
+            GradientShape::Linear { start, end } => {
+                let dir = end.sub(start);
+                let mag2 = dir.dot(dir);
+                if mag2 == 0.0 {
+                    0.0
+                } else {
+                    point.sub(start).dot(dir) / mag2
+                }
+            }
+            GradientShape::Radial { center, radius } => {
+                if radius == 0.0 {
+                    0.0
+                } else {
+                    point.dist(center) / radius
+                }
+            }
+        }
+    }
+}
@@ -1,28 +1,184 @@
-use std::{cell::Ref, time::Instant};
+use std::{
+    any::Any,
+    cell::{Cell, Ref, RefCell},
+    fmt,
+    rc::Rc,
+    time::Instant,
+};
 
-use glium::{glutin::*, *};
+use glium::{backend::Facade, glutin::*, *};
 use vector2math::*;
 
 pub use monitor::MonitorHandle;
-pub use window::{Fullscreen, WindowId};
+pub use window::{CursorIcon, Fullscreen, WindowId};
+
+use event_loop::EventLoopProxy;
 
+use crate::{
+    batch::StreamBuffers,
+    post::BloomPass,
+    recording::{Playback, Recorder},
+    BlendMode, Bloom, Camera, CanFail, Col, Color, DrawStats, Drawer, FontHandle, Fonts,
+    GlyphCache, KuleError, KuleResult, MeshCache, RenderTarget, Resources, Shaders, StateTracker,
+    Texture, TextureCanvas, TextureOptions, Trans, Vec2, WindowCanvas,
+};
 #[cfg(feature = "sound")]
 use crate::{
     rodio::{Sample, Source},
     Kule, Mixer, SoundSource, Sounds,
 };
-use crate::{
-    Camera, CanFail, Drawer, Fonts, GlyphCache, KuleResult, MeshCache, Resources, StateTracker,
-    Vec2, WindowCanvas,
-};
+
+/// A value sent to the app with an [`EventSender`](struct.EventSender.html)
+pub type CustomEvent = Box<dyn Any + Send>;
+
+/**
+Sends custom events to the app from any thread
+
+Sending an event wakes the event loop, and the event is passed to
+[`Kule::custom_event`](trait.Kule.html#method.custom_event). This is useful
+for background threads that do things like networking or loading assets.
+
+Get one with [`Context::event_sender`](struct.Context.html#method.event_sender).
+*/
+#[derive(Clone)]
+pub struct EventSender(EventLoopProxy<CustomEvent>);
+
+impl EventSender {
+    /// Send a custom event to the app
+    pub fn send<T>(&self, event: T) -> KuleResult<()>
+    where
+        T: Any + Send,
+    {
+        self.0
+            .send_event(Box::new(event))
+            .map_err(|_| KuleError::EventLoopClosed)
+    }
+}
+
+impl fmt::Debug for EventSender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EventSender")
+    }
+}
 
 /// A handle to the app's window
-pub struct Window(pub(crate) Display);
+pub struct Window {
+    pub(crate) display: Display,
+    cursor_visible: Cell<bool>,
+    cursor_grabbed: Cell<bool>,
+    cursor_locked: Cell<bool>,
+    cursor_icon: Cell<CursorIcon>,
+    cursor_image: RefCell<Option<CursorImage>>,
+    logical_pixels: bool,
+    #[cfg(feature = "clipboard")]
+    clipboard: RefCell<Option<arboard::Clipboard>>,
+}
+
+/**
+An image that is drawn in place of the system cursor
+
+The hotspot is the point in the image, in pixels from its top-left corner,
+that is placed at the mouse's position.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorImage {
+    /// The image's texture
+    pub texture: Texture,
+    /// The point in the image that is placed at the mouse's position
+    pub hotspot: Vec2,
+}
+
+impl CursorImage {
+    /// Create a new cursor image from a texture and a hotspot
+    pub fn new(texture: Texture, hotspot: Vec2) -> Self {
+        CursorImage { texture, hotspot }
+    }
+    /// Create a new cursor image from RGBA bytes and a hotspot
+    pub fn from_rgba(
+        window: &Window,
+        width: u32,
+        height: u32,
+        bytes: &[u8],
+        hotspot: Vec2,
+    ) -> KuleResult<Self> {
+        Ok(CursorImage::new(
+            Texture::from_rgba_with(window, width, height, bytes, TextureOptions::pixelated())?,
+            hotspot,
+        ))
+    }
+}
+
+#[cfg(feature = "clipboard")]
+/**
+A handle to the system clipboard
+
+Get one with [`Window::clipboard`](struct.Window.html#method.clipboard).
+*/
+pub struct Clipboard<'a>(&'a RefCell<Option<arboard::Clipboard>>);
+
+#[cfg(feature = "clipboard")]
+impl<'a> Clipboard<'a> {
+    fn with<F, T>(&self, f: F) -> KuleResult<T>
+    where
+        F: FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+    {
+        let mut clipboard = self.0.borrow_mut();
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new()?);
+        }
+        Ok(f(clipboard.as_mut().unwrap())?)
+    }
+    /// Get the text on the clipboard
+    pub fn get_text(&self) -> KuleResult<String> {
+        self.with(|clipboard| clipboard.get_text())
+    }
+    /// Put text on the clipboard
+    pub fn set_text<S>(&self, text: S) -> KuleResult<()>
+    where
+        S: Into<String>,
+    {
+        self.with(|clipboard| clipboard.set_text(text.into()))
+    }
+}
 
 impl Window {
+    pub(crate) fn new(display: Display, logical_pixels: bool) -> Self {
+        Window {
+            display,
+            cursor_visible: Cell::new(true),
+            cursor_grabbed: Cell::new(false),
+            cursor_locked: Cell::new(false),
+            cursor_icon: Cell::new(CursorIcon::Default),
+            cursor_image: RefCell::new(None),
+            logical_pixels,
+            #[cfg(feature = "clipboard")]
+            clipboard: RefCell::new(None),
+        }
+    }
+    /**
+    Get the window's scale factor
+
+    This is the number of physical pixels per logical pixel.
+    It is greater than `1.0` on high DPI displays.
+    */
+    pub fn scale_factor(&self) -> f32 {
+        self.inner().scale_factor() as f32
+    }
+    /// Get whether window space is measured in logical pixels
+    pub fn logical_pixels(&self) -> bool {
+        self.logical_pixels
+    }
+    /// Get the number of physical pixels per unit of window space
+    pub(crate) fn window_space_scale(&self) -> f32 {
+        if self.logical_pixels {
+            self.scale_factor()
+        } else {
+            1.0
+        }
+    }
     /// Get a reference to the inner window
     pub fn inner(&self) -> Ref<window::Window> {
-        Ref::map(self.0.gl_window(), |gl_window| gl_window.window())
+        Ref::map(self.display.gl_window(), |gl_window| gl_window.window())
     }
     /// Get the position of the window
     pub fn position(&self) -> [i32; 2] {
@@ -54,11 +210,151 @@ impl Window {
     }
     /// Get whether the cursor should be visible
     pub fn cursor_visible(&self) -> bool {
-        todo!()
+        self.cursor_visible.get()
     }
     /// Set whether the cursor should be visible
     pub fn set_cursor_visible(&self, visible: bool) {
-        self.inner().set_cursor_visible(visible);
+        self.cursor_visible.set(visible);
+        self.update_system_cursor();
+    }
+    /// Show the system cursor only if it is visible, unlocked, and not replaced by an image
+    fn update_system_cursor(&self) {
+        self.inner().set_cursor_visible(
+            self.cursor_visible.get()
+                && !self.cursor_locked.get()
+                && self.cursor_image.borrow().is_none(),
+        );
+    }
+    /// Get the system cursor's icon
+    pub fn cursor(&self) -> CursorIcon {
+        self.cursor_icon.get()
+    }
+    /**
+    Set the system cursor's icon
+
+    Use this to signal what the mouse is hovering over,
+    like `CursorIcon::Hand` for buttons or `CursorIcon::Text` for text boxes.
+    This removes the cursor image if there is one.
+    */
+    pub fn set_cursor(&self, icon: CursorIcon) {
+        if self.cursor_icon.replace(icon) != icon {
+            self.inner().set_cursor_icon(icon);
+        }
+        if self.cursor_image.borrow().is_some() {
+            self.set_cursor_image(None);
+        }
+    }
+    /// Get the image that is drawn in place of the system cursor
+    pub fn cursor_image(&self) -> Option<CursorImage> {
+        self.cursor_image.borrow().clone()
+    }
+    /**
+    Set the image that is drawn in place of the system cursor
+
+    The image is drawn on top of everything else at the end of each frame,
+    and the system cursor is hidden while it is set. Set it to `None`
+    to go back to the system cursor.
+    */
+    pub fn set_cursor_image(&self, image: Option<CursorImage>) {
+        *self.cursor_image.borrow_mut() = image;
+        self.update_system_cursor();
+    }
+    /// Get the cursor image and its rectangle if it should be drawn
+    fn cursor_image_rect(&self, mouse_pos: Vec2) -> Option<(Texture, [f32; 4])> {
+        if !self.cursor_visible.get() || self.cursor_locked.get() {
+            return None;
+        }
+        let image = self.cursor_image.borrow();
+        let image = image.as_ref()?;
+        let size: Vec2 = image.texture.size().map_with(|d| d as f32);
+        let pos = mouse_pos.sub(image.hotspot);
+        Some((image.texture.clone(), [pos[0], pos[1], size[0], size[1]]))
+    }
+    /// Get whether the cursor is confined to the window
+    pub fn cursor_grabbed(&self) -> bool {
+        self.cursor_grabbed.get()
+    }
+    /**
+    Set whether the cursor is confined to the window
+
+    A grabbed cursor cannot leave the window. Use `Event::MouseDelta`
+    to get the mouse's motion while it is grabbed.
+    */
+    pub fn set_cursor_grab(&self, grab: bool) -> KuleResult<()> {
+        if !self.cursor_locked.get() {
+            self.inner().set_cursor_grab(grab)?;
+        }
+        self.cursor_grabbed.set(grab);
+        Ok(())
+    }
+    /// Get whether the cursor is locked in place
+    pub fn cursor_locked(&self) -> bool {
+        self.cursor_locked.get()
+    }
+    /**
+    Set whether the cursor is locked in place
+
+    A locked cursor is hidden, confined to the window, and moved back to
+    the center of the window whenever it moves. This is useful for first
+    person cameras. Use `Event::MouseDelta` to get the mouse's motion while
+    it is locked, because `Event::MouseRelative` includes the jumps back
+    to the center.
+
+    When the cursor is unlocked, its visibility and grab go back to what
+    they were set to.
+    */
+    pub fn set_cursor_locked(&self, locked: bool) -> KuleResult<()> {
+        self.inner()
+            .set_cursor_grab(locked || self.cursor_grabbed.get())?;
+        self.cursor_locked.set(locked);
+        self.update_system_cursor();
+        if locked {
+            self.set_cursor_position(self.center())?;
+        }
+        Ok(())
+    }
+    /// Move the cursor to a position in window space
+    pub fn set_cursor_position(&self, pos: Vec2) -> KuleResult<()> {
+        let pos = pos.mul(self.window_space_scale());
+        self.inner()
+            .set_cursor_position(dpi::PhysicalPosition::<f32>::from(pos))?;
+        Ok(())
+    }
+    /// Get the center of the window in window space
+    fn center(&self) -> Vec2 {
+        let size: Vec2 = self.size().map_with(|d| d as f32);
+        size.div(2.0 * self.window_space_scale())
+    }
+    /// Move a locked cursor back to the center of the window if it has moved
+    pub(crate) fn recenter_locked_cursor(&self, pos: Vec2) -> KuleResult<()> {
+        let center = self.center();
+        if self.cursor_locked.get() && pos.dist(center) >= 1.0 {
+            self.set_cursor_position(center)?;
+        }
+        Ok(())
+    }
+    /**
+    Set the position of the input method's candidate window in window space
+
+    This should usually be set to the position of a text box's caret.
+    */
+    pub fn set_ime_position(&self, pos: Vec2) {
+        let pos = pos.mul(self.window_space_scale());
+        self.inner()
+            .set_ime_position(dpi::PhysicalPosition::<f32>::from(pos));
+    }
+    #[cfg(feature = "clipboard")]
+    /**
+    Get a handle to the system clipboard
+
+    The clipboard is opened the first time it is used.
+    */
+    pub fn clipboard(&self) -> Clipboard<'_> {
+        Clipboard(&self.clipboard)
+    }
+    /// Create an offscreen render target with the given size in pixels
+    pub fn render_target(&self, size: [u32; 2]) -> KuleResult<RenderTarget> {
+        RenderTarget::new(self, size)
     }
     /// Set the window icon using bitmap data
     pub fn set_icon(&self, rgba: Vec<u8>, width: u32, height: u32) -> KuleResult<()> {
@@ -68,6 +364,12 @@ impl Window {
     }
 }
 
+impl Facade for Window {
+    fn get_context(&self) -> &Rc<backend::Context> {
+        self.display.get_context()
+    }
+}
+
 /// Holds the state of the engine
 pub struct Context<R = ()>
 where
@@ -75,6 +377,8 @@ where
 {
     /// The main shader to use for drawing
     pub program: Program,
+    /// The shader used to draw signed distance field text
+    pub(crate) sdf_program: Program,
     /// Tracks the state of various inputs
     pub tracker: StateTracker,
     /// The scene camera
@@ -85,6 +389,8 @@ where
     pub fonts: Fonts<R::FontId>,
     /// The mesh cache
     pub meshes: MeshCache<R>,
+    /// The custom shader cache
+    pub shaders: Shaders<R::ShaderId>,
     #[cfg(feature = "sound")]
     /// The audio mixer
     pub mixer: Mixer,
@@ -94,38 +400,251 @@ where
     #[cfg(feature = "script")]
     /// The scripting environment
     pub scripts: KuleResult<crate::Scripts>,
+    #[cfg(feature = "gamepad")]
+    /// The settings for processing gamepad axes
+    pub gamepad_settings: crate::GamepadSettings,
+    #[cfg(feature = "gamepad")]
+    /// The gamepad context, or `None` if gamepads are not supported
+    pub(crate) gilrs: Option<gilrs::Gilrs>,
+    #[cfg(feature = "gamepad")]
+    /// Rumble effects that are playing and when they end
+    pub(crate) rumbles: Vec<(gilrs::ff::Effect, Instant)>,
     /// Whether the window should close
     pub should_close: bool,
+    pub(crate) blank_texture: Texture,
+    pub(crate) low_res: Option<RenderTarget>,
+    pub(crate) scene_target: RefCell<Option<RenderTarget>>,
+    pub(crate) bloom: Option<BloomPass>,
+    pub(crate) persistent: bool,
+    pub(crate) buffers: RefCell<StreamBuffers>,
+    pub(crate) srgb: bool,
+    pub(crate) start_time: Instant,
     pub(crate) update_timer: Instant,
     pub(crate) fps_timer: Instant,
+    pub(crate) recorder: Option<Recorder>,
+    pub(crate) playback: Option<Playback>,
+    pub(crate) event_proxy: EventLoopProxy<CustomEvent>,
 }
 
 impl<R> Context<R>
 where
     R: Resources,
 {
+    /**
+    Get statistics about the rendering of the last frame
+
+    This includes everything drawn during the frame, including draws to render targets.
+    */
+    pub fn draw_stats(&self) -> DrawStats {
+        self.buffers.borrow().last_stats
+    }
+    /// Get a sender that can send custom events to the app from any thread
+    pub fn event_sender(&self) -> EventSender {
+        EventSender(self.event_proxy.clone())
+    }
     /// Get the world coordinates of the mouse cursor
     pub fn mouse_coords(&self) -> Vec2 {
-        self.camera.pos_to_coords(self.tracker.mouse_pos())
+        match self.low_res_viewport() {
+            Some((size, scale, offset)) => Camera {
+                window_size: size,
+                scale: 1.0,
+                ..self.camera
+            }
+            .pos_to_coords(self.tracker.mouse_pos().sub(offset).div(scale)),
+            None => self.camera.pos_to_coords(self.tracker.mouse_pos()),
+        }
+    }
+    /**
+    Get the size of the low resolution target, the scale it is drawn at,
+    and the window position of its top-left corner
+
+    The scale and position are found in physical pixels, so that each target
+    pixel covers a whole number of physical pixels, and then converted to window space.
+    This is `None` if the app does not render at a low resolution.
+    */
+    fn low_res_viewport(&self) -> Option<(Vec2, f32, Vec2)> {
+        let size: Vec2 = self.low_res.as_ref()?.size().map_with(|d| d as f32);
+        let physical_size: Vec2 = self.camera.window_size;
+        let scale = (physical_size[0] / size[0])
+            .min(physical_size[1] / size[1])
+            .floor()
+            .max(1.0);
+        let offset: Vec2 = physical_size
+            .sub(size.mul(scale))
+            .div(2.0)
+            .map_with(f32::floor);
+        Some((
+            size,
+            scale / self.camera.scale,
+            offset.div(self.camera.scale),
+        ))
+    }
+    /// Whether the scene is drawn to an offscreen target before being drawn to the window
+    pub(crate) fn draws_offscreen(&self) -> bool {
+        self.low_res.is_some() || self.bloom.is_some() || self.persistent
+    }
+    /**
+    Draw the scene to an offscreen target, apply post effects,
+    then draw the result to the window
+
+    The target is the low resolution target if there is one.
+    Otherwise, it is a target the size of the window.
+    */
+    pub(crate) fn draw_offscreen<F>(&self, f: F) -> CanFail
+    where
+        F: FnOnce(&mut Drawer<TextureCanvas, R>) -> CanFail,
+    {
+        let (size, scale, offset) =
+            self.low_res_viewport()
+                .unwrap_or((self.camera.window_size(), 1.0, [0.0; 2]));
+        let window_target;
+        let (target, camera) = if let Some(target) = &self.low_res {
+            let camera = Camera {
+                window_size: size,
+                scale: 1.0,
+                ..self.camera
+            };
+            (target, camera)
+        } else {
+            let target_size = self.camera.window_size.map_with(|d| (d as u32).max(1));
+            let mut scene_target = self.scene_target.borrow_mut();
+            if !matches!(&*scene_target, Some(target) if target.size() == target_size) {
+                *scene_target = Some(RenderTarget::new(&self.window, target_size)?);
+            }
+            drop(scene_target);
+            window_target = Ref::map(self.scene_target.borrow(), |target| {
+                target.as_ref().unwrap()
+            });
+            (&*window_target, self.camera)
+        };
+        let res = self.draw_to_with(target, camera, &self.program, f);
+        let glow = if let Some(bloom) = &self.bloom {
+            Some((bloom.apply(self, target)?, bloom.config.intensity))
+        } else {
+            None
+        };
+        let texture = target.texture().with_options(TextureOptions::pixelated());
+        let rect = [offset[0], offset[1], size[0] * scale, size[1] * scale];
+        self.draw(|drawer| {
+            drawer.clear(Col::black());
+            drawer.with_absolute_camera(|drawer| {
+                drawer.image(&texture, rect);
+                if let Some((glow, intensity)) = &glow {
+                    drawer.with_blend_mode(BlendMode::Add, |drawer| {
+                        drawer.image(glow, rect).color(Col::gray(*intensity));
+                    });
+                }
+            });
+            Ok(())
+        })?;
+        res
     }
     pub(crate) fn draw<F>(&self, mut f: F) -> CanFail
     where
         F: FnMut(&mut Drawer<WindowCanvas, R>) -> CanFail,
     {
-        let mut frame = self.window.0.draw();
+        let mut frame = self.window.display.draw();
         let mut drawer = Drawer::new(
             &mut frame,
-            &self.window.0,
+            &self.window.display,
             &self.program,
+            &self.sdf_program,
+            &self.blank_texture,
             &self.fonts,
             &self.meshes,
+            &self.shaders,
+            &self.buffers,
             self.camera,
+            (Instant::now() - self.start_time).as_secs_f32(),
         );
-        f(&mut drawer)?;
+        drawer.srgb = self.srgb;
+        let res = f(&mut drawer);
+        if let Some((texture, rect)) = self.window.cursor_image_rect(self.tracker.mouse_pos()) {
+            drawer.with_absolute_camera(|drawer| {
+                drawer.image(&texture, rect);
+            });
+        }
         // #[cfg(feature = "script")]
         // if let Ok(scripts) = self.scripts() {}
+        drop(drawer);
         frame.finish().unwrap();
-        Ok(())
+        let mut buffers = self.buffers.borrow_mut();
+        buffers.last_stats = std::mem::take(&mut buffers.stats);
+        res
+    }
+    /**
+    Draw to an offscreen render target
+
+    The drawer's camera is set up so that world space matches the
+    target's pixel coordinates. The target is not cleared beforehand.
+    */
+    pub fn draw_to<F>(&self, target: &RenderTarget, f: F) -> CanFail
+    where
+        F: FnOnce(&mut Drawer<TextureCanvas, R>) -> CanFail,
+    {
+        let size: Vec2 = target.size().map_with(|d| d as f32);
+        let camera = Camera {
+            center: size.div(2.0),
+            zoom: 1.0,
+            projection: Trans::identity(),
+            window_size: size,
+            scale: 1.0,
+        };
+        self.draw_to_with(target, camera, &self.program, f)
+    }
+    /// Draw to an offscreen render target with a specific default program
+    pub(crate) fn draw_to_with_program<F>(
+        &self,
+        target: &RenderTarget,
+        program: &Program,
+        f: F,
+    ) -> CanFail
+    where
+        F: FnOnce(&mut Drawer<TextureCanvas, R>) -> CanFail,
+    {
+        let size: Vec2 = target.size().map_with(|d| d as f32);
+        let camera = Camera {
+            center: size.div(2.0),
+            zoom: 1.0,
+            projection: Trans::identity(),
+            window_size: size,
+            scale: 1.0,
+        };
+        self.draw_to_with(target, camera, program, f)
+    }
+    /// Draw to an offscreen render target with a specific camera and default program
+    fn draw_to_with<F>(
+        &self,
+        target: &RenderTarget,
+        camera: Camera,
+        program: &Program,
+        f: F,
+    ) -> CanFail
+    where
+        F: FnOnce(&mut Drawer<TextureCanvas, R>) -> CanFail,
+    {
+        let mut surface = framebuffer::SimpleFrameBuffer::with_stencil_buffer(
+            &self.window.display,
+            target.texture().inner(),
+            &target.stencil,
+        )?;
+        let mut drawer = Drawer::new(
+            &mut surface,
+            &self.window.display,
+            program,
+            &self.sdf_program,
+            &self.blank_texture,
+            &self.fonts,
+            &self.meshes,
+            &self.shaders,
+            &self.buffers,
+            camera,
+            (Instant::now() - self.start_time).as_secs_f32(),
+        );
+        // Render targets are not sRGB, so colors are left as they are.
+        // The conversion happens when the target's texture is drawn.
+        drawer.flip_y = true;
+        f(&mut drawer)
     }
     #[cfg(feature = "script")]
     /// Get a reference to the scripting environment
@@ -161,6 +680,65 @@ where
     pub fn load_font(&mut self, font_id: R::FontId, bytes: &[u8]) -> KuleResult<()> {
         self.fonts.load(font_id, bytes)
     }
+    /// Load a font from a file
+    pub fn load_font_file<P>(&mut self, font_id: R::FontId, path: P) -> KuleResult<()>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        self.fonts.load_file(font_id, path)
+    }
+    /**
+    Load a font from a file in the background
+
+    See [`Fonts::load_file_async`](struct.Fonts.html#method.load_file_async)
+    */
+    pub fn load_font_file_async<P>(&mut self, font_id: R::FontId, path: P) -> FontHandle<R::FontId>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        self.fonts.load_file_async(font_id, path)
+    }
+    /// Load an installed font by name, or the platform's default font if it is not installed
+    pub fn load_system_font(&mut self, font_id: R::FontId, name: &str) -> KuleResult<()> {
+        self.fonts.load_system(font_id, name)
+    }
+    /// Load an installed font by name, or a fallback font if it is not installed
+    pub fn load_system_font_or(
+        &mut self,
+        font_id: R::FontId,
+        name: &str,
+        fallback: &[u8],
+    ) -> KuleResult<()> {
+        self.fonts.load_system_or(font_id, name, fallback)
+    }
+    /// Compile and load a custom shader from vertex and fragment shader source
+    pub fn load_shader(
+        &mut self,
+        shader_id: R::ShaderId,
+        vertex: &str,
+        fragment: &str,
+    ) -> KuleResult<()> {
+        self.shaders.load(&self.window, shader_id, vertex, fragment)
+    }
+    /**
+    Compile and load a custom shader from vertex and fragment shader files
+
+    If `ContextBuilder::hot_reload_shaders` is enabled, the shader will be
+    recompiled whenever the files change.
+    */
+    pub fn load_shader_files<V, G>(
+        &mut self,
+        shader_id: R::ShaderId,
+        vertex: V,
+        fragment: G,
+    ) -> KuleResult<()>
+    where
+        V: AsRef<std::path::Path>,
+        G: AsRef<std::path::Path>,
+    {
+        self.shaders
+            .load_files(&self.window, shader_id, vertex, fragment)
+    }
     /**
     Get the glyph cache for a font
 
@@ -235,10 +813,75 @@ pub struct ContextBuilder {
     pub automatic_close: bool,
     /// How often to call the app's `update` function in Hz
     pub update_frequency: f32,
-    /// Samples to use for antialiasing
+    /**
+    Whether window space should be measured in logical pixels
+
+    Logical pixels are physical pixels divided by the window's scale factor.
+    If this is enabled, mouse positions, window sizes in events, and the camera
+    all use logical pixels, so things drawn at the default zoom have the same
+    apparent size on high DPI displays as on regular ones. Rendering is still
+    done at the full physical resolution.
+    */
+    pub logical_pixels: bool,
+    /**
+    Whether input events should be held until the next update
+
+    If this is enabled, input events are passed to `Kule::event` right before
+    `Kule::update` instead of as soon as they happen, so that they line up
+    with the updates. Window events like resizing are not held.
+    */
+    pub buffer_events: bool,
+    /**
+    How many updates presses and releases are remembered for after the one they happen in
+
+    See [`StateTracker::set_input_buffer`](struct.StateTracker.html#method.set_input_buffer).
+    */
+    pub input_buffer: u32,
+    /**
+    Samples to use for antialiasing
+
+    This should be a power of 2. `0` disables antialiasing.
+    If the requested number of samples is not supported,
+    antialiasing is disabled.
+    */
     pub samples: u16,
     /// The window's icon
     pub icon: Option<window::Icon>,
+    /**
+    Whether to use an sRGB framebuffer
+
+    If enabled, colors and textures are converted to linear space in the default shaders
+    so that blending and interpolation are gamma correct.
+    */
+    pub srgb: bool,
+    /**
+    Whether shaders loaded from files should be recompiled when the files change
+
+    Compilation errors are passed to `Kule::handle_error`.
+    This is intended for development.
+    */
+    pub hot_reload_shaders: bool,
+    /**
+    The resolution to render the scene at
+
+    If this is set, the scene is drawn to an offscreen target of this size.
+    The target is then scaled up to the window by the largest whole number
+    that fits, with no filtering, and centered with black bars around it.
+    This is good for pixel art.
+    */
+    pub resolution: Option<[u32; 2]>,
+    /// The bloom post effect, if any
+    pub bloom: Option<Bloom>,
+    /**
+    Whether what is drawn each frame should be kept for the next one
+
+    If this is enabled, the scene is drawn to an offscreen target that is never
+    cleared automatically, so draws accumulate across frames unless
+    `Drawer::clear` is called. This is good for paint programs and motion trails.
+
+    Without this, the contents of the window are undefined at the start of each frame.
+    */
+    pub persistent: bool,
     #[cfg(feature = "script")]
     /// Configuration for the scripting environment
     pub script_env: crate::ScriptEnv,
@@ -251,8 +894,16 @@ impl Default for ContextBuilder {
             size: [800.0; 2],
             automatic_close: true,
             update_frequency: 120.0,
+            logical_pixels: false,
+            buffer_events: false,
+            input_buffer: 0,
             samples: 0,
             icon: None,
+            srgb: false,
+            hot_reload_shaders: false,
+            resolution: None,
+            bloom: None,
+            persistent: false,
             #[cfg(feature = "script")]
             script_env: crate::ScriptEnv::default(),
         }
@@ -291,10 +942,60 @@ impl ContextBuilder {
             ..self
         }
     }
+    /// Set whether window space should be measured in logical pixels
+    pub fn logical_pixels(self, logical_pixels: bool) -> Self {
+        ContextBuilder {
+            logical_pixels,
+            ..self
+        }
+    }
+    /// Set whether input events should be held until the next update
+    pub fn buffer_events(self, buffer_events: bool) -> Self {
+        ContextBuilder {
+            buffer_events,
+            ..self
+        }
+    }
+    /// Set how many updates presses and releases are remembered for after the one they happen in
+    pub fn input_buffer(self, input_buffer: u32) -> Self {
+        ContextBuilder {
+            input_buffer,
+            ..self
+        }
+    }
     /// Set the samples used for antialiasing
     pub fn samples(self, samples: u16) -> Self {
         ContextBuilder { samples, ..self }
     }
+    /// Set whether to use an sRGB framebuffer
+    pub fn srgb(self, srgb: bool) -> Self {
+        ContextBuilder { srgb, ..self }
+    }
+    /// Set whether shaders loaded from files should be recompiled when the files change
+    pub fn hot_reload_shaders(self, hot_reload_shaders: bool) -> Self {
+        ContextBuilder {
+            hot_reload_shaders,
+            ..self
+        }
+    }
+    /// Set the resolution to render the scene at
+    pub fn resolution(self, resolution: [u32; 2]) -> Self {
+        ContextBuilder {
+            resolution: Some(resolution),
+            ..self
+        }
+    }
+    /// Enable the bloom post effect
+    pub fn bloom(self, bloom: Bloom) -> Self {
+        ContextBuilder {
+            bloom: Some(bloom),
+            ..self
+        }
+    }
+    /// Set whether what is drawn each frame should be kept for the next one
+    pub fn persistent(self, persistent: bool) -> Self {
+        ContextBuilder { persistent, ..self }
+    }
     /// Set the window icon using bitmap data
     pub fn icon(self, rgba: Vec<u8>, width: u32, height: u32) -> KuleResult<Self> {
         Ok(ContextBuilder {
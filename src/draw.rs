@@ -1,27 +1,59 @@
 use std::{
     cell::{Ref, RefCell},
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt,
     iter::once,
+    marker::PhantomData,
     rc::Rc,
 };
 
-use glium::{backend::*, *};
+use glium::{backend::*, uniforms::UniformValue, *};
 use vector2math::*;
 
-use crate::{Col, Color, Fonts, GlyphSize, GlyphSpec, Rect, Resources, Trans, Vec2};
+use crate::{
+    batch::{Batch, BatchState, DrawUniforms, Mask, StencilMode, StreamBuffers},
+    layout_runs, mesh_outline, sdf_spread, CanFail, Col, Color, Fonts, GlyphCache, GlyphId,
+    GlyphSize, GlyphSpec, Gradient, GradientShape, LaidOutDecoration, Mesh, NineSlice, Outline,
+    Rect, Resources, RichText, Shaders, TextLayout, TextMode, Texture, Trans, Uniform, UniformList,
+    Vec2, Wrap,
+};
 
 pub use index::PrimitiveType;
 
+/// A vertex used for drawing
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Vertex {
+    /// The position
     pub pos: Vec2,
+    /// The texture coordinates
+    pub uv: Vec2,
+    /// The color
+    pub color: Col,
 }
 
-implement_vertex!(Vertex, pos);
+implement_vertex!(Vertex, pos, uv, color);
 
-fn extend_transform(trans: Trans) -> [[f32; 3]; 3] {
-    [trans[0], trans[1], [0.0, 0.0, 1.0]]
+/**
+Create vertices from positions
+
+The texture coordinates are generated such that the bounding rectangle
+of the positions maps to the entire texture
+*/
+pub(crate) fn bounded_vertices<I>(positions: I) -> Vec<Vertex>
+where
+    I: IntoIterator<Item = Vec2>,
+{
+    let positions: Vec<Vec2> = positions.into_iter().collect();
+    let bounds: Rect = Rect::bounding(positions.iter().copied()).unwrap_or_default();
+    let size = bounds.size().map_with(|d| if d == 0.0 { 1.0 } else { d });
+    positions
+        .into_iter()
+        .map(|pos| Vertex {
+            pos,
+            uv: pos.sub(bounds.top_left()).div2(size),
+            color: Col::white(),
+        })
+        .collect()
 }
 
 /// A scene camera
@@ -31,13 +63,27 @@ pub struct Camera {
     pub center: Vec2,
     /// The zoom factor
     pub zoom: f32,
+    /**
+    A projection that is applied to world coordinates before the camera's view
+
+    This is the identity by default. See [`Projection`](trait.Projection.html)
+    for some common projections.
+    */
+    pub projection: Trans,
+    /// The size of the window in physical pixels
     pub(crate) window_size: Vec2,
+    /// The number of physical pixels per unit of window space
+    pub(crate) scale: f32,
 }
 
 impl Camera {
-    /// Get the size of the window
+    /**
+    Get the size of the window in window space
+
+    This is in logical pixels if the app uses them. Otherwise, it is in physical pixels.
+    */
     pub fn window_size(self) -> Vec2 {
-        self.window_size
+        self.window_size.div(self.scale)
     }
     /// Set the center
     pub fn with_center(self, center: Vec2) -> Self {
@@ -47,6 +93,10 @@ impl Camera {
     pub fn with_zoom(self, zoom: f32) -> Self {
         Camera { zoom, ..self }
     }
+    /// Set the projection
+    pub fn with_projection(self, projection: Trans) -> Self {
+        Camera { projection, ..self }
+    }
     /// Multiply the zoom by some factor
     pub fn zoom_by(self, by: f32) -> Self {
         Camera {
@@ -54,6 +104,18 @@ impl Camera {
             ..self
         }
     }
+    /**
+    Multiply the zoom by some factor while keeping the
+    point under a window space position in place
+
+    This is useful for zooming toward the mouse or a pinch gesture.
+    */
+    pub fn zoom_on(self, pos: Vec2, by: f32) -> Self {
+        let old = self.pos_to_coords(pos);
+        let zoomed = self.zoom_by(by);
+        let new = zoomed.pos_to_coords(pos);
+        zoomed.translate(old.sub(new))
+    }
     /// Keep the zoom within some bounds
     pub fn bound_zoom(self, min: f32, max: f32) -> Self {
         Camera {
@@ -70,36 +132,60 @@ impl Camera {
     }
     /// Convert a vector from window space to world space
     pub fn pos_to_coords(self, pos: Vec2) -> Vec2 {
-        pos.sub(self.window_size.div(2.0))
+        pos.sub(self.window_size().div(2.0))
             .div(self.zoom)
+            .transform(invert_transform(self.projection))
             .add(self.center)
     }
-    /// Convert a vector frrom world space to window space
+    /**
+    Convert a vector from world space to window space
+
+    This is the inverse of [`Camera::pos_to_coords`]. Older versions halved
+    the offset from the center, so positions did not line up with the
+    coordinates they came from.
+    */
     pub fn coords_to_pos(self, coords: Vec2) -> Vec2 {
         coords
             .sub(self.center)
-            .div(2.0)
+            .transform(self.projection)
             .mul(self.zoom)
-            .add(self.window_size.div(2.0))
+            .add(self.window_size().div(2.0))
     }
     /// Get the rectangle that bounds the view
     pub fn view_rect(self) -> Rect {
-        Rect::centered(self.center, self.window_size.div(self.zoom))
+        let [w, h] = self.window_size();
+        let corners = [[0.0, 0.0], [w, 0.0], [0.0, h], [w, h]].map(|pos| self.pos_to_coords(pos));
+        let min = corners
+            .iter()
+            .fold([f32::INFINITY; 2], |m, c| [m[0].min(c[0]), m[1].min(c[1])]);
+        let max = corners.iter().fold([f32::NEG_INFINITY; 2], |m, c| {
+            [m[0].max(c[0]), m[1].max(c[1])]
+        });
+        Rect::new(min, max.sub(min))
     }
     fn transform(&self) -> Trans {
         Trans::new_translate(self.center.neg())
+            .then(self.projection)
             .scale([self.zoom; 2].mul2([1.0, -1.0]))
-            .scale::<Vec2>(self.window_size.map_with(|d| 1.0 / d))
+            .scale::<Vec2>(self.window_size().map_with(|d| 1.0 / d))
             .zoom(2.0)
     }
 }
 
-type Vertices = VertexBuffer<Vertex>;
-type Indices = IndexBuffer<u16>;
-type MeshMap<R> = HashMap<DrawType<R>, (Vertices, Indices)>;
+type Vertices = Vec<Vertex>;
+type Indices = Vec<u32>;
+type MeshMap<R> = HashMap<DrawType<R>, CachedMesh>;
+
+/// A mesh in a [`MeshCache`](struct.MeshCache.html)
+struct CachedMesh {
+    vertices: Vertices,
+    indices: Indices,
+    /// The outline used for borders, which is found the first time it is needed
+    outline: Option<Outline>,
+}
 
 /**
-A cache for gpu geometry
+A cache for geometry
 
 Most simple geometries are cached automatically. However, irregular polygons are
 not cached by default. If a shape is drawn using a `Drawer::cached_*` method,
@@ -122,13 +208,15 @@ impl<R> MeshCache<R>
 where
     R: Resources,
 {
-    pub(crate) fn insert(
-        &self,
-        draw_type: DrawType<R>,
-        vertices: VertexBuffer<Vertex>,
-        indices: IndexBuffer<u16>,
-    ) {
-        self.0.borrow_mut().insert(draw_type, (vertices, indices));
+    pub(crate) fn insert(&self, draw_type: DrawType<R>, vertices: Vertices, indices: Indices) {
+        self.0.borrow_mut().insert(
+            draw_type,
+            CachedMesh {
+                vertices,
+                indices,
+                outline: None,
+            },
+        );
     }
     pub(crate) fn contains(&self, draw_type: &DrawType<R>) -> bool {
         self.0.borrow().contains_key(draw_type)
@@ -137,13 +225,26 @@ where
         let map = self.0.borrow();
         if map.contains_key(draw_type) {
             Some(Ref::map_split(self.0.borrow(), |map| {
-                let (vertices, indices) = &map[draw_type];
-                (vertices, indices)
+                let cached = &map[draw_type];
+                (&cached.vertices, &cached.indices)
             }))
         } else {
             None
         }
     }
+    /// Get the outline of a cached mesh, finding it if it has not been found yet
+    pub(crate) fn outline(&self, draw_type: &DrawType<R>) -> Option<Ref<'_, Outline>> {
+        if let Some(cached) = self.0.borrow_mut().get_mut(draw_type) {
+            if cached.outline.is_none() {
+                cached.outline = Some(mesh_outline(&cached.vertices, &cached.indices));
+            }
+        } else {
+            return None;
+        }
+        Some(Ref::map(self.0.borrow(), |map| {
+            map[draw_type].outline.as_ref().unwrap()
+        }))
+    }
     /// Check if the cache contains a mesh
     pub fn contains_mesh(&self, mesh_id: R::MeshId) -> bool {
         self.contains(&DrawType::Irregular(Some(mesh_id)))
@@ -182,6 +283,14 @@ impl Canvas for WindowCanvas {
     type Surface = Frame;
 }
 
+/// The canvas used for drawing to a [`RenderTarget`](struct.RenderTarget.html)
+pub struct TextureCanvas<'a>(PhantomData<&'a ()>);
+
+impl<'a> Canvas for TextureCanvas<'a> {
+    type Facade = Display;
+    type Surface = framebuffer::SimpleFrameBuffer<'a>;
+}
+
 /// The primary struct for drawing 2d geometry
 pub struct Drawer<'ctx, T = WindowCanvas, R = ()>
 where
@@ -191,14 +300,109 @@ where
     surface: &'ctx mut T::Surface,
     facade: &'ctx T::Facade,
     program: &'ctx Program,
+    sdf_program: &'ctx Program,
+    blank: &'ctx Texture,
     /// The fonts
     pub fonts: &'ctx Fonts<R::FontId>,
     /// The mesh cache
     pub meshes: &'ctx MeshCache<R>,
+    /// The custom shaders
+    pub shaders: &'ctx Shaders<R::ShaderId>,
+    /// The custom shader to use for drawing
+    ///
+    /// If this is `None` or the shader is not loaded, the default shader is used
+    pub shader: Option<R::ShaderId>,
     /// The scene camera
     pub camera: Camera,
-    /// The draw parameters
-    pub draw_params: DrawParameters<'ctx>,
+    uniforms: Rc<Vec<(String, Uniform)>>,
+    time: f32,
+    /// The blend mode
+    pub blend_mode: BlendMode,
+    clip: Option<Rect>,
+    mask: Option<Mask>,
+    writing_mask: bool,
+    pub(crate) flip_y: bool,
+    pub(crate) srgb: bool,
+    /// The draw parameters, which are changed with `Drawer::set_draw_params`
+    draw_params: DrawParameters<'ctx>,
+    /**
+    The layer that draws are added to
+
+    Draws in lower layers are drawn beneath draws in higher layers,
+    regardless of the order they are made in. Draws in the same layer
+    are drawn in order. Layers are only sorted between flushes.
+    */
+    pub layer: i32,
+    /**
+    Whether to snap the vertices of draws to the nearest pixel
+
+    This keeps thin lines and small text crisp, but it can make
+    slowly moving geometry appear to jitter.
+    */
+    pub pixel_snap: bool,
+    layers: BTreeMap<i32, Vec<Batch<'ctx>>>,
+    /// Drawn batches whose storage can be reused
+    spare_batches: Vec<Batch<'ctx>>,
+    buffers: &'ctx RefCell<StreamBuffers>,
+    /// Reusable buffers for building borders
+    border_buffers: (Vec<Vec2>, Vec<u32>),
+}
+
+/// Determines how the colors of a draw are combined with what has already been drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    /// Blend based on the alpha of the draw
+    #[default]
+    Alpha,
+    /// Add the color to what is already drawn
+    ///
+    /// This is good for glows and light effects
+    Add,
+    /// Subtract the color from what is already drawn
+    Subtract,
+    /// Multiply what is already drawn by the color
+    ///
+    /// The alpha of the draw is ignored. This is good for shadows and tinting.
+    Multiply,
+    /// Replace what is already drawn
+    Replace,
+}
+
+impl From<BlendMode> for Blend {
+    fn from(mode: BlendMode) -> Self {
+        let keep_alpha = BlendingFunction::Addition {
+            source: LinearBlendingFactor::Zero,
+            destination: LinearBlendingFactor::One,
+        };
+        match mode {
+            BlendMode::Alpha => Blend::alpha_blending(),
+            BlendMode::Add => Blend {
+                color: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::SourceAlpha,
+                    destination: LinearBlendingFactor::One,
+                },
+                alpha: keep_alpha,
+                ..Default::default()
+            },
+            BlendMode::Subtract => Blend {
+                color: BlendingFunction::ReverseSubtraction {
+                    source: LinearBlendingFactor::SourceAlpha,
+                    destination: LinearBlendingFactor::One,
+                },
+                alpha: keep_alpha,
+                ..Default::default()
+            },
+            BlendMode::Multiply => Blend {
+                color: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::DestinationColor,
+                    destination: LinearBlendingFactor::Zero,
+                },
+                alpha: keep_alpha,
+                ..Default::default()
+            },
+            BlendMode::Replace => Blend::default(),
+        }
+    }
 }
 
 impl<'ctx, T, R> Drawer<'ctx, T, R>
@@ -206,25 +410,46 @@ where
     T: Canvas,
     R: Resources,
 {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         surface: &'ctx mut T::Surface,
         facade: &'ctx T::Facade,
         program: &'ctx Program,
+        sdf_program: &'ctx Program,
+        blank: &'ctx Texture,
         fonts: &'ctx Fonts<R::FontId>,
         meshes: &'ctx MeshCache<R>,
+        shaders: &'ctx Shaders<R::ShaderId>,
+        buffers: &'ctx RefCell<StreamBuffers>,
         camera: Camera,
+        time: f32,
     ) -> Self {
         Drawer {
             surface,
             facade,
             program,
+            sdf_program,
+            blank,
             fonts,
             camera,
             meshes,
-            draw_params: DrawParameters {
-                blend: Blend::alpha_blending(),
-                ..Default::default()
-            },
+            shaders,
+            shader: None,
+            uniforms: Rc::new(Vec::new()),
+            time,
+            blend_mode: BlendMode::Alpha,
+            clip: None,
+            mask: None,
+            writing_mask: false,
+            flip_y: false,
+            srgb: false,
+            draw_params: Default::default(),
+            layer: 0,
+            pixel_snap: false,
+            layers: BTreeMap::new(),
+            spare_batches: Vec::new(),
+            buffers,
+            border_buffers: Default::default(),
         }
     }
     /**
@@ -259,13 +484,208 @@ where
         let base_camera = self.camera;
         self.with_camera(
             |_| Camera {
-                center: base_camera.window_size.div(2.0),
+                center: base_camera.window_size().div(2.0),
                 zoom: 1.0,
-                window_size: base_camera.window_size,
+                projection: Trans::identity(),
+                ..base_camera
             },
             draw,
         )
     }
+    /**
+    Temporarily use a custom shader for drawing
+
+    The shader is changed, the `draw` closure is called, and then
+    the shader is returned to its original state.
+    */
+    pub fn with_shader<F, S>(&mut self, shader_id: R::ShaderId, draw: F) -> S
+    where
+        F: FnOnce(&mut Self) -> S,
+    {
+        let base_shader = self.shader.replace(shader_id);
+        let res = draw(self);
+        self.shader = base_shader;
+        res
+    }
+    /**
+    Restrict drawing to a rectangle in world space
+
+    Drawing is restricted, the `draw` closure is called, and then
+    the restriction is returned to its original state.
+
+    Nested clips are intersected. Because clipping uses the scissor test,
+    the clipping region is always axis-aligned in window space.
+    */
+    pub fn clip<E, F, S>(&mut self, rect: E, draw: F) -> S
+    where
+        E: Rectangle<Scalar = f32>,
+        F: FnOnce(&mut Self) -> S,
+    {
+        let rect: Rect = rect.map();
+        let a = self.camera.coords_to_pos(rect.top_left());
+        let b = self.camera.coords_to_pos(rect.bottom_right());
+        self.clip_absolute(Rect::new(a, b.sub(a)), draw)
+    }
+    /**
+    Restrict drawing to a rectangle in window space
+
+    Drawing is restricted, the `draw` closure is called, and then
+    the restriction is returned to its original state.
+
+    Nested clips are intersected.
+    */
+    pub fn clip_absolute<E, F, S>(&mut self, rect: E, draw: F) -> S
+    where
+        E: Rectangle<Scalar = f32>,
+        F: FnOnce(&mut Self) -> S,
+    {
+        let rect: Rect = rect.map();
+        let base_clip = self.clip;
+        self.clip = Some(base_clip.map_or(rect, |clip| intersect_rects(clip, rect)));
+        let res = draw(self);
+        self.clip = base_clip;
+        res
+    }
+    /**
+    Restrict drawing to the inside of an arbitrary shape
+
+    Everything drawn in the `mask` closure is used as the mask shape and is not
+    actually drawn to the surface. Then the `draw` closure is called, and only the
+    parts of its draws that are inside the mask shape are drawn.
+
+    Masks do not nest. Starting a mask inside the `draw` closure of
+    another ends the outer one.
+
+    Draws are flushed before and after each closure, so layers only
+    order the draws within the same closure.
+    */
+    pub fn mask<M, F, S>(&mut self, mask: M, draw: F) -> S
+    where
+        M: FnOnce(&mut Self),
+        F: FnOnce(&mut Self) -> S,
+    {
+        self.masked(Mask::Inside, mask, draw)
+    }
+    /**
+    Restrict drawing to the outside of an arbitrary shape
+
+    This works the same as [`Drawer::mask`](struct.Drawer.html#method.mask),
+    but only the parts of draws that are outside the mask shape are drawn.
+    */
+    pub fn mask_outside<M, F, S>(&mut self, mask: M, draw: F) -> S
+    where
+        M: FnOnce(&mut Self),
+        F: FnOnce(&mut Self) -> S,
+    {
+        self.masked(Mask::Outside, mask, draw)
+    }
+    fn masked<M, F, S>(&mut self, mode: Mask, mask: M, draw: F) -> S
+    where
+        M: FnOnce(&mut Self),
+        F: FnOnce(&mut Self) -> S,
+    {
+        self.flush();
+        self.mask = None;
+        self.surface.clear_stencil(0);
+        self.writing_mask = true;
+        mask(self);
+        // The whole mask must be written before anything is drawn with it,
+        // even draws in lower layers
+        self.flush();
+        self.writing_mask = false;
+        self.mask = Some(mode);
+        let res = draw(self);
+        self.flush();
+        self.mask = None;
+        self.surface.clear_stencil(0);
+        res
+    }
+    /**
+    Temporarily use a different layer for drawing
+
+    The layer is changed, the `draw` closure is called, and then
+    the layer is returned to its original state.
+    */
+    pub fn with_layer<F, S>(&mut self, layer: i32, draw: F) -> S
+    where
+        F: FnOnce(&mut Self) -> S,
+    {
+        let base_layer = self.layer;
+        self.layer = layer;
+        let res = draw(self);
+        self.layer = base_layer;
+        res
+    }
+    /**
+    Temporarily use a different blend mode for drawing
+
+    The blend mode is changed, the `draw` closure is called, and then
+    the blend mode is returned to its original state.
+    */
+    pub fn with_blend_mode<F, S>(&mut self, blend_mode: BlendMode, draw: F) -> S
+    where
+        F: FnOnce(&mut Self) -> S,
+    {
+        let base_blend_mode = self.blend_mode;
+        self.blend_mode = blend_mode;
+        let res = draw(self);
+        self.blend_mode = base_blend_mode;
+        res
+    }
+    /// Get the draw parameters
+    pub fn draw_params(&self) -> &DrawParameters<'ctx> {
+        &self.draw_params
+    }
+    /**
+    Set the draw parameters for every subsequent draw
+
+    The `blend` field is ignored in favor of `blend_mode`.
+    Previous draws are flushed so that they are not affected.
+    */
+    pub fn set_draw_params(&mut self, draw_params: DrawParameters<'ctx>) {
+        self.flush();
+        self.draw_params = draw_params;
+    }
+    /**
+    Temporarily use different draw parameters for drawing
+
+    The draw parameters are changed, the `draw` closure is called, and then
+    the draw parameters are returned to their original state.
+    */
+    pub fn with_draw_params<F, S>(&mut self, draw_params: DrawParameters<'ctx>, draw: F) -> S
+    where
+        F: FnOnce(&mut Self) -> S,
+    {
+        let base_draw_params = self.draw_params.clone();
+        self.set_draw_params(draw_params);
+        let res = draw(self);
+        self.set_draw_params(base_draw_params);
+        res
+    }
+    /**
+    Set a uniform that is passed to the shader for every subsequent draw
+
+    Uniforms set on an individual draw with `Transformable::uniform` take
+    precedence over these.
+    */
+    pub fn set_uniform<U>(&mut self, name: &str, value: U)
+    where
+        U: Into<Uniform>,
+    {
+        set_uniform(Rc::make_mut(&mut self.uniforms), name, value.into());
+    }
+    /// Remove a uniform set with `Drawer::set_uniform`
+    pub fn remove_uniform(&mut self, name: &str) {
+        Rc::make_mut(&mut self.uniforms).retain(|(n, _)| n != name);
+    }
+    /// Get the time in seconds since the app started
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+    /// Get the custom shader program that should be used for drawing, if there is one
+    fn custom_program(&self) -> Option<&'ctx Program> {
+        self.shader.and_then(|id| self.shaders.get(id))
+    }
     /// Clear the surface with a color
     ///
     /// This clears the depth and stencil buffers as well
@@ -273,22 +693,184 @@ where
     where
         C: Color,
     {
+        self.flush();
         self.surface.clear_all(color.map(), 0.0, 0)
     }
+    /**
+    Draw any batched geometry
+
+    Consecutive draws in the same layer that use the same shader, texture,
+    and other gpu state are batched into a single draw call. Batches are drawn
+    in order of their layers. This happens automatically when drawing finishes
+    and before operations that need previous draws to be finished, such as
+    clearing and masking, so this usually does not need to be called manually.
+    */
+    pub fn flush(&mut self) {
+        // The layers and batches are kept so that their storage can be reused
+        let mut layers = std::mem::take(&mut self.layers);
+        for batches in layers.values_mut() {
+            for mut batch in batches.drain(..) {
+                self.draw_batch(&batch);
+                batch.clear();
+                self.spare_batches.push(batch);
+            }
+        }
+        self.layers = layers;
+    }
+    /// Draw a single batch
+    fn draw_batch(&mut self, batch: &Batch<'ctx>) {
+        let state = if let Some(state) = &batch.state {
+            state
+        } else {
+            return;
+        };
+        let mut buffers = self.buffers.borrow_mut();
+        buffers.stats.draw_calls += 1;
+        buffers.stats.vertices += batch.vertices.len();
+        buffers.stats.indices += batch.indices.len();
+        let (vertices, indices) = buffers.write(self.facade, &batch.vertices, &batch.indices);
+        let mut uniforms = vec![
+            ("tex", state.texture.uniform_value()),
+            ("u_time", UniformValue::Float(self.time)),
+            ("u_resolution", UniformValue::Vec2(self.camera.window_size)),
+            ("srgb", UniformValue::Bool(self.srgb)),
+        ];
+        if let Some(draw) = state.draw_uniforms {
+            uniforms.extend_from_slice(&[
+                (
+                    "transform",
+                    UniformValue::Mat3(extend_transform(draw.transform)),
+                ),
+                ("color", UniformValue::Vec4(draw.color)),
+                ("tex_rect", UniformValue::Vec4(draw.tex_rect)),
+            ]);
+        }
+        uniforms.extend(
+            state
+                .uniforms
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_uniform_value())),
+        );
+        self.surface
+            .draw(
+                vertices,
+                indices,
+                state.program,
+                &UniformList(uniforms),
+                &state.draw_parameters(),
+            )
+            .unwrap();
+    }
+    /// Add geometry to the last batch in a layer, starting a new batch if the state is different
+    fn push_batch<I>(&mut self, layer: i32, state: BatchState<'ctx>, vertices: I, indices: &[u32])
+    where
+        I: IntoIterator<Item = Vertex>,
+    {
+        let batches = self.layers.entry(layer).or_default();
+        match batches.last_mut() {
+            Some(batch) if batch.accepts(&state) => batch.push(state, vertices, indices),
+            _ => {
+                let mut batch = self.spare_batches.pop().unwrap_or_default();
+                batch.push(state, vertices, indices);
+                batches.push(batch);
+            }
+        }
+    }
+    /**
+    Draw vertices of a custom type with a custom shader
+
+    Unlike other draws, the vertices are not transformed on the cpu, so the shader
+    receives them exactly as they are given. The camera's transform from world coordinates
+    to normalized device coordinates is passed as the `mat3` uniform `u_camera`.
+    The builtin uniforms and any uniforms set with `Drawer::set_uniform` are passed as well.
+
+    Every 3 indices form a triangle. The current blend mode, clip, mask, and draw
+    parameters are respected. If the shader does not exist, nothing is drawn.
+
+    Custom draws are not layered. Everything drawn before them is flushed first.
+    */
+    pub fn custom<V>(&mut self, shader: R::ShaderId, vertices: &[V], indices: &[u32]) -> CanFail
+    where
+        V: glium::Vertex,
+    {
+        let program = if let Some(program) = self.shaders.get(shader) {
+            program
+        } else {
+            return Ok(());
+        };
+        self.flush();
+        {
+            let stats = &mut self.buffers.borrow_mut().stats;
+            stats.draw_calls += 1;
+            stats.vertices += vertices.len();
+            stats.indices += indices.len();
+            stats.uploads += 2;
+            stats.allocations += 2;
+        }
+        let vertices = VertexBuffer::new(self.facade, vertices)?;
+        let indices = IndexBuffer::new(self.facade, index::PrimitiveType::TrianglesList, indices)?;
+        let mut camera = self.camera.transform();
+        if self.flip_y {
+            camera = camera.scale([1.0, -1.0]);
+        }
+        let [[a, b, c], [d, e, f]] = camera;
+        let clip = self
+            .clip
+            .map(|clip| scissor_rect(clip, self.camera, self.flip_y));
+        let state = BatchState {
+            program,
+            texture: self.blank.clone(),
+            uniforms: Rc::clone(&self.uniforms),
+            blend_mode: self.blend_mode,
+            scissor: match (self.draw_params.scissor, clip) {
+                (Some(a), Some(b)) => Some(intersect_scissors(a, b)),
+                (a, b) => a.or(b),
+            },
+            stencil: if self.writing_mask {
+                StencilMode::WriteMask
+            } else {
+                self.mask.map_or(StencilMode::Normal, StencilMode::Masked)
+            },
+            params: self.draw_params.clone(),
+            custom_params: true,
+            draw_uniforms: None,
+        };
+        let mut uniforms = vec![
+            ("tex", state.texture.uniform_value()),
+            ("u_time", UniformValue::Float(self.time)),
+            ("u_resolution", UniformValue::Vec2(self.camera.window_size)),
+            ("srgb", UniformValue::Bool(self.srgb)),
+            (
+                "u_camera",
+                UniformValue::Mat3([[a, d, 0.0], [b, e, 0.0], [c, f, 1.0]]),
+            ),
+        ];
+        uniforms.extend(
+            state
+                .uniforms
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_uniform_value())),
+        );
+        self.surface.draw(
+            &vertices,
+            &indices,
+            program,
+            &UniformList(uniforms),
+            &state.draw_parameters(),
+        )?;
+        Ok(())
+    }
     /// Draw a rectangle
     pub fn rectangle<C, E>(&mut self, color: C, rect: E) -> Transformable<'ctx, '_, T, R>
     where
         C: Color,
         E: Rectangle<Scalar = f32>,
     {
-        let rect: [f32; 4] = rect.map();
         Transformable::new(
             self,
             color.map(),
             DrawType::Regular(4),
-            Trans::identity()
-                .scale(rect.size().mul(0.5 * std::f32::consts::SQRT_2))
-                .translate(rect.center()),
+            rect_transform(rect.map()),
         )
     }
     /// Draw a circle
@@ -331,96 +913,641 @@ where
                 .translate(ellip.center()),
         )
     }
-    /// Draw a polygon
-    pub fn polygon<'p, C, V, P>(&mut self, color: C, vertices: P) -> Transformable<'ctx, '_, T, R>
+    /**
+    Draw a filled ring
+
+    The circle is the outside of the ring. The inner radius is clamped
+    between zero and the outer radius.
+    */
+    pub fn ring<C, E>(
+        &mut self,
+        color: C,
+        circ: E,
+        inner_radius: f32,
+        resolution: u16,
+    ) -> Transformable<'ctx, '_, T, R>
     where
         C: Color,
-        V: Vector2<Scalar = f32> + 'p,
-        P: IntoIterator<Item = &'p V>,
+        E: Circle<Scalar = f32>,
     {
-        self.optionally_cached_polygon(None, color, vertices)
+        let center: Vec2 = circ.center().map();
+        let outer = circ.radius();
+        let inner = inner_radius.clamp(0.0, outer.max(0.0));
+        // The ring is built for each draw, as there are too many possible radii to cache
+        let vertices = bounded_vertices(regular_vertices(resolution).iter().flat_map(|v| {
+            once(center.add(v.pos.mul(inner))).chain(once(center.add(v.pos.mul(outer))))
+        }));
+        let n = resolution as u32 * 2;
+        let indices = (0..resolution as u32)
+            .flat_map(|i| {
+                let j = i * 2;
+                let [a, b, c, d] = [j, j + 1, (j + 2) % n, (j + 3) % n];
+                vec![a, b, d, a, d, c]
+            })
+            .collect();
+        let mesh = Mesh::new(vertices, indices);
+        self.mesh(color, &mesh)
     }
-    /// Draw a polygon with cached geometry
-    pub fn cached_polygon<'p, C, V, P>(
+    /**
+    Draw a stroked segment of a circle's outline
+
+    Angles are in radians. The arc goes from `start` to `end` with
+    `resolution` segments, and its thickness is centered on the circle.
+    */
+    pub fn arc<C, E>(
         &mut self,
-        mesh_id: R::MeshId,
         color: C,
-        vertices: P,
+        circ: E,
+        [start, end]: [f32; 2],
+        thickness: f32,
+        resolution: u16,
     ) -> Transformable<'ctx, '_, T, R>
     where
         C: Color,
-        V: Vector2<Scalar = f32> + 'p,
-        P: IntoIterator<Item = &'p V>,
+        E: Circle<Scalar = f32>,
     {
-        self.optionally_cached_polygon(Some(mesh_id), color, vertices)
+        let center: Vec2 = circ.center().map();
+        let inner = circ.radius() - thickness / 2.0;
+        let outer = circ.radius() + thickness / 2.0;
+        let resolution = resolution.max(1) as u32;
+        let vertices = bounded_vertices((0..=resolution).flat_map(|i| {
+            let dir = (start + (end - start) * i as f32 / resolution as f32).angle_as_vector();
+            once(center.add(dir.mul(inner))).chain(once(center.add(dir.mul(outer))))
+        }));
+        let indices = (0..resolution)
+            .flat_map(|i| {
+                let j = i * 2;
+                vec![j, j + 1, j + 3, j, j + 3, j + 2]
+            })
+            .collect();
+        let mesh = Mesh::new(vertices, indices);
+        self.mesh(color, &mesh)
     }
-    fn optionally_cached_polygon<'p, C, V, P>(
+    /**
+    Draw a filled slice of a circle
+
+    Angles are in radians. The sector goes from `start` to `end` with `resolution` segments.
+    */
+    pub fn sector<C, E>(
         &mut self,
-        mesh_id: Option<R::MeshId>,
         color: C,
-        vertices: P,
+        circ: E,
+        [start, end]: [f32; 2],
+        resolution: u16,
     ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        E: Circle<Scalar = f32>,
+    {
+        let center: Vec2 = circ.center().map();
+        let radius = circ.radius();
+        let resolution = resolution.max(1);
+        let vertices: Vec<Vec2> = once(center)
+            .chain((0..=resolution).map(|i| {
+                let angle = start + (end - start) * i as f32 / resolution as f32;
+                center.add(angle.angle_as_vector().mul(radius))
+            }))
+            .collect();
+        self.polygon(color, &vertices)
+    }
+    /// Draw a polygon
+    pub fn polygon<'p, C, V, P>(&mut self, color: C, vertices: P) -> Transformable<'ctx, '_, T, R>
     where
         C: Color,
         V: Vector2<Scalar = f32> + 'p,
         P: IntoIterator<Item = &'p V>,
     {
-        let vertices = VertexBuffer::new(
-            self.facade,
-            &vertices
-                .into_iter()
-                .map(|v| Vertex { pos: v.map() })
-                .collect::<Vec<_>>(),
-        )
-        .unwrap();
-        let len = vertices.len() as u16;
-        let indices = IndexBuffer::new(
-            self.facade,
-            PrimitiveType::TrianglesList,
-            &(1..(len - 2))
-                .flat_map(|n| once(0).chain(once(n)).chain(once(n + 1)))
-                .chain(once(0).chain(once(len - 2)).chain(once(len - 1)))
-                .collect::<Vec<_>>(),
-        )
-        .unwrap();
-        self.meshes
-            .insert(DrawType::Irregular(mesh_id), vertices, indices);
-        Transformable::new(
-            self,
-            color.map(),
-            DrawType::Irregular(mesh_id),
-            Trans::identity(),
-        )
+        self.optionally_cached_polygon(None, color, vertices)
     }
-    /// Draw a line
-    pub fn line<C, P>(
-        &mut self,
-        color: C,
-        endpoints: P,
-        thickness: f32,
-    ) -> Transformable<'ctx, '_, T, R>
+    /**
+    Draw a polygon where each vertex has its own color
+
+    Colors are interpolated across the polygon
+    */
+    pub fn polygon_colored<V, C, P>(&mut self, vertices: P) -> Transformable<'ctx, '_, T, R>
     where
+        V: Vector2<Scalar = f32>,
         C: Color,
-        P: Pair,
+        P: IntoIterator<Item = (V, C)>,
+    {
+        let mesh = Mesh::polygon_colored(vertices);
+        self.mesh(Col::white(), &mesh)
+    }
+    /// Draw a rectangle filled with a gradient
+    pub fn rectangle_gradient<E>(
+        &mut self,
+        rect: E,
+        gradient: &Gradient,
+        shape: GradientShape,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let rect: Rect = rect.map();
+        let corners = [
+            rect.top_left(),
+            rect.top_right(),
+            rect.bottom_right(),
+            rect.bottom_left(),
+        ];
+        self.polygon_gradient(&corners, gradient, shape)
+    }
+    /// Draw a circle filled with a gradient
+    pub fn circle_gradient<E>(
+        &mut self,
+        circ: E,
+        resolution: u16,
+        gradient: &Gradient,
+        shape: GradientShape,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        E: Circle<Scalar = f32>,
+    {
+        let points: Vec<Vec2> = regular_vertices(resolution)
+            .into_iter()
+            .map(|v| v.pos.mul(circ.radius()).add(circ.center().map()))
+            .collect();
+        self.polygon_gradient(&points, gradient, shape)
+    }
+    /**
+    Draw a convex polygon filled with a gradient
+
+    The gradient's shape is in the same coordinates as the polygon's vertices.
+    */
+    pub fn polygon_gradient<'p, V, P>(
+        &mut self,
+        vertices: P,
+        gradient: &Gradient,
+        shape: GradientShape,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        let mesh = Mesh::polygon_gradient(vertices, gradient, shape);
+        self.mesh(Col::white(), &mesh)
+    }
+    /// Draw a polygon with cached geometry
+    pub fn cached_polygon<'p, C, V, P>(
+        &mut self,
+        mesh_id: R::MeshId,
+        color: C,
+        vertices: P,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        self.optionally_cached_polygon(Some(mesh_id), color, vertices)
+    }
+    fn optionally_cached_polygon<'p, C, V, P>(
+        &mut self,
+        mesh_id: Option<R::MeshId>,
+        color: C,
+        vertices: P,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        let vertices = bounded_vertices(vertices.into_iter().map(|v| v.map()));
+        self.insert_irregular(mesh_id, vertices);
+        Transformable::new(
+            self,
+            color.map(),
+            DrawType::Irregular(mesh_id),
+            Trans::identity(),
+        )
+    }
+    /**
+    Draw a textured polygon
+
+    Each vertex is paired with texture coordinates, where `[0.0, 0.0]` is the
+    top-left of the texture and `[1.0, 1.0]` is the bottom-right.
+    */
+    pub fn textured_polygon<'p, V, U, P>(
+        &mut self,
+        texture: &Texture,
+        vertices: P,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        V: Vector2<Scalar = f32> + 'p,
+        U: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p (V, U)>,
+    {
+        self.optionally_cached_textured_polygon(None, texture, vertices)
+    }
+    /// Draw a textured polygon with cached geometry
+    pub fn cached_textured_polygon<'p, V, U, P>(
+        &mut self,
+        mesh_id: R::MeshId,
+        texture: &Texture,
+        vertices: P,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        V: Vector2<Scalar = f32> + 'p,
+        U: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p (V, U)>,
+    {
+        self.optionally_cached_textured_polygon(Some(mesh_id), texture, vertices)
+    }
+    fn optionally_cached_textured_polygon<'p, V, U, P>(
+        &mut self,
+        mesh_id: Option<R::MeshId>,
+        texture: &Texture,
+        vertices: P,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        V: Vector2<Scalar = f32> + 'p,
+        U: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p (V, U)>,
+    {
+        let vertices: Vec<Vertex> = vertices
+            .into_iter()
+            .map(|(pos, uv)| Vertex {
+                pos: pos.map(),
+                uv: uv.map(),
+                color: Col::white(),
+            })
+            .collect();
+        self.insert_irregular(mesh_id, vertices);
+        Transformable::multi(
+            self,
+            Col::white(),
+            once(DrawItem {
+                ty: DrawType::Irregular(mesh_id),
+                transform: Trans::identity(),
+                color: None,
+                texture: Some(texture.clone()),
+                src: None,
+                mesh: None,
+                border: None,
+            }),
+            Trans::identity(),
+        )
+    }
+    /// Draw a nine-slice texture that is stretched to fill a rectangle
+    pub fn nine_slice<E>(
+        &mut self,
+        nine_slice: &NineSlice,
+        rect: E,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let rect: Rect = rect.map();
+        let [width, height]: Vec2 = nine_slice.texture.size().map_with(|d| d as f32);
+        let [left, right, top, bottom] = nine_slice.insets;
+        let scale = nine_slice.scale;
+        let src_xs = [0.0, left, width - right, width];
+        let src_ys = [0.0, top, height - bottom, height];
+        let dst_xs = [
+            rect.left(),
+            rect.left() + left * scale,
+            rect.right() - right * scale,
+            rect.right(),
+        ];
+        let dst_ys = [
+            rect.top(),
+            rect.top() + top * scale,
+            rect.bottom() - bottom * scale,
+            rect.bottom(),
+        ];
+        let items: Vec<DrawItem<R>> = (0..3)
+            .flat_map(|j| (0..3).map(move |i| (i, j)))
+            .filter(|&(i, j)| src_xs[i + 1] > src_xs[i] && src_ys[j + 1] > src_ys[j])
+            .map(|(i, j)| DrawItem {
+                ty: DrawType::Regular(4),
+                transform: rect_transform([
+                    dst_xs[i],
+                    dst_ys[j],
+                    dst_xs[i + 1] - dst_xs[i],
+                    dst_ys[j + 1] - dst_ys[j],
+                ]),
+                color: None,
+                texture: Some(nine_slice.texture.clone()),
+                src: Some([
+                    src_xs[i],
+                    src_ys[j],
+                    src_xs[i + 1] - src_xs[i],
+                    src_ys[j + 1] - src_ys[j],
+                ]),
+                mesh: None,
+                border: None,
+            })
+            .collect();
+        Transformable::multi(self, Col::white(), items, Trans::identity())
+    }
+    /**
+    Fill a rectangle with a repeating texture
+
+    Each repetition of the texture has the size `tile_size` in world space.
+    The texture is made to repeat regardless of its wrap option.
+    */
+    pub fn tiled_rectangle<E>(
+        &mut self,
+        texture: &Texture,
+        rect: E,
+        tile_size: Vec2,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let rect: Rect = rect.map();
+        let texture = texture.with_options(texture.options().wrap(Wrap::Repeat));
+        let mut tfbl = self.image(&texture, rect);
+        tfbl.uv_scale = rect.size().div2(tile_size);
+        tfbl
+    }
+    /// Triangulate a polygon and insert it into the mesh cache
+    fn insert_irregular(&mut self, mesh_id: Option<R::MeshId>, vertices: Vec<Vertex>) {
+        let positions: Vec<Vec2> = vertices.iter().map(|v| v.pos).collect();
+        let indices = polygon_indices(&positions);
+        self.meshes
+            .insert(DrawType::Irregular(mesh_id), vertices, indices);
+    }
+    /// Draw a line
+    pub fn line<C, P>(
+        &mut self,
+        color: C,
+        endpoints: P,
+        thickness: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        P: Pair,
         P::Item: Vector2<Scalar = f32>,
     {
         let (a, b) = endpoints.to_pair();
-        let a: Vec2 = a.map();
-        let b: Vec2 = b.map();
-        let diff = b.sub(a);
-        let length = diff.mag();
-        let midpoint = a.lerp(b, 0.5);
-        let rot = diff.atan();
         Transformable::new(
             self,
             color.map(),
             DrawType::Regular(4),
-            Trans::identity()
-                .scale([length, thickness].mul(0.5 * std::f32::consts::SQRT_2))
-                .rotate(rot)
-                .translate(midpoint),
+            line_transform(a.map(), b.map(), thickness),
         )
     }
+    /**
+    Draw an image
+
+    The texture is stretched to fill the rectangle.
+
+    The image is drawn with a white color, so calling `Transformable::color`
+    or `Transformable::tint` on the result tints the image.
+    */
+    pub fn image<E>(&mut self, texture: &Texture, rect: E) -> Transformable<'ctx, '_, T, R>
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        Transformable::multi(
+            self,
+            Col::white(),
+            once(DrawItem {
+                ty: DrawType::Regular(4),
+                transform: Trans::identity(),
+                color: None,
+                texture: Some(texture.clone()),
+                src: None,
+                mesh: None,
+                border: None,
+            }),
+            rect_transform(rect.map()),
+        )
+    }
+}
+
+impl<'ctx, T, R> Drop for Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Get the vertices of a regular polygon with the given number of sides
+pub(crate) fn regular_vertices(n: u16) -> Vec<Vertex> {
+    let angle_offset = f32::TAU / n as f32 / 2.0;
+    bounded_vertices(
+        (0..n).map(|i| (i as f32 / n as f32 * f32::TAU + angle_offset).angle_as_vector()),
+    )
+}
+
+/// Get the indices that triangulate a convex polygon with the given number of vertices
+pub(crate) fn fan_indices(len: u32) -> Vec<u32> {
+    (1..(len - 2))
+        .flat_map(|n| once(0).chain(once(n)).chain(once(n + 1)))
+        .chain(once(0).chain(once(len - 2)).chain(once(len - 1)))
+        .collect()
+}
+
+/// Get the cross product of the edges from `a` to `b` and from `b` to `c`
+fn corner_cross(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    let [x1, y1] = b.sub(a);
+    let [x2, y2] = c.sub(b);
+    x1 * y2 - y1 * x2
+}
+
+/// Check if a point is inside or on the edge of a triangle
+fn in_triangle(p: Vec2, [a, b, c]: [Vec2; 3]) -> bool {
+    let ab = corner_cross(a, b, p);
+    let bc = corner_cross(b, c, p);
+    let ca = corner_cross(c, a, p);
+    (ab >= 0.0 && bc >= 0.0 && ca >= 0.0) || (ab <= 0.0 && bc <= 0.0 && ca <= 0.0)
+}
+
+/**
+Get the indices that triangulate a simple polygon
+
+Convex polygons are triangulated with a fan. Concave polygons
+are triangulated by ear clipping.
+*/
+pub(crate) fn polygon_indices(polygon: &[Vec2]) -> Vec<u32> {
+    let n = polygon.len();
+    if n < 3 {
+        return Vec::new();
+    }
+    let corner =
+        |i: usize| corner_cross(polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]);
+    let convex = (0..n).all(|i| corner(i) >= 0.0) || (0..n).all(|i| corner(i) <= 0.0);
+    if convex {
+        return fan_indices(n as u32);
+    }
+    // The sign of the area is the sign of the cross product at convex corners
+    let area: f32 = (0..n)
+        .map(|i| {
+            let [x1, y1] = polygon[i];
+            let [x2, y2] = polygon[(i + 1) % n];
+            x1 * y2 - x2 * y1
+        })
+        .sum();
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut indices = Vec::with_capacity((n - 2) * 3);
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let triangle = |i: usize| {
+            [
+                remaining[(i + len - 1) % len],
+                remaining[i],
+                remaining[(i + 1) % len],
+            ]
+        };
+        let is_ear = |i: usize| {
+            let [a, b, c] = triangle(i);
+            let points = [polygon[a], polygon[b], polygon[c]];
+            corner_cross(points[0], points[1], points[2]) * area > 0.0
+                && remaining
+                    .iter()
+                    .filter(|&&j| j != a && j != b && j != c)
+                    .all(|&j| !in_triangle(polygon[j], points))
+        };
+        // If there are no ears, the polygon is not simple, so just clip something
+        let ear = (0..len).find(|&i| is_ear(i)).unwrap_or(0);
+        indices.extend(triangle(ear).iter().map(|&i| i as u32));
+        remaining.remove(ear);
+    }
+    indices.extend(remaining.into_iter().map(|i| i as u32));
+    indices
+}
+
+/// Get the intersection of two rectangles
+///
+/// If the rectangles do not overlap, the resulting rectangle has zero size
+fn intersect_rects(a: Rect, b: Rect) -> Rect {
+    let left = a.left().max(b.left());
+    let top = a.top().max(b.top());
+    let right = a.right().min(b.right()).max(left);
+    let bottom = a.bottom().min(b.bottom()).max(top);
+    [left, top, right - left, bottom - top]
+}
+
+/// Get the intersection of two scissor rectangles
+fn intersect_scissors(a: glium::Rect, b: glium::Rect) -> glium::Rect {
+    let left = a.left.max(b.left);
+    let bottom = a.bottom.max(b.bottom);
+    let right = (a.left + a.width).min(b.left + b.width);
+    let top = (a.bottom + a.height).min(b.bottom + b.height);
+    glium::Rect {
+        left,
+        bottom,
+        width: right.saturating_sub(left),
+        height: top.saturating_sub(bottom),
+    }
+}
+
+/// Convert a rectangle in window space into a scissor rectangle
+///
+/// `flip_y` should be true when the surface's y axis is flipped
+fn scissor_rect(rect: Rect, camera: Camera, flip_y: bool) -> glium::Rect {
+    let window_size = camera.window_size;
+    let rect = Rect::new(
+        rect.top_left().mul(camera.scale),
+        rect.size().mul(camera.scale),
+    );
+    let left = rect.left().max(0.0);
+    let top = rect.top().max(0.0);
+    let right = rect.right().min(window_size.x()).max(left);
+    let bottom = rect.bottom().min(window_size.y()).max(top);
+    glium::Rect {
+        left: left.round() as u32,
+        bottom: if flip_y {
+            top
+        } else {
+            window_size.y() - bottom
+        }
+        .round() as u32,
+        width: (right - left).round() as u32,
+        height: (bottom - top).round() as u32,
+    }
+}
+
+/// Get the inverse of an affine transformation
+fn invert_transform([[a, b, c], [d, e, f]]: Trans) -> Trans {
+    let det = a * e - b * d;
+    if det == 0.0 {
+        return Trans::identity();
+    }
+    let [ia, ib, id, ie] = [e / det, -b / det, -d / det, a / det];
+    [[ia, ib, -(ia * c + ib * f)], [id, ie, -(id * c + ie * f)]]
+}
+
+/**
+Common projections for viewing the world from different perspectives
+
+These are intended to be used as a [`Camera`](struct.Camera.html)'s projection
+so that the world can be drawn in normal world coordinates.
+*/
+pub trait Projection {
+    /**
+    Get a dimetric projection
+
+    The world's x axis points down and to the right, and its y axis points
+    down and to the left. A unit square becomes a diamond that is `2.0` wide
+    and `2.0 * ratio` tall. A ratio of `0.5` is the projection commonly called
+    isometric in pixel art games.
+    */
+    fn dimetric(ratio: f32) -> Self;
+    /**
+    Get a true isometric projection
+
+    This is a dimetric projection with a ratio of `tan(30°)`
+    */
+    fn isometric() -> Self
+    where
+        Self: Sized,
+    {
+        Self::dimetric(std::f32::consts::FRAC_PI_6.tan())
+    }
+}
+
+impl Projection for Trans {
+    fn dimetric(ratio: f32) -> Self {
+        [[1.0, -1.0, 0.0], [ratio, ratio, 0.0]]
+    }
+}
+
+/// Extend a transformation to a 3x3 matrix that can be passed to a shader
+fn extend_transform(trans: Trans) -> [[f32; 3]; 3] {
+    [trans[0], trans[1], [0.0, 0.0, 1.0]]
+}
+
+/// Snap a position in normalized device coordinates to the nearest pixel
+fn snap_to_pixel(pos: Vec2, window_size: Vec2) -> Vec2 {
+    let half = window_size.div(2.0);
+    pos.add([1.0; 2])
+        .mul2(half)
+        .map_with::<Vec2, _>(f32::round)
+        .div2(half)
+        .sub([1.0; 2])
+}
+
+/// Set a uniform in a list sorted by name, replacing any uniform with the same name
+fn set_uniform(uniforms: &mut Vec<(String, Uniform)>, name: &str, value: Uniform) {
+    match uniforms.binary_search_by(|(n, _)| n.as_str().cmp(name)) {
+        Ok(i) => uniforms[i].1 = value,
+        Err(i) => uniforms.insert(i, (name.into(), value)),
+    }
+}
+
+/// Get the transform that maps a `DrawType::Regular(4)` onto a rectangle
+pub(crate) fn rect_transform(rect: Rect) -> Trans {
+    Trans::identity()
+        .scale(rect.size().mul(0.5 * std::f32::consts::SQRT_2))
+        .translate(rect.center())
+}
+
+/// Get the transform that maps a `DrawType::Regular(4)` onto a line
+pub(crate) fn line_transform(a: Vec2, b: Vec2, thickness: f32) -> Trans {
+    let diff = b.sub(a);
+    let length = diff.mag();
+    let midpoint = a.lerp(b, 0.5);
+    let rot = diff.atan();
+    Trans::identity()
+        .scale([length, thickness].mul(0.5 * std::f32::consts::SQRT_2))
+        .rotate(rot)
+        .translate(midpoint)
 }
 
 /// Parameters for drawing rounded lines
@@ -447,6 +1574,30 @@ impl RoundLine {
     }
 }
 
+/**
+Get the outline of a capsule around two points
+
+The outline always winds in the same direction. If the points are
+the same, the outline is a circle.
+*/
+fn capsule_vertices(a: Vec2, b: Vec2, radius: f32, resolution: u16) -> Vec<Vec2> {
+    let diff = b.sub(a);
+    let diff_unit = if diff.mag() == 0.0 {
+        [1.0, 0.0]
+    } else {
+        diff.unit()
+    };
+    let perp = diff_unit.rotate(f32::TAU / 4.0).mul(radius);
+    let a_start = a.add(perp);
+    let b_start = b.sub(perp);
+    let semi_res = (resolution / 2).max(1);
+    let angle = |i: u16| i as f32 / (semi_res * 2) as f32 * f32::TAU;
+    (0..=semi_res)
+        .map(|i| a_start.rotate_about(angle(i), a))
+        .chain((0..=semi_res).map(|i| b_start.rotate_about(angle(i), b)))
+        .collect()
+}
+
 impl From<f32> for RoundLine {
     fn from(thickness: f32) -> Self {
         RoundLine::new(thickness)
@@ -506,26 +1657,65 @@ where
         let a: Vec2 = a.map();
         let b: Vec2 = b.map();
         let rl = rl.into();
-        let diff = b.sub(a);
-        let diff_unit = diff.unit();
         let radius = rl.thickness / 2.0;
-        let perp = diff_unit.rotate(f32::TAU / 4.0).mul(radius);
-        let length = diff.mag();
+        let length = b.dist(a);
         let a_center = a.lerp(b, radius / length);
         let b_center = b.lerp(a, radius / length);
-        let a_start = a_center.add(perp);
-        let b_start = b_center.add(perp);
-        let semi_res = rl.resolution / 2;
-        let vertices: Vec<Vec2> = (0..=semi_res)
-            .map(|i| {
-                let angle = i as f32 / rl.resolution as f32 * f32::TAU;
-                a_start.rotate_about(angle, a_center)
-            })
-            .chain((semi_res..=rl.resolution).map(|i| {
-                let angle = i as f32 / rl.resolution as f32 * f32::TAU;
-                b_start.rotate_about(angle, b_center)
-            }))
-            .collect();
+        let vertices = capsule_vertices(a_center, b_center, radius, rl.resolution);
+        self.optionally_cached_polygon(mesh_id, color, &vertices)
+    }
+    /**
+    Draw a capsule
+
+    A capsule is a rectangle between two points with a semicircle of
+    the given radius around each point. `resolution` is the resolution
+    of the circle formed by the two ends.
+    */
+    pub fn capsule<C, P>(
+        &mut self,
+        color: C,
+        endpoints: P,
+        radius: f32,
+        resolution: u16,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        P: Pair,
+        P::Item: Vector2<Scalar = f32>,
+    {
+        self.optionally_cached_capsule(None, color, endpoints, radius, resolution)
+    }
+    /// Draw a capsule with cached geometry
+    pub fn cached_capsule<C, P>(
+        &mut self,
+        mesh_id: R::MeshId,
+        color: C,
+        endpoints: P,
+        radius: f32,
+        resolution: u16,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        P: Pair,
+        P::Item: Vector2<Scalar = f32>,
+    {
+        self.optionally_cached_capsule(Some(mesh_id), color, endpoints, radius, resolution)
+    }
+    fn optionally_cached_capsule<C, P>(
+        &mut self,
+        mesh_id: Option<R::MeshId>,
+        color: C,
+        endpoints: P,
+        radius: f32,
+        resolution: u16,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        P: Pair,
+        P::Item: Vector2<Scalar = f32>,
+    {
+        let (a, b) = endpoints.to_pair();
+        let vertices = capsule_vertices(a.map(), b.map(), radius, resolution);
         self.optionally_cached_polygon(mesh_id, color, &vertices)
     }
     /// Draw a single character
@@ -542,64 +1732,361 @@ where
         let color: Col = color.map();
         let spec = spec.into();
         let scale_trans = GlyphSize::transform(&spec.size);
-        Transformable::new(
-            self,
-            color,
-            DrawType::Character {
-                ch,
-                resolution: spec.size.resolution,
-                font_id: spec.font_id,
-            },
-            scale_trans,
-        )
+        let sdf = self.atlas_fonts(&spec).map(|(_, sdf)| sdf);
+        let fonts: &'ctx Fonts<R::FontId> = self.fonts;
+        let items = fonts
+            .get(spec.font_id)
+            .and_then(|glyphs| {
+                self.glyph_item(
+                    glyphs,
+                    spec.font_id,
+                    ch.into(),
+                    spec.size.resolution,
+                    sdf,
+                    scale_trans,
+                )
+            })
+            .into_iter()
+            .collect();
+        self.text_transformable(color, items, &spec, sdf)
+    }
+    /**
+    Draw a string of text
+
+    The text can be a plain string or [`RichText`](struct.RichText.html),
+    whose spans can override the color, size, and font. The text is positioned
+    relative to the origin according to the alignment of the [`GlyphSpec`](struct.GlyphSpec.html).
+    */
+    pub fn text<'t, C, S, L>(&mut self, color: C, text: S, spec: L) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        S: Into<RichText<'t, R::FontId>>,
+        L: Into<GlyphSpec<R::FontId>>,
+    {
+        let color: Col = color.map();
+        let text = text.into();
+        let spec = spec.into();
+        let fonts: &'ctx Fonts<R::FontId> = self.fonts;
+        let (runs, styles) = fonts.runs(&text, &spec);
+        if runs.is_empty() {
+            return Transformable::new(self, color, DrawType::Empty, Trans::identity());
+        }
+        let (glyphs, decorations, _) = layout_runs(&runs, &spec);
+        let sdf = self.atlas_fonts(&spec).map(|(_, sdf)| sdf);
+        let mut items: Vec<DrawItem<R>> = glyphs
+            .into_iter()
+            .filter_map(|glyph| {
+                let run = &runs[glyph.run];
+                let (font_id, color) = styles[glyph.run];
+                self.glyph_item(
+                    run.glyphs,
+                    font_id,
+                    glyph.id,
+                    run.size.resolution,
+                    sdf,
+                    glyph.transform,
+                )
+                .map(|item| DrawItem {
+                    color: item.color.or(color),
+                    ..item
+                })
+            })
+            .collect();
+        items.extend(Self::decoration_items(&decorations, &styles, &spec));
+        self.text_transformable(color, items, &spec, sdf)
+    }
+    /**
+    Lay out text ahead of time so that it can be drawn many times
+
+    This does all of the work of laying out the text and looking up its glyphs,
+    so drawing the prepared text with [`Drawer::prepared_text`](struct.Drawer.html#method.prepared_text)
+    is much cheaper than drawing the text with `Drawer::text` every frame.
+
+    Returns `None` if none of the text's fonts are loaded.
+    */
+    pub fn prepare_text<'t, S, L>(&self, text: S, spec: L) -> Option<PreparedText<R>>
+    where
+        S: Into<RichText<'t, R::FontId>>,
+        L: Into<GlyphSpec<R::FontId>>,
+    {
+        let text = text.into();
+        let spec = spec.into();
+        let fonts: &'ctx Fonts<R::FontId> = self.fonts;
+        let (runs, styles) = fonts.runs(&text, &spec);
+        if runs.is_empty() {
+            return None;
+        }
+        let (glyphs, decorations, layout) = layout_runs(&runs, &spec);
+        let sdf = self.atlas_fonts(&spec).map(|(_, sdf)| sdf);
+        let mut items = Vec::new();
+        // Each run's glyph geometry is combined into a single mesh
+        let mut meshes = vec![(Vec::new(), Vec::new()); runs.len()];
+        for glyph in glyphs {
+            let run = &runs[glyph.run];
+            let (font_id, color) = styles[glyph.run];
+            let resolution = run.size.resolution;
+            if sdf.is_some() || run.glyphs.has_color_glyph(glyph.id) {
+                let item = self.glyph_item(
+                    run.glyphs,
+                    font_id,
+                    glyph.id,
+                    resolution,
+                    sdf,
+                    glyph.transform,
+                );
+                items.extend(item.map(|item| DrawItem {
+                    color: item.color.or(color),
+                    ..item
+                }));
+                continue;
+            }
+            let (vertices, indices): &mut (Vec<Vertex>, Vec<u32>) = &mut meshes[glyph.run];
+            let (_, geometry) = &*run.glyphs.glyph(glyph.id, resolution);
+            let offset = vertices.len() as u32;
+            vertices.extend(
+                bounded_vertices(geometry.vertices.iter().copied())
+                    .into_iter()
+                    .map(|v| Vertex {
+                        pos: v.pos.transform(glyph.transform),
+                        ..v
+                    }),
+            );
+            indices.extend(geometry.indices.iter().map(|&i| i as u32 + offset));
+        }
+        for (r, (vertices, indices)) in meshes.into_iter().enumerate() {
+            if !indices.is_empty() {
+                items.push(DrawItem {
+                    ty: DrawType::Empty,
+                    transform: Trans::identity(),
+                    color: styles[r].1,
+                    texture: None,
+                    src: None,
+                    mesh: Some(Mesh::new(vertices, indices)),
+                    border: None,
+                });
+            }
+        }
+        items.extend(Self::decoration_items(&decorations, &styles, &spec));
+        Some(PreparedText {
+            items: Rc::new(Self::style_glyphs(items, &spec, sdf)),
+            spec,
+            sdf,
+            layout,
+        })
+    }
+    /// Draw text that was prepared with [`Drawer::prepare_text`](struct.Drawer.html#method.prepare_text)
+    pub fn prepared_text<C>(
+        &mut self,
+        color: C,
+        text: &PreparedText<R>,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+    {
+        self.glyph_transformable(color.map(), Rc::clone(&text.items), &text.spec, text.sdf)
+    }
+    /**
+    Get the glyph cache for a spec if it should be drawn from an atlas
+
+    Also returns whether the atlas is a signed distance field
+    */
+    fn atlas_fonts(&self, spec: &GlyphSpec<R::FontId>) -> Option<(&'ctx GlyphCache, bool)> {
+        let fonts: &'ctx Fonts<R::FontId> = self.fonts;
+        let glyphs = fonts.get(spec.font_id)?;
+        match spec.mode.unwrap_or_else(|| glyphs.mode()) {
+            TextMode::Vector => None,
+            TextMode::Atlas => Some((glyphs, false)),
+            TextMode::Sdf => Some((glyphs, true)),
+        }
+    }
+    /**
+    Get the item that draws a glyph
+
+    `sdf` is whether the glyph is drawn from a signed distance field atlas,
+    or `None` if it is drawn from its geometry. Color glyphs are drawn as
+    textured quads, except in signed distance field text.
+    */
+    fn glyph_item(
+        &self,
+        glyphs: &GlyphCache,
+        font_id: R::FontId,
+        id: GlyphId,
+        resolution: u32,
+        sdf: Option<bool>,
+        transform: Trans,
+    ) -> Option<DrawItem<R>> {
+        if sdf != Some(true) {
+            if let Some(item) = self.color_item(glyphs, id, resolution, transform) {
+                return Some(item);
+            }
+        }
+        match sdf {
+            Some(sdf) => self.atlas_item(glyphs, id, resolution, sdf, transform),
+            None => Some(DrawItem {
+                ty: DrawType::Character {
+                    id,
+                    resolution,
+                    font_id,
+                },
+                transform,
+                color: None,
+                texture: None,
+                src: None,
+                mesh: None,
+                border: None,
+            }),
+        }
+    }
+    /**
+    Get the textured quad for a color glyph, such as an emoji
+
+    The quad is white so that the text's color does not affect the image.
+    */
+    fn color_item(
+        &self,
+        glyphs: &GlyphCache,
+        id: GlyphId,
+        resolution: u32,
+        transform: Trans,
+    ) -> Option<DrawItem<R>> {
+        let color_glyph = glyphs.color_glyph(id)?;
+        let texture = color_glyph.texture(self.facade).ok()?;
+        let rect = color_glyph.rect(&glyphs.metrics(id, resolution), resolution);
+        Some(DrawItem {
+            ty: DrawType::Regular(4),
+            transform: rect_transform(rect).then(transform),
+            color: Some(Col::white()),
+            texture: Some(texture),
+            src: None,
+            mesh: None,
+            border: None,
+        })
+    }
+    /// Get the quads for the underlines and strikethroughs of some text
+    fn decoration_items(
+        decorations: &[LaidOutDecoration],
+        styles: &[(R::FontId, Option<Col>)],
+        spec: &GlyphSpec<R::FontId>,
+    ) -> Vec<DrawItem<R>> {
+        let rotation = Trans::new_rotate(spec.rotation);
+        decorations
+            .iter()
+            .map(|decoration| DrawItem {
+                ty: DrawType::Regular(4),
+                transform: rect_transform(decoration.rect).then(rotation),
+                color: styles[decoration.run].1,
+                texture: None,
+                src: None,
+                mesh: None,
+                border: None,
+            })
+            .collect()
+    }
+    /// Get the textured quad for a glyph in a font's atlas
+    fn atlas_item(
+        &self,
+        glyphs: &GlyphCache,
+        id: GlyphId,
+        resolution: u32,
+        sdf: bool,
+        transform: Trans,
+    ) -> Option<DrawItem<R>> {
+        let (texture, src) = glyphs.atlas_glyph(self.facade, id, resolution, sdf).ok()?;
+        let padding = if sdf {
+            sdf_spread(resolution) as f32
+        } else {
+            0.0
+        };
+        Some(DrawItem {
+            ty: DrawType::Regular(4),
+            transform: rect_transform([-padding, -padding, src.width(), src.height()])
+                .then(transform),
+            color: None,
+            texture: Some(texture),
+            src: Some(src),
+            mesh: None,
+            border: None,
+        })
     }
-    /// Draw a string of text
-    pub fn text<C, L>(&mut self, color: C, string: &str, spec: L) -> Transformable<'ctx, '_, T, R>
-    where
-        C: Color,
-        L: Into<GlyphSpec<R::FontId>>,
-    {
-        use fontdue::layout::*;
-        let color: Col = color.map();
-        let spec = spec.into();
-        let scale_trans = GlyphSize::transform(&spec.size);
-        if let Some(glyphs) = self.fonts.get(spec.font_id) {
-            let mut gps = Vec::new();
-            Layout::new().layout_horizontal(
-                &[glyphs.font()],
-                &[&TextStyle::new(string, spec.size.resolution as f32, 0)],
-                &LayoutSettings {
-                    ..Default::default()
-                },
-                &mut gps,
-            );
-            let offset_chars: Vec<_> = gps
-                .into_iter()
-                .map(|gp| {
-                    let offset = [
-                        gp.x,
-                        -(spec.size.resolution as f32 + gp.y + gp.height as f32),
-                    ];
-                    (offset, gp.key.c)
-                })
-                .collect();
-            Transformable::multi(
-                self,
+    /**
+    Draw glyphs with the style of a spec
+
+    `sdf` is whether the glyphs are signed distance field atlas quads,
+    or `None` if they are glyph meshes. Outlines of glyph meshes and
+    shadows are drawn as extra copies of the glyphs behind them.
+    */
+    fn text_transformable(
+        &mut self,
+        color: Col,
+        items: Vec<DrawItem<R>>,
+        spec: &GlyphSpec<R::FontId>,
+        sdf: Option<bool>,
+    ) -> Transformable<'ctx, '_, T, R> {
+        let items = Rc::new(Self::style_glyphs(items, spec, sdf));
+        self.glyph_transformable(color, items, spec, sdf)
+    }
+    /// Add copies of glyphs for their spec's outline and shadow
+    fn style_glyphs(
+        items: Vec<DrawItem<R>>,
+        spec: &GlyphSpec<R::FontId>,
+        sdf: Option<bool>,
+    ) -> Vec<DrawItem<R>> {
+        let outline = spec
+            .outline
+            .filter(|_| sdf.is_none())
+            .map(|(color, width)| Border {
                 color,
-                offset_chars.into_iter().map(|(offset, ch)| DrawItem {
-                    ty: DrawType::Character {
-                        ch,
-                        resolution: spec.size.resolution,
-                        font_id: spec.font_id,
-                    },
-                    transform: Trans::new_translate(offset).then(scale_trans),
-                    color: None,
-                }),
-                Trans::identity(),
-            )
-        } else {
-            Transformable::new(self, color, DrawType::Empty, Trans::identity())
+                thickness: width * 2.0,
+            });
+        let copy = |item: &DrawItem<R>, offset: Vec2, color: Col| DrawItem {
+            ty: item.ty,
+            transform: item.transform.translate(offset),
+            color: Some(color),
+            texture: item.texture.clone(),
+            src: item.src,
+            mesh: item.mesh.clone(),
+            border: outline.map(|border| Border { color, ..border }),
+        };
+        let mut styled = Vec::new();
+        if let Some((shadow_color, offset)) = spec.shadow {
+            styled.extend(items.iter().map(|item| copy(item, offset, shadow_color)));
+        }
+        if let Some(outline) = outline {
+            styled.extend(items.iter().map(|item| copy(item, [0.0; 2], outline.color)));
         }
+        styled.extend(items);
+        styled
+    }
+    /// Draw styled glyphs, using the signed distance field shader if necessary
+    fn glyph_transformable(
+        &mut self,
+        color: Col,
+        items: Rc<Vec<DrawItem<R>>>,
+        spec: &GlyphSpec<R::FontId>,
+        sdf: Option<bool>,
+    ) -> Transformable<'ctx, '_, T, R> {
+        let sdf_program = self.sdf_program;
+        let mut tfbl = Transformable::shared(self, color, items, Trans::identity());
+        if sdf == Some(true) {
+            // Convert widths to distance field units
+            let field_units =
+                1.0 / (spec.size.ratio() * sdf_spread(spec.size.resolution) as f32 * 2.0);
+            let uniforms = Rc::make_mut(&mut tfbl.uniforms);
+            if let Some((color, width)) = spec.outline {
+                set_uniform(uniforms, "outline_color", color.into());
+                set_uniform(
+                    uniforms,
+                    "outline_width",
+                    (width * field_units).min(0.5).into(),
+                );
+            }
+            if let Some((color, width)) = spec.glow {
+                set_uniform(uniforms, "glow_color", color.into());
+                set_uniform(uniforms, "glow_width", (width * field_units).into());
+            }
+            tfbl.program = Some(sdf_program);
+        }
+        tfbl
     }
 }
 
@@ -612,7 +2099,7 @@ where
     Regular(u16),
     Irregular(Option<R::MeshId>),
     Character {
-        ch: char,
+        id: GlyphId,
         resolution: u32,
         font_id: R::FontId,
     },
@@ -622,56 +2109,15 @@ impl<R> DrawType<R>
 where
     R: Resources,
 {
-    fn vertices_indices<F>(
-        self,
-        facade: &F,
-        fonts: &Fonts<R::FontId>,
-    ) -> (VertexBuffer<Vertex>, IndexBuffer<u16>)
-    where
-        F: Facade,
-    {
+    fn vertices_indices(self) -> (Vertices, Indices) {
         match self {
-            DrawType::Empty => (
-                VertexBuffer::empty(facade, 0).unwrap(),
-                IndexBuffer::empty(facade, PrimitiveType::Points, 0).unwrap(),
-            ),
-            DrawType::Regular(n) => {
-                let angle_offset = f32::TAU / n as f32 / 2.0;
-                let vertices: Vec<Vertex> = (0..n)
-                    .map(|i| (i as f32 / n as f32 * f32::TAU + angle_offset).angle_as_vector())
-                    .map(|pos| Vertex { pos })
-                    .collect();
-                let indices: Vec<u16> = (1..(n - 2))
-                    .flat_map(|n| once(0).chain(once(n)).chain(once(n + 1)))
-                    .chain(once(0).chain(once(n - 2)).chain(once(n - 1)))
-                    .collect();
-                (
-                    VertexBuffer::new(facade, &vertices).unwrap(),
-                    IndexBuffer::new(facade, PrimitiveType::TrianglesList, &indices).unwrap(),
-                )
-            }
+            DrawType::Empty => (Vec::new(), Vec::new()),
+            DrawType::Regular(n) => (regular_vertices(n), fan_indices(n as u32)),
             DrawType::Irregular(_) => {
                 panic!("called DrawType::vertices_indices on DrawType::Irregular")
             }
-            DrawType::Character {
-                ch,
-                resolution,
-                font_id,
-            } => {
-                let (_, geometry) = &*fonts[font_id].glyph(ch, resolution);
-                let vertices = VertexBuffer::new(
-                    facade,
-                    &geometry
-                        .vertices
-                        .iter()
-                        .map(|&pos| Vertex { pos })
-                        .collect::<Vec<_>>(),
-                )
-                .unwrap();
-                let indices =
-                    IndexBuffer::new(facade, PrimitiveType::TrianglesList, &geometry.indices)
-                        .unwrap();
-                (vertices, indices)
+            DrawType::Character { .. } => {
+                panic!("called DrawType::vertices_indices on DrawType::Character")
             }
         }
     }
@@ -688,29 +2134,143 @@ where
             DrawType::Irregular(None) => write!(f, "Uncached"),
             DrawType::Irregular(Some(mesh_id)) => write!(f, "Cached ({:?})", mesh_id),
             DrawType::Character {
-                ch,
+                id,
                 resolution,
                 font_id,
-            } => write!(f, "'{}' at {}px with {:?}", ch, resolution, font_id),
+                ..
+            } => write!(f, "{:?} at {}px with {:?}", id, resolution, font_id),
         }
     }
 }
 
-struct DrawItem<R>
+/**
+Text that has been laid out ahead of time
+
+Laying out text and looking up its glyphs every frame can be expensive for
+text that rarely changes. Prepared text is created with
+[`Drawer::prepare_text`](struct.Drawer.html#method.prepare_text), which does
+this work once, and can be drawn any number of times with
+[`Drawer::prepared_text`](struct.Drawer.html#method.prepared_text).
+
+Prepared text is not affected by changes to the fonts it was prepared with.
+*/
+pub struct PreparedText<R>
 where
     R: Resources,
 {
-    ty: DrawType<R>,
-    transform: Trans,
-    color: Option<Col>,
+    items: Rc<Vec<DrawItem<R>>>,
+    spec: GlyphSpec<R::FontId>,
+    sdf: Option<bool>,
+    layout: TextLayout,
+}
+
+impl<R> PreparedText<R>
+where
+    R: Resources,
+{
+    /// Get the layout of the text, which can be used to find positions in it
+    pub fn layout(&self) -> &TextLayout {
+        &self.layout
+    }
+    /// Get the size of the text
+    pub fn size(&self) -> Vec2 {
+        self.layout.size()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct DrawItem<R>
+where
+    R: Resources,
+{
+    pub ty: DrawType<R>,
+    pub transform: Trans,
+    pub color: Option<Col>,
+    pub texture: Option<Texture>,
+    pub src: Option<Rect>,
+    pub mesh: Option<Mesh>,
+    /// The border, which overrides the draw's border
+    pub border: Option<Border>,
+}
+
+/// The items drawn by a draw command
+#[derive(Clone)]
+enum DrawItems<R>
+where
+    R: Resources,
+{
+    /// A single item, which is stored inline so that simple draws do not allocate
+    Single(DrawItem<R>),
+    /// Items that may be shared with other draws
+    Shared(Rc<Vec<DrawItem<R>>>),
+}
+
+impl<R> DrawItem<R>
+where
+    R: Resources,
+{
+    /**
+    Get the mesh that the item owns, if it is not stored in the mesh cache
+
+    Character meshes are kept in their font's glyph cache so that
+    they are freed along with the rest of the glyph's geometry.
+    */
+    fn own_mesh(&self, fonts: &Fonts<R::FontId>) -> Option<Mesh> {
+        match self.ty {
+            DrawType::Character {
+                id,
+                resolution,
+                font_id,
+                ..
+            } => Some(fonts[font_id].glyph_mesh(id, resolution)),
+            _ => self.mesh.clone(),
+        }
+    }
+}
+
+impl<R> DrawItems<R>
+where
+    R: Resources,
+{
+    fn iter(&self) -> std::slice::Iter<'_, DrawItem<R>> {
+        match self {
+            DrawItems::Single(item) => std::slice::from_ref(item).iter(),
+            DrawItems::Shared(items) => items.iter(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
-struct Border {
+pub(crate) struct Border {
     color: Col,
     thickness: f32,
 }
 
+/**
+A point about which a draw command's transformations are applied
+
+Relative pivots are positions within the bounding rectangle of the drawn
+geometry, where `[0.0, 0.0]` is its top-left corner and `[1.0, 1.0]` is
+its bottom-right corner.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pivot {
+    /// The center of the geometry
+    Center,
+    /// The top-left corner of the geometry
+    TopLeft,
+    /// The top-right corner of the geometry
+    TopRight,
+    /// The bottom-left corner of the geometry
+    BottomLeft,
+    /// The bottom-right corner of the geometry
+    BottomRight,
+    /// A position relative to the geometry's bounding rectangle
+    Relative(Vec2),
+    /// An absolute point
+    Absolute(Vec2),
+}
+
 /**
 A planned draw command
 
@@ -724,11 +2284,23 @@ where
     R: Resources,
 {
     drawer: &'drawer mut Drawer<'ctx, T, R>,
-    items: Rc<Vec<DrawItem<R>>>,
+    items: DrawItems<R>,
     color: Col,
     drawn: bool,
     transform: Trans,
+    pivot: Option<Pivot>,
     border: Option<Border>,
+    src: Option<Rect>,
+    tint: Col,
+    texture: Option<Texture>,
+    uv_scale: Vec2,
+    uniforms: Rc<Vec<(String, Uniform)>>,
+    shader: Option<R::ShaderId>,
+    /// A built in program that is used instead of the drawer's shader
+    program: Option<&'ctx Program>,
+    blend_mode: Option<BlendMode>,
+    draw_params: Option<DrawParameters<'ctx>>,
+    layer: Option<i32>,
 }
 
 impl<'ctx, 'drawer, T, R> Transformable<'ctx, 'drawer, T, R>
@@ -736,22 +2308,213 @@ where
     T: Canvas,
     R: Resources,
 {
-    /// Change the color
-    pub fn color<'tfbl, C>(&'tfbl mut self, color: C) -> Transformable<'ctx, 'tfbl, T, R>
-    where
-        C: Color,
-    {
+    /// Create a new draw command with the same state that can be modified
+    fn derive(&mut self) -> Transformable<'ctx, '_, T, R> {
         self.drawn = true;
         Transformable {
             drawer: self.drawer,
-            items: Rc::clone(&self.items),
-            color: color.map(),
+            items: self.items.clone(),
+            color: self.color,
             transform: self.transform,
+            pivot: self.pivot,
             drawn: false,
             border: self.border,
+            src: self.src,
+            tint: self.tint,
+            texture: self.texture.clone(),
+            uv_scale: self.uv_scale,
+            uniforms: Rc::clone(&self.uniforms),
+            shader: self.shader,
+            program: self.program,
+            blend_mode: self.blend_mode,
+            draw_params: self.draw_params.clone(),
+            layer: self.layer,
         }
     }
-    /// Apply a transformation
+    /// Change the color
+    pub fn color<'tfbl, C>(&'tfbl mut self, color: C) -> Transformable<'ctx, 'tfbl, T, R>
+    where
+        C: Color,
+    {
+        let mut tfbl = self.derive();
+        tfbl.color = color.map();
+        tfbl
+    }
+    /**
+    Multiply a tint color onto the draw
+
+    Unlike `color`, this also affects parts of the draw that have their own colors.
+    When drawing images, this tints the texture.
+
+    Tints stack, so tinting multiple times multiplies all of the tints together.
+    */
+    pub fn tint<'tfbl, C>(&'tfbl mut self, tint: C) -> Transformable<'ctx, 'tfbl, T, R>
+    where
+        C: Color,
+    {
+        let mut tfbl = self.derive();
+        tfbl.tint = tfbl.tint.mul_color(tint);
+        tfbl
+    }
+    /**
+    Multiply the opacity of the draw
+
+    This affects every color of the draw, including its border.
+    Like tints, opacities stack.
+    */
+    pub fn opacity<'tfbl>(&'tfbl mut self, opacity: f32) -> Transformable<'ctx, 'tfbl, T, R> {
+        let mut tfbl = self.derive();
+        tfbl.tint = tfbl.tint.mul_alpha(opacity);
+        tfbl
+    }
+    /**
+    Fill the draw with a texture
+
+    By default, the texture is stretched to fit the bounds of each shape.
+    This replaces any texture the draw already has.
+    */
+    pub fn texture<'tfbl>(&'tfbl mut self, texture: &Texture) -> Transformable<'ctx, 'tfbl, T, R> {
+        let mut tfbl = self.derive();
+        tfbl.texture = Some(texture.clone());
+        tfbl
+    }
+    /**
+    Set how many times the texture repeats across each shape on each axis
+
+    For the texture to actually repeat, its `TextureOptions::wrap` should
+    be `Wrap::Repeat` or `Wrap::Mirror`.
+    */
+    pub fn tile<'tfbl>(&'tfbl mut self, repetitions: Vec2) -> Transformable<'ctx, 'tfbl, T, R> {
+        let mut tfbl = self.derive();
+        tfbl.uv_scale = repetitions;
+        tfbl
+    }
+    /**
+    Draw with a custom shader
+
+    This overrides the drawer's current shader for this draw only.
+    If no shader with the given id has been loaded, the drawer's current shader is used.
+    */
+    pub fn shader<'tfbl>(
+        &'tfbl mut self,
+        shader_id: R::ShaderId,
+    ) -> Transformable<'ctx, 'tfbl, T, R> {
+        let mut tfbl = self.derive();
+        tfbl.shader = Some(shader_id);
+        tfbl
+    }
+    /// Draw in a different layer than the drawer's
+    pub fn layer<'tfbl>(&'tfbl mut self, layer: i32) -> Transformable<'ctx, 'tfbl, T, R> {
+        let mut tfbl = self.derive();
+        tfbl.layer = Some(layer);
+        tfbl
+    }
+    /// Draw with a different blend mode than the drawer's
+    pub fn blend<'tfbl>(
+        &'tfbl mut self,
+        blend_mode: BlendMode,
+    ) -> Transformable<'ctx, 'tfbl, T, R> {
+        let mut tfbl = self.derive();
+        tfbl.blend_mode = Some(blend_mode);
+        tfbl
+    }
+    /**
+    Modify the draw parameters used for this draw
+
+    The closure is passed the current draw parameters, which are the
+    drawer's unless they have already been modified for this draw.
+
+    The `blend` field is ignored in favor of the blend mode
+    */
+    pub fn draw_params<'tfbl, F>(&'tfbl mut self, f: F) -> Transformable<'ctx, 'tfbl, T, R>
+    where
+        F: FnOnce(DrawParameters<'ctx>) -> DrawParameters<'ctx>,
+    {
+        let mut tfbl = self.derive();
+        let params = tfbl
+            .draw_params
+            .take()
+            .unwrap_or_else(|| tfbl.drawer.draw_params.clone());
+        tfbl.draw_params = Some(f(params));
+        tfbl
+    }
+    /// Set the depth test
+    pub fn depth<'tfbl>(&'tfbl mut self, depth: Depth) -> Transformable<'ctx, 'tfbl, T, R> {
+        self.draw_params(|params| DrawParameters { depth, ..params })
+    }
+    /// Set which color channels are written to in the order `[r, g, b, a]`
+    pub fn color_mask<'tfbl>(&'tfbl mut self, mask: [bool; 4]) -> Transformable<'ctx, 'tfbl, T, R> {
+        let color_mask = (mask[0], mask[1], mask[2], mask[3]);
+        self.draw_params(|params| DrawParameters {
+            color_mask,
+            ..params
+        })
+    }
+    /// Set the polygon mode
+    ///
+    /// This can be used to draw wireframes
+    pub fn polygon_mode<'tfbl>(
+        &'tfbl mut self,
+        polygon_mode: PolygonMode,
+    ) -> Transformable<'ctx, 'tfbl, T, R> {
+        self.draw_params(|params| DrawParameters {
+            polygon_mode,
+            ..params
+        })
+    }
+    /**
+    Only draw within a rectangle
+
+    The rectangle is in window space
+    */
+    pub fn scissor<'tfbl, E>(&'tfbl mut self, rect: E) -> Transformable<'ctx, 'tfbl, T, R>
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let scissor = scissor_rect(rect.map(), self.drawer.camera, self.drawer.flip_y);
+        self.draw_params(|params| DrawParameters {
+            scissor: Some(scissor),
+            ..params
+        })
+    }
+    /// Set a uniform that is passed to the shader
+    pub fn uniform<'tfbl, U>(
+        &'tfbl mut self,
+        name: &str,
+        value: U,
+    ) -> Transformable<'ctx, 'tfbl, T, R>
+    where
+        U: Into<Uniform>,
+    {
+        let mut tfbl = self.derive();
+        set_uniform(Rc::make_mut(&mut tfbl.uniforms), name, value.into());
+        tfbl
+    }
+    /**
+    Set the pivot about which transformations are applied
+
+    Transformations are applied about the origin by default.
+    Only transformations applied after the pivot is set are affected.
+    */
+    pub fn pivot<'tfbl>(&'tfbl mut self, pivot: Pivot) -> Transformable<'ctx, 'tfbl, T, R> {
+        let mut tfbl = self.derive();
+        tfbl.pivot = Some(pivot);
+        tfbl
+    }
+    /// Set a point about which transformations are applied
+    pub fn anchor<'tfbl>(&'tfbl mut self, point: Vec2) -> Transformable<'ctx, 'tfbl, T, R> {
+        self.pivot(Pivot::Absolute(point))
+    }
+    /**
+    Apply a transformation
+
+    The function is passed the draw's current transformation, which includes
+    the shape's own transformation, and returns the new one.
+
+    If a [`Pivot`](enum.Pivot.html) is set, the transformation is applied about it.
+    The function is then passed the current transformation followed by a translation
+    that moves the pivot to the origin, and the result is translated back.
+    */
     pub fn transform<'tfbl, D>(
         &'tfbl mut self,
         transformation: D,
@@ -759,21 +2522,44 @@ where
     where
         D: Fn(Trans) -> Trans,
     {
-        self.drawn = true;
-        Transformable {
-            drawer: self.drawer,
-            items: Rc::clone(&self.items),
-            color: self.color,
-            transform: transformation(self.transform),
-            drawn: false,
-            border: self.border,
-        }
+        let pivot = self.pivot_point();
+        let mut tfbl = self.derive();
+        tfbl.transform = match pivot {
+            Some(pivot) => transformation(tfbl.transform.translate(pivot.neg())).translate(pivot),
+            None => transformation(tfbl.transform),
+        };
+        tfbl
     }
     /// Apply a translation
     pub fn translate<'tfbl>(&'tfbl mut self, offset: Vec2) -> Transformable<'ctx, 'tfbl, T, R> {
         self.transform(|t| t.translate(offset))
     }
-    /// Set a border
+    /// Apply a rotation about the origin
+    pub fn rotate<'tfbl>(&'tfbl mut self, radians: f32) -> Transformable<'ctx, 'tfbl, T, R> {
+        self.transform(|t| t.rotate(radians))
+    }
+    /// Apply a rotation about a pivot point
+    pub fn rotate_about<'tfbl>(
+        &'tfbl mut self,
+        radians: f32,
+        pivot: Vec2,
+    ) -> Transformable<'ctx, 'tfbl, T, R> {
+        self.transform(|t| t.rotate_about(radians, pivot))
+    }
+    /// Apply a scaling about the origin
+    pub fn scale<'tfbl>(&'tfbl mut self, ratio: Vec2) -> Transformable<'ctx, 'tfbl, T, R> {
+        self.transform(|t| t.scale(ratio))
+    }
+    /// Apply a uniform scaling about the origin
+    pub fn zoom<'tfbl>(&'tfbl mut self, ratio: f32) -> Transformable<'ctx, 'tfbl, T, R> {
+        self.transform(|t| t.zoom(ratio))
+    }
+    /**
+    Set a border
+
+    The border follows the outline of the drawn geometry on its outside.
+    Borders are not drawn when writing a mask.
+    */
     pub fn border<'tfbl, C>(
         &'tfbl mut self,
         color: C,
@@ -782,30 +2568,34 @@ where
     where
         C: Color,
     {
-        self.drawn = true;
-        Transformable {
-            drawer: self.drawer,
-            items: Rc::clone(&self.items),
-            color: self.color,
-            transform: self.transform,
-            drawn: false,
-            border: Some(Border {
-                color: color.map(),
-                thickness,
-            }),
-        }
+        let mut tfbl = self.derive();
+        tfbl.border = Some(Border {
+            color: color.map(),
+            thickness,
+        });
+        tfbl
     }
     /// Remove the border
     pub fn no_border<'tfbl>(&'tfbl mut self) -> Transformable<'ctx, 'tfbl, T, R> {
-        self.drawn = true;
-        Transformable {
-            drawer: self.drawer,
-            items: Rc::clone(&self.items),
-            color: self.color,
-            transform: self.transform,
-            drawn: false,
-            border: None,
-        }
+        let mut tfbl = self.derive();
+        tfbl.border = None;
+        tfbl
+    }
+    /**
+    Set the source rectangle of the texture in pixels
+
+    Only this part of the texture will be drawn. This allows a single
+    texture to be used as a sprite sheet.
+
+    This has no effect on untextured draws.
+    */
+    pub fn src<'tfbl, E>(&'tfbl mut self, src: E) -> Transformable<'ctx, 'tfbl, T, R>
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let mut tfbl = self.derive();
+        tfbl.src = Some(src.map());
+        tfbl
     }
     /**
     Execute the draw command
@@ -813,97 +2603,214 @@ where
     This is usually called automatically
     */
     pub fn draw(&mut self) {
-        let camera_transform = self.drawer.camera.transform();
-        for item in self.items.iter() {
-            let Drawer {
-                meshes,
-                facade,
-                fonts,
-                surface,
-                program,
-                draw_params,
-                ..
-            } = &mut self.drawer;
-            if !meshes.contains(&item.ty) {
-                let (vertices, indices) = item.ty.vertices_indices(*facade, fonts);
-                meshes.insert(item.ty, vertices, indices);
+        self.drawn = true;
+        let mut camera_transform = self.drawer.camera.transform();
+        // Textures are stored bottom row first, so drawing to one is flipped
+        if self.drawer.flip_y {
+            camera_transform = camera_transform.scale([1.0, -1.0]);
+        }
+        let window_size = self.drawer.camera.window_size;
+        let pixel_snap = self.drawer.pixel_snap;
+        let snap = |pos: Vec2| {
+            if pixel_snap {
+                snap_to_pixel(pos, window_size)
+            } else {
+                pos
             }
-            let (vertices, indices) = meshes.get(&item.ty).unwrap();
-            let world_transform = item.transform.then(self.transform);
+        };
+        let drawer = &*self.drawer;
+        let own_uniforms = &self.uniforms;
+        // Only merge uniforms when necessary so that most draws can share the drawer's list
+        let uniforms = if own_uniforms.is_empty() {
+            Rc::clone(&drawer.uniforms)
+        } else if drawer.uniforms.is_empty() {
+            Rc::clone(own_uniforms)
+        } else {
+            let mut uniforms: Vec<(String, Uniform)> = drawer
+                .uniforms
+                .iter()
+                .filter(|(name, _)| !own_uniforms.iter().any(|(n, _)| n == name))
+                .chain(own_uniforms.iter())
+                .cloned()
+                .collect();
+            uniforms.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Rc::new(uniforms)
+        };
+        let clip = drawer
+            .clip
+            .map(|clip| scissor_rect(clip, drawer.camera, drawer.flip_y));
+        let params = self
+            .draw_params
+            .clone()
+            .unwrap_or_else(|| drawer.draw_params.clone());
+        let custom_program = match (self.shader, self.program) {
+            (Some(id), _) if drawer.shaders.contains(id) => drawer.shaders.get(id),
+            (_, Some(_)) => None,
+            _ => drawer.custom_program(),
+        };
+        let base_state = BatchState {
+            program: custom_program.or(self.program).unwrap_or(drawer.program),
+            texture: drawer.blank.clone(),
+            uniforms,
+            blend_mode: self.blend_mode.unwrap_or(drawer.blend_mode),
+            scissor: match (params.scissor, clip) {
+                (Some(a), Some(b)) => Some(intersect_scissors(a, b)),
+                (a, b) => a.or(b),
+            },
+            stencil: if drawer.writing_mask {
+                StencilMode::WriteMask
+            } else {
+                drawer.mask.map_or(StencilMode::Normal, StencilMode::Masked)
+            },
+            params,
+            custom_params: self.draw_params.is_some(),
+            draw_uniforms: None,
+        };
+        let border_state = BatchState {
+            program: drawer.program,
+            texture: drawer.blank.clone(),
+            uniforms: Rc::default(),
+            ..base_state.clone()
+        };
+        let writing_mask = drawer.writing_mask;
+        let layer = self.layer.unwrap_or(drawer.layer);
+        let meshes = drawer.meshes;
+        self.cache_meshes();
+        let transform = self.transform;
+        for item in self.items.iter() {
+            let world_transform = item.transform.then(transform);
             let full_transform = world_transform.then(camera_transform);
-            let uniforms = uniform! {
-                transform: extend_transform(full_transform),
-                color: item.color.unwrap_or(self.color)
-            };
-            surface
-                .draw(&*vertices, &*indices, program, &uniforms, draw_params)
-                .unwrap();
-            // Draw border
-            if let Some(border) = self.border {
-                let bounding_rect = Rect::bounding(
-                    vertices
-                        .read()
-                        .unwrap()
-                        .iter()
-                        .map(|v| v.pos.transform(world_transform)),
-                );
-                if let Some(bounding_rect) = bounding_rect {
-                    let center = bounding_rect.center();
-                    let size = bounding_rect.size();
-                    let scale = size.add([border.thickness; 2]).div2(size);
-                    // Draw stencil
-                    let border_inner_transform = world_transform
-                        .translate(center.neg())
-                        .scale([1.0; 2].div2(scale))
-                        .translate(center)
-                        .then(camera_transform);
-                    let uniforms = uniform! {
-                        transform: extend_transform(border_inner_transform),
-                        color: [0f32; 4]
-                    };
-                    let draw_params = DrawParameters {
-                        stencil: draw_parameters::Stencil {
-                            reference_value_clockwise: 1,
-                            reference_value_counter_clockwise: 1,
-                            write_mask_clockwise: 0xffffffff,
-                            write_mask_counter_clockwise: 0xffffffff,
-                            depth_pass_operation_clockwise: StencilOperation::Replace,
-                            depth_pass_operation_counter_clockwise: StencilOperation::Replace,
-                            ..Default::default()
-                        },
-                        ..draw_params.clone()
-                    };
-                    surface
-                        .draw(&*vertices, &*indices, program, &uniforms, &draw_params)
-                        .unwrap();
-                    // Draw border
-                    let border_outer_transform = world_transform
-                        .translate(center.neg())
-                        .scale(scale)
-                        .translate(center)
-                        .then(camera_transform);
-                    let uniforms = uniform! {
-                        transform: extend_transform(border_outer_transform),
-                        color: border.color
+            let texture = self
+                .texture
+                .as_ref()
+                .or(item.texture.as_ref())
+                .unwrap_or(self.drawer.blank);
+            let tex_rect: [f32; 4] = self.src.or(item.src).map_or([0.0, 0.0, 1.0, 1.0], |src| {
+                let size: Vec2 = texture.size().map_with(|d| d as f32);
+                [
+                    src.left() / size.x(),
+                    src.top() / size.y(),
+                    src.width() / size.x(),
+                    src.height() / size.y(),
+                ]
+            });
+            let uv_offset = [tex_rect[0], tex_rect[1]];
+            let uv_scale = [tex_rect[2], tex_rect[3]].mul2(self.uv_scale);
+            let color = item.color.unwrap_or(self.color).mul_color(self.tint);
+            let own_mesh = item.own_mesh(self.drawer.fonts);
+            {
+                let mesh_ref;
+                let (vertices, indices): (&[Vertex], &[u32]) = if let Some(mesh) = &own_mesh {
+                    (mesh.vertices(), mesh.indices())
+                } else {
+                    mesh_ref = meshes.get(&item.ty).unwrap();
+                    (&mesh_ref.0, &mesh_ref.1)
+                };
+                if custom_program.is_some() {
+                    // Custom shaders transform the vertices themselves
+                    let state = BatchState {
+                        texture: texture.clone(),
+                        draw_uniforms: Some(DrawUniforms {
+                            transform: full_transform,
+                            color,
+                            tex_rect: [uv_offset[0], uv_offset[1], uv_scale[0], uv_scale[1]],
+                        }),
+                        ..base_state.clone()
                     };
-                    let draw_params = DrawParameters {
-                        stencil: draw_parameters::Stencil {
-                            reference_value_clockwise: 1,
-                            reference_value_counter_clockwise: 1,
-                            test_clockwise: StencilTest::IfNotEqual { mask: 0xffffffff },
-                            test_counter_clockwise: StencilTest::IfNotEqual { mask: 0xffffffff },
-                            ..Default::default()
-                        },
-                        ..draw_params.clone()
+                    self.drawer
+                        .push_batch(layer, state, vertices.iter().copied(), indices);
+                } else {
+                    let state = BatchState {
+                        texture: texture.clone(),
+                        ..base_state.clone()
                     };
-                    surface
-                        .draw(&*vertices, &*indices, program, &uniforms, &draw_params)
-                        .unwrap();
-                    surface.clear_stencil(0);
+                    self.drawer.push_batch(
+                        layer,
+                        state,
+                        vertices.iter().map(|v| Vertex {
+                            pos: snap(v.pos.transform(full_transform)),
+                            uv: uv_offset.add(v.uv.mul2(uv_scale)),
+                            color: v.color.mul_color(color),
+                        }),
+                        indices,
+                    );
                 }
             }
+            // Draw border
+            if let Some(border) = item.border.or(self.border).filter(|_| !writing_mask) {
+                let (mut positions, mut indices) = std::mem::take(&mut self.drawer.border_buffers);
+                positions.clear();
+                indices.clear();
+                let outline = match &own_mesh {
+                    Some(mesh) => mesh.outline(),
+                    None => meshes.outline(&item.ty).unwrap(),
+                };
+                outline.border(
+                    world_transform,
+                    border.thickness,
+                    &mut positions,
+                    &mut indices,
+                );
+                let border_color = border.color.mul_alpha(self.tint.alpha());
+                self.drawer.push_batch(
+                    layer,
+                    border_state.clone(),
+                    positions.iter().map(|pos| Vertex {
+                        pos: snap(pos.transform(camera_transform)),
+                        uv: [0.0; 2],
+                        color: border_color,
+                    }),
+                    &indices,
+                );
+                self.drawer.border_buffers = (positions, indices);
+            }
         }
-        self.drawn = true;
+    }
+    /// Make sure that the geometry of every item is cached
+    fn cache_meshes(&self) {
+        let meshes = self.drawer.meshes;
+        for item in self.items.iter() {
+            let owned = item.mesh.is_some() || matches!(item.ty, DrawType::Character { .. });
+            if !owned && !meshes.contains(&item.ty) {
+                let (vertices, indices) = item.ty.vertices_indices();
+                meshes.insert(item.ty, vertices, indices);
+            }
+        }
+    }
+    /// Get the position of the pivot with the current transformation, if there is one
+    fn pivot_point(&self) -> Option<Vec2> {
+        let relative = match self.pivot? {
+            Pivot::Center => [0.5, 0.5],
+            Pivot::TopLeft => [0.0, 0.0],
+            Pivot::TopRight => [1.0, 0.0],
+            Pivot::BottomLeft => [0.0, 1.0],
+            Pivot::BottomRight => [1.0, 1.0],
+            Pivot::Relative(relative) => relative,
+            Pivot::Absolute(point) => return Some(point),
+        };
+        self.cache_meshes();
+        let meshes = self.drawer.meshes;
+        let mut min = [f32::INFINITY; 2];
+        let mut max = [f32::NEG_INFINITY; 2];
+        for item in self.items.iter() {
+            let mut add = |pos: Vec2| {
+                let pos = pos.transform(item.transform);
+                min = [min[0].min(pos[0]), min[1].min(pos[1])];
+                max = [max[0].max(pos[0]), max[1].max(pos[1])];
+            };
+            if let Some(mesh) = item.own_mesh(self.drawer.fonts) {
+                mesh.vertices().iter().for_each(|v| add(v.pos));
+            } else if let Some(mesh) = meshes.get(&item.ty) {
+                mesh.0.iter().for_each(|v| add(v.pos));
+            }
+        }
+        if min[0] > max[0] {
+            return None;
+        }
+        Some(
+            min.add(max.sub(min).mul2(relative))
+                .transform(self.transform),
+        )
     }
     fn new(
         drawer: &'drawer mut Drawer<'ctx, T, R>,
@@ -918,11 +2825,15 @@ where
                 ty,
                 transform: Trans::identity(),
                 color: None,
+                texture: None,
+                src: None,
+                mesh: None,
+                border: None,
             }),
             transform,
         )
     }
-    fn multi<I>(
+    pub(crate) fn multi<I>(
         drawer: &'drawer mut Drawer<'ctx, T, R>,
         color: Col,
         items: I,
@@ -931,13 +2842,48 @@ where
     where
         I: IntoIterator<Item = DrawItem<R>>,
     {
+        let mut items = items.into_iter();
+        let items = match (items.next(), items.next()) {
+            (Some(item), None) => DrawItems::Single(item),
+            (first, second) => DrawItems::Shared(Rc::new(
+                first.into_iter().chain(second).chain(items).collect(),
+            )),
+        };
+        Transformable::with_items(drawer, color, items, transform)
+    }
+    /// Create a new draw command from items that may be shared with other draws
+    pub(crate) fn shared(
+        drawer: &'drawer mut Drawer<'ctx, T, R>,
+        color: Col,
+        items: Rc<Vec<DrawItem<R>>>,
+        transform: Trans,
+    ) -> Self {
+        Transformable::with_items(drawer, color, DrawItems::Shared(items), transform)
+    }
+    fn with_items(
+        drawer: &'drawer mut Drawer<'ctx, T, R>,
+        color: Col,
+        items: DrawItems<R>,
+        transform: Trans,
+    ) -> Self {
         Transformable {
             drawer,
-            items: Rc::new(items.into_iter().collect()),
+            items,
             color,
             transform,
+            pivot: None,
             drawn: false,
             border: None,
+            src: None,
+            tint: Col::white(),
+            texture: None,
+            uv_scale: [1.0; 2],
+            uniforms: Rc::default(),
+            shader: None,
+            program: None,
+            blend_mode: None,
+            draw_params: None,
+            layer: None,
         }
     }
 }
@@ -953,20 +2899,3 @@ where
         }
     }
 }
-
-pub(crate) fn default_shaders<F>(facade: &F) -> Program
-where
-    F: Facade,
-{
-    Program::new(
-        facade,
-        program::SourceCode {
-            vertex_shader: include_str!("shaders/vertex.vert"),
-            fragment_shader: include_str!("shaders/fragment.frag"),
-            tessellation_control_shader: None,
-            tessellation_evaluation_shader: None,
-            geometry_shader: None,
-        },
-    )
-    .unwrap_or_else(|e| panic!("{}", e))
-}
@@ -20,10 +20,81 @@ pub enum KuleError {
     /// Bad window icon data
     #[error("{0}")]
     BadIcon(#[from] glium::glutin::window::BadIcon),
+    /// Error compiling a shader program
+    #[error("{0}")]
+    ProgramCreation(#[from] glium::ProgramCreationError),
+    /// Error creating a texture
+    #[error("{0}")]
+    TextureCreation(#[from] glium::texture::TextureCreationError),
+    /// Error from an operation the platform does not support
+    #[error("{0}")]
+    External(#[from] glium::glutin::error::ExternalError),
+    /// A custom event was sent after the event loop closed
+    #[error("The event loop has closed")]
+    EventLoopClosed,
+    /// A key name that could not be parsed
+    #[error("Invalid key {0:?}")]
+    InvalidKey(String),
+    /// A keyboard shortcut that could not be parsed
+    #[error("Invalid shortcut {0:?}")]
+    InvalidShortcut(String),
+    /// No installed font has the given name
+    #[error("No installed font named {0:?} was found")]
+    FontNotFound(String),
+    /// Raw image data with the wrong length
+    #[error("Expected {expected} bytes of image data, but got {found}")]
+    ImageDataLength {
+        /// The expected number of bytes
+        expected: usize,
+        /// The actual number of bytes
+        found: usize,
+    },
+    /// A texture region that is not inside the texture
+    #[error("The region {region:?} is outside of the {size:?} texture")]
+    TextureRegion {
+        /// The region as `[left, top, width, height]`
+        region: [u32; 4],
+        /// The size of the texture
+        size: [u32; 2],
+    },
+    /// Error creating a vertex buffer
+    #[error("{0}")]
+    VertexBufferCreation(#[from] glium::vertex::BufferCreationError),
+    /// Error creating an index buffer
+    #[error("{0}")]
+    IndexBufferCreation(#[from] glium::index::BufferCreationError),
+    /// Error drawing
+    #[error("{0}")]
+    Draw(#[from] glium::DrawError),
+    /// Error tessellating a path
+    #[error("Tessellation failed: {0:?}")]
+    Tessellation(lyon_tessellation::TessellationError),
+    /// Error creating a render buffer
+    #[error("{0}")]
+    RenderBufferCreation(#[from] glium::framebuffer::RenderBufferCreationError),
+    /// Error creating a framebuffer
+    #[error("{0}")]
+    Framebuffer(#[from] glium::framebuffer::ValidationError),
+    #[cfg(feature = "image")]
+    /// Image decode error
+    #[error("{0}")]
+    Image(#[from] image::ImageError),
+    #[cfg(feature = "svg")]
+    /// SVG parsing error
+    #[error("{0}")]
+    Svg(#[from] usvg::Error),
     #[cfg(feature = "sound")]
     /// Audio decode error
     #[error("{0}")]
     AudioDecode(#[from] rodio::decoder::DecoderError),
+    #[cfg(feature = "clipboard")]
+    /// A clipboard error
+    #[error("{0}")]
+    Clipboard(#[from] arboard::Error),
+    #[cfg(feature = "gamepad")]
+    /// A gamepad force feedback error
+    #[error("{0}")]
+    ForceFeedback(#[from] gilrs::ff::Error),
     #[cfg(feature = "script")]
     /// A toml serialization error
     #[error("{0}")]
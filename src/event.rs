@@ -1,4 +1,10 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    hash::Hash,
+    str::FromStr,
+    time::Instant,
+};
 
 use glutin::event::{self, *};
 use vector2math::*;
@@ -8,6 +14,7 @@ use crate::{Camera, Vec2};
 pub use event::ElementState as ButtonState;
 pub use event::ModifiersState as Modifiers;
 pub use event::MouseButton;
+pub use event::TouchPhase;
 
 /// An input event
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -20,6 +27,14 @@ pub enum Event {
     MouseAbsolute(Vec2),
     /// The mouse cursor's relative position has changed
     MouseRelative(Vec2),
+    /**
+    The mouse moved
+
+    This is the raw motion of the mouse, without acceleration. Unlike
+    `Event::MouseRelative`, it is not limited by the edges of the screen,
+    so it should be used for camera controls when the cursor is grabbed.
+    */
+    MouseDelta(Vec2),
     /// A mouse button's state has changed
     MouseButton {
         /// The mouse button
@@ -38,12 +53,48 @@ pub enum Event {
     },
     /// The window was resized
     Resize(Vec2),
+    /**
+    The window's scale factor changed
+
+    This happens when the window moves to a monitor with a different pixel density
+    or the user changes their display settings.
+    */
+    ScaleFactor(f32),
     /// The window was moved
     Move(Vec2),
     /// The window has gained or lost focus
     Focus(bool),
     /// The mouse wheel was scrolled
     Scroll(Vec2),
+    /**
+    A finger touched, moved on, or left a touchscreen
+
+    Use a [`GestureRecognizer`](struct.GestureRecognizer.html) to turn these into gestures.
+    */
+    Touch {
+        /// The id of the finger, which is unique while it is touching
+        id: u64,
+        /// The phase of the touch
+        phase: TouchPhase,
+        /// The position in window space
+        pos: Vec2,
+    },
+    /**
+    A character was typed
+
+    Unlike `Event::Key`, this respects the keyboard layout, dead keys,
+    and text committed by an input method, so it should be used for text input.
+    Control characters like backspace are sent as well.
+    */
+    ReceivedCharacter(char),
+    #[cfg(feature = "gamepad")]
+    /// A gamepad input event
+    Gamepad {
+        /// The id of the gamepad
+        id: usize,
+        /// The event
+        event: crate::GamepadEvent,
+    },
     /// The window was requested to close
     CloseRequest,
 }
@@ -54,44 +105,70 @@ impl Event {
         tracker: &mut StateTracker,
         camera: &mut Camera,
     ) -> Two<Self> {
-        let window_event = if let event::Event::WindowEvent { event, .. } = event {
-            event
-        } else {
-            return Two::none();
+        let window_event = match event {
+            event::Event::WindowEvent { event, .. } => event,
+            event::Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } => return Event::MouseDelta([x as f32, y as f32]).into(),
+            _ => return Two::none(),
         };
         match window_event {
             WindowEvent::CloseRequested => Event::CloseRequest.into(),
             WindowEvent::Resized(size) => {
-                let size = [size.width as f32, size.height as f32];
-                camera.window_size = size;
-                Event::Resize(size).into()
+                camera.window_size = [size.width as f32, size.height as f32];
+                Event::Resize(camera.window_size()).into()
+            }
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size,
+            } => {
+                camera.window_size = [new_inner_size.width as f32, new_inner_size.height as f32];
+                Event::ScaleFactor(scale_factor as f32).into()
             }
             WindowEvent::Moved(size) => Event::Move([size.x as f32, size.y as f32]).into(),
             WindowEvent::Focused(foc) => Event::Focus(foc).into(),
             WindowEvent::CursorMoved { position, .. } => {
-                let pos = [position.x as f32, position.y as f32];
+                let pos = [position.x as f32, position.y as f32].div(camera.scale);
                 let two = Two::two(
                     Event::MouseAbsolute(pos),
                     Event::MouseRelative(pos.sub(tracker.mouse_pos)),
                 );
-                tracker.mouse_pos = pos;
+                tracker.apply(&Event::MouseAbsolute(pos));
                 two
             }
             WindowEvent::MouseInput { button, state, .. } => {
-                match state {
-                    ButtonState::Pressed => tracker.mouse_buttons.insert(button),
-                    ButtonState::Released => tracker.mouse_buttons.remove(&button),
-                };
-                Event::MouseButton { button, state }.into()
+                let event = Event::MouseButton { button, state };
+                tracker.apply(&event);
+                event.into()
             }
             WindowEvent::MouseWheel {
                 delta: MouseScrollDelta::LineDelta(x, y),
                 ..
-            } => Event::Scroll([x, y]).into(),
+            } => {
+                tracker.scroll.add([x, y]);
+                Event::Scroll([x, y]).into()
+            }
             WindowEvent::MouseWheel {
                 delta: MouseScrollDelta::PixelDelta(pos),
                 ..
-            } => Event::Scroll([pos.x as f32, pos.y as f32]).into(),
+            } => {
+                let pixels = [pos.x as f32, pos.y as f32];
+                tracker
+                    .scroll
+                    .add(pixels.div(tracker.scroll.pixels_per_line));
+                Event::Scroll(pixels).into()
+            }
+            WindowEvent::Touch(touch) => {
+                let event = Event::Touch {
+                    id: touch.id,
+                    phase: touch.phase,
+                    pos: [touch.location.x as f32, touch.location.y as f32].div(camera.scale),
+                };
+                tracker.apply(&event);
+                event.into()
+            }
+            WindowEvent::ReceivedCharacter(ch) => Event::ReceivedCharacter(ch).into(),
             WindowEvent::ModifiersChanged(modifiers) => {
                 tracker.modifiers = modifiers;
                 Two::none()
@@ -101,20 +178,116 @@ impl Event {
                     .virtual_keycode
                     .map(Key::from_glutin)
                     .unwrap_or(Key::Unknown);
-                match input.state {
-                    ButtonState::Pressed => tracker.keys.insert(key),
-                    ButtonState::Released => tracker.keys.remove(&key),
-                };
-                Event::Key {
+                let event = Event::Key {
                     key,
                     scancode: input.scancode,
                     state: input.state,
-                }
-                .into()
+                };
+                tracker.apply(&event);
+                event.into()
             }
             _ => Two::none(),
         }
     }
+    /// Check if a glutin event is an input event that would update the tracker
+    pub(crate) fn glutin_is_input(event: &event::Event<()>) -> bool {
+        match event {
+            event::Event::WindowEvent { event, .. } => matches!(
+                event,
+                WindowEvent::CursorMoved { .. }
+                    | WindowEvent::MouseInput { .. }
+                    | WindowEvent::MouseWheel { .. }
+                    | WindowEvent::Touch(_)
+                    | WindowEvent::ReceivedCharacter(_)
+                    | WindowEvent::ModifiersChanged(_)
+                    | WindowEvent::KeyboardInput { .. }
+            ),
+            event::Event::DeviceEvent { .. } => true,
+            _ => false,
+        }
+    }
+    /// Check if the event is from the mouse, keyboard, a touchscreen, or a gamepad
+    pub fn is_input(&self) -> bool {
+        !matches!(
+            self,
+            Event::Resize(_)
+                | Event::ScaleFactor(_)
+                | Event::Move(_)
+                | Event::Focus(_)
+                | Event::CloseRequest
+        )
+    }
+}
+
+/// Accumulates scrolling in lines
+#[derive(Debug, Clone)]
+struct ScrollAccumulator {
+    /// How many pixels of pixel scrolling make up one line
+    pixels_per_line: f32,
+    /// The scrolling since the last update
+    delta: Vec2,
+    /// The scrolling since the app started
+    total: Vec2,
+}
+
+impl Default for ScrollAccumulator {
+    fn default() -> Self {
+        ScrollAccumulator {
+            pixels_per_line: 20.0,
+            delta: [0.0; 2],
+            total: [0.0; 2],
+        }
+    }
+}
+
+impl ScrollAccumulator {
+    fn add(&mut self, lines: Vec2) {
+        self.delta = self.delta.add(lines);
+        self.total = self.total.add(lines);
+    }
+}
+
+/// Counts consecutive clicks of mouse buttons
+#[derive(Debug, Clone)]
+struct ClickCounter {
+    /// The longest time in seconds between clicks that are counted together
+    time: f32,
+    /// The furthest distance in pixels between clicks that are counted together
+    distance: f32,
+    /// The last button pressed, when, and where
+    last: Option<(MouseButton, Instant, Vec2)>,
+    counts: HashMap<MouseButton, u8>,
+}
+
+impl Default for ClickCounter {
+    fn default() -> Self {
+        ClickCounter {
+            time: 0.5,
+            distance: 4.0,
+            last: None,
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl ClickCounter {
+    fn press(&mut self, button: MouseButton, pos: Vec2) {
+        let now = Instant::now();
+        let continues = self.last.into_iter().any(|(last, time, last_pos)| {
+            last == button
+                && (now - time).as_secs_f32() <= self.time
+                && last_pos.dist(pos) <= self.distance
+        });
+        let count = if continues {
+            self.counts
+                .get(&button)
+                .map_or(1, |count| count.saturating_add(1))
+        } else {
+            1
+        };
+        self.counts.insert(button, count);
+        self.last = Some((button, now, pos));
+    }
 }
 
 /**
@@ -132,6 +305,28 @@ pub struct StateTracker {
     modifiers: Modifiers,
     keys: HashSet<Key>,
     mouse_buttons: HashSet<MouseButton>,
+    /// Keys pressed and released recently, and how many updates ago
+    pressed_keys: HashMap<Key, u32>,
+    released_keys: HashMap<Key, u32>,
+    /// Mouse buttons pressed and released recently, and how many updates ago
+    pressed_mouse_buttons: HashMap<MouseButton, u32>,
+    released_mouse_buttons: HashMap<MouseButton, u32>,
+    /// How many updates presses and releases are remembered for after the one they happen in
+    input_buffer: u32,
+    touches: BTreeMap<u64, Vec2>,
+    #[cfg_attr(feature = "ser", serde(skip))]
+    clicks: ClickCounter,
+    #[cfg_attr(feature = "ser", serde(skip))]
+    scroll: ScrollAccumulator,
+    pub(crate) input_map: crate::InputMap,
+    /// When each held key was pressed
+    #[cfg_attr(feature = "ser", serde(skip))]
+    key_times: HashMap<Key, Instant>,
+    /// When each held mouse button was pressed
+    #[cfg_attr(feature = "ser", serde(skip))]
+    mouse_button_times: HashMap<MouseButton, Instant>,
+    #[cfg(feature = "gamepad")]
+    pub(crate) gamepads: BTreeMap<usize, crate::GamepadState>,
     pub(crate) fps: f32,
 }
 
@@ -152,22 +347,233 @@ impl StateTracker {
     pub fn mouse_button(&self, mb: MouseButton) -> bool {
         self.mouse_buttons.contains(&mb)
     }
+    /// Get how long a key has been held in seconds, or `0.0` if it is not held
+    pub fn held_time(&self, key: Key) -> f32 {
+        self.key_times
+            .get(&key)
+            .map_or(0.0, |time| time.elapsed().as_secs_f32())
+    }
+    /// Get how long a mouse button has been held in seconds, or `0.0` if it is not held
+    pub fn mouse_button_held_time(&self, mb: MouseButton) -> f32 {
+        self.mouse_button_times
+            .get(&mb)
+            .map_or(0.0, |time| time.elapsed().as_secs_f32())
+    }
+    /// Check if a key was pressed since the last update
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.pressed_keys.contains_key(&key)
+    }
+    /// Check if a key was released since the last update
+    pub fn key_released(&self, key: Key) -> bool {
+        self.released_keys.contains_key(&key)
+    }
+    /// Check if a mouse button was pressed since the last update
+    pub fn mouse_button_pressed(&self, mb: MouseButton) -> bool {
+        self.pressed_mouse_buttons.contains_key(&mb)
+    }
+    /// Check if a mouse button was released since the last update
+    pub fn mouse_button_released(&self, mb: MouseButton) -> bool {
+        self.released_mouse_buttons.contains_key(&mb)
+    }
+    /// Get how many updates presses and releases are remembered for after the one they happen in
+    pub fn input_buffer(&self) -> u32 {
+        self.input_buffer
+    }
+    /**
+    Set how many updates presses and releases are remembered for after the one they happen in
+
+    With a buffer, methods like [`key_pressed`](struct.StateTracker.html#method.key_pressed)
+    keep returning `true` for a few updates after the press. This gives players some
+    leniency, like in fighting games where a move can be input slightly before the
+    previous one finishes. Use [`consume_press`](struct.StateTracker.html#method.consume_press)
+    so that a buffered press is only acted on once.
+
+    The default is `0`.
+    */
+    pub fn set_input_buffer(&mut self, updates: u32) {
+        self.input_buffer = updates;
+    }
+    /**
+    Forget that an input was pressed so that it is not acted on again
+
+    Returns whether the input was pressed recently.
+    */
+    pub fn consume_press(&mut self, input: crate::Input) -> bool {
+        match input {
+            crate::Input::Key(key) => self.pressed_keys.remove(&key).is_some(),
+            crate::Input::MouseButton(mb) => self.pressed_mouse_buttons.remove(&mb).is_some(),
+            #[cfg(feature = "gamepad")]
+            crate::Input::GamepadButton(button) => {
+                let mut consumed = false;
+                for state in self.gamepads.values_mut() {
+                    consumed |= state.consume_press(button);
+                }
+                consumed
+            }
+        }
+    }
+    /**
+    Get the number of clicks in the most recent series of presses of a mouse button
+
+    Presses are in the same series if they are close enough together in
+    time and position. This is `2` for the press of a double click, `3` for
+    a triple click, and so on. It is `0` if the button has never been pressed.
+    */
+    pub fn clicks(&self, mb: MouseButton) -> u8 {
+        self.clicks.counts.get(&mb).copied().unwrap_or(0)
+    }
+    /**
+    Set how close together presses of a mouse button must be to count as multiple clicks
+
+    `time` is in seconds and `distance` is in pixels. The defaults are `0.5` and `4.0`.
+    */
+    pub fn set_multi_click_threshold(&mut self, time: f32, distance: f32) {
+        self.clicks.time = time;
+        self.clicks.distance = distance;
+    }
+    /**
+    Get how far the mouse wheel has scrolled since the last update, in lines
+
+    Unlike `Event::Scroll`, this is in the same units for both mouse wheels
+    and touchpads. Scrolling in pixels is converted to lines with the
+    tracker's [pixels per line](struct.StateTracker.html#method.set_pixels_per_line).
+    */
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll.delta
+    }
+    /// Get how far the mouse wheel has scrolled since the app started, in lines
+    pub fn scroll(&self) -> Vec2 {
+        self.scroll.total
+    }
+    /// Get how many pixels of touchpad scrolling make up one line of mouse wheel scrolling
+    pub fn pixels_per_line(&self) -> f32 {
+        self.scroll.pixels_per_line
+    }
+    /// Set how many pixels of touchpad scrolling make up one line of mouse wheel scrolling
+    ///
+    /// The default is `20.0`.
+    pub fn set_pixels_per_line(&mut self, pixels_per_line: f32) {
+        self.scroll.pixels_per_line = pixels_per_line;
+    }
+    /**
+    Update the tracked state from an event
+
+    Scrolling and modifier keys are not updated, because scroll events
+    do not say what units they are in and modifiers are not events.
+    */
+    pub(crate) fn apply(&mut self, event: &Event) {
+        match *event {
+            Event::MouseAbsolute(pos) => self.mouse_pos = pos,
+            Event::MouseButton {
+                button,
+                state: ButtonState::Pressed,
+            } => {
+                if self.mouse_buttons.insert(button) {
+                    self.pressed_mouse_buttons.insert(button, 0);
+                    self.mouse_button_times.insert(button, Instant::now());
+                    self.capture_press(button.into());
+                }
+                self.clicks.press(button, self.mouse_pos);
+            }
+            Event::MouseButton {
+                button,
+                state: ButtonState::Released,
+            } => {
+                if self.mouse_buttons.remove(&button) {
+                    self.released_mouse_buttons.insert(button, 0);
+                }
+                self.mouse_button_times.remove(&button);
+            }
+            Event::Key {
+                key,
+                state: ButtonState::Pressed,
+                ..
+            } => {
+                // Held keys repeat their presses, which do not count as new presses
+                let repeat = !self.keys.insert(key);
+                if !repeat {
+                    self.pressed_keys.insert(key, 0);
+                    self.key_times.insert(key, Instant::now());
+                    self.capture_press(key.into());
+                }
+            }
+            Event::Key {
+                key,
+                state: ButtonState::Released,
+                ..
+            } => {
+                if self.keys.remove(&key) {
+                    self.released_keys.insert(key, 0);
+                }
+                self.key_times.remove(&key);
+            }
+            Event::Touch { id, phase, pos } => match phase {
+                TouchPhase::Started | TouchPhase::Moved => {
+                    self.touches.insert(id, pos);
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    self.touches.remove(&id);
+                }
+            },
+            #[cfg(feature = "gamepad")]
+            Event::Gamepad { id, event } => self.apply_gamepad(id, event),
+            _ => {}
+        }
+    }
+    /// Update the tracked state from a recorded event
+    pub(crate) fn replay(&mut self, recorded: &crate::RecordedEvent) {
+        self.modifiers = recorded.modifiers;
+        self.scroll.add(recorded.scroll);
+        self.apply(&recorded.event);
+    }
+    /**
+    Forget which keys and buttons were pressed and released and how far the mouse wheel scrolled,
+    which happens after every update
+
+    Presses and releases are kept until they are older than the input buffer.
+    */
+    pub(crate) fn end_update(&mut self) {
+        let buffer = self.input_buffer;
+        self.scroll.delta = [0.0; 2];
+        age_inputs(&mut self.pressed_keys, buffer);
+        age_inputs(&mut self.released_keys, buffer);
+        age_inputs(&mut self.pressed_mouse_buttons, buffer);
+        age_inputs(&mut self.released_mouse_buttons, buffer);
+        #[cfg(feature = "gamepad")]
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.end_update(buffer);
+        }
+    }
+    /// Get the ids and window space positions of the fingers touching the screen
+    pub fn touches(&self) -> impl Iterator<Item = (u64, Vec2)> + '_ {
+        self.touches.iter().map(|(&id, &pos)| (id, pos))
+    }
     /**
     Get a scalar representing the difference between two key states
 
     This is useful for times when you need two keys to represent different
     directions of some control, i.e. zooming in and out with +-.
+
+    Mouse buttons and gamepad buttons can be used as well as keys.
     */
-    pub fn key_diff_scalar(&self, neg: Key, pos: Key) -> f32 {
-        self.key(pos) as i8 as f32 - self.key(neg) as i8 as f32
+    pub fn key_diff_scalar<I>(&self, neg: I, pos: I) -> f32
+    where
+        I: Into<crate::Input>,
+    {
+        self.input(pos.into()) as i8 as f32 - self.input(neg.into()) as i8 as f32
     }
     /**
     Get a vector representing the difference between two pairs of key states
 
     This is useful for times when you need four keys to represent different
     directions of some control, i.e. controlling a character with WASD.
+
+    Mouse buttons and gamepad buttons can be used as well as keys.
     */
-    pub fn key_diff_vector(&self, left: Key, right: Key, up: Key, down: Key) -> Vec2 {
+    pub fn key_diff_vector<I>(&self, left: I, right: I, up: I, down: I) -> Vec2
+    where
+        I: Into<crate::Input>,
+    {
         [
             self.key_diff_scalar(left, right),
             self.key_diff_scalar(up, down),
@@ -179,6 +585,17 @@ impl StateTracker {
     }
 }
 
+/// Age recent presses or releases by one update and forget the ones older than the buffer
+pub(crate) fn age_inputs<T>(inputs: &mut HashMap<T, u32>, buffer: u32)
+where
+    T: Eq + Hash,
+{
+    inputs.retain(|_, age| {
+        *age += 1;
+        *age <= buffer
+    });
+}
+
 pub(crate) struct Two<T>(Option<T>, Option<T>);
 
 impl<T> Two<T> {
@@ -225,6 +642,20 @@ macro_rules! keys {
                     $(event::VirtualKeyCode::$glutinkey => Key::$key),*
                 }
             }
+            /// Get the key's name
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Key::$key => stringify!($key),)*
+                    Key::Unknown => "Unknown",
+                }
+            }
+            /// Get a key from its name, ignoring case
+            pub fn from_name(name: &str) -> Option<Self> {
+                $(if name.eq_ignore_ascii_case(stringify!($key)) {
+                    return Some(Key::$key);
+                })*
+                None
+            }
         }
     };
 }
@@ -392,3 +823,132 @@ keys!(
     (Paste, Paste),
     (Cut, Cut),
 );
+
+/// Keys that type a character regardless of shift, other than letters and digits
+const KEY_CHARS: &[(Key, char)] = &[
+    (Key::Space, ' '),
+    (Key::Apostrophe, '\''),
+    (Key::Backslash, '\\'),
+    (Key::Comma, ','),
+    (Key::Equals, '='),
+    (Key::Grave, '`'),
+    (Key::LBracket, '['),
+    (Key::RBracket, ']'),
+    (Key::Minus, '-'),
+    (Key::Period, '.'),
+    (Key::Semicolon, ';'),
+    (Key::Slash, '/'),
+    (Key::Colon, ':'),
+    (Key::At, '@'),
+    (Key::Caret, '^'),
+    (Key::Underline, '_'),
+    (Key::Yen, '¥'),
+];
+
+/// Names to show to users for keys whose enum names are unclear
+const KEY_LABELS: &[(Key, &str)] = &[
+    (Key::Escape, "Esc"),
+    (Key::Back, "Backspace"),
+    (Key::Left, "Left Arrow"),
+    (Key::Right, "Right Arrow"),
+    (Key::Up, "Up Arrow"),
+    (Key::Down, "Down Arrow"),
+    (Key::PageUp, "Page Up"),
+    (Key::PageDown, "Page Down"),
+    (Key::Snapshot, "Print Screen"),
+    (Key::Scroll, "Scroll Lock"),
+    (Key::Numlock, "Num Lock"),
+    (Key::Capital, "Caps Lock"),
+    (Key::LControl, "Left Ctrl"),
+    (Key::RControl, "Right Ctrl"),
+    (Key::LShift, "Left Shift"),
+    (Key::RShift, "Right Shift"),
+    (Key::LAlt, "Left Alt"),
+    (Key::RAlt, "Right Alt"),
+    (Key::LWin, "Left Super"),
+    (Key::RWin, "Right Super"),
+    (Key::Apps, "Menu"),
+    (Key::Add, "Numpad +"),
+    (Key::Subtract, "Numpad -"),
+    (Key::Multiply, "Numpad *"),
+    (Key::Divide, "Numpad /"),
+    (Key::Decimal, "Numpad ."),
+    (Key::NumpadComma, "Numpad ,"),
+    (Key::NumpadEnter, "Numpad Enter"),
+    (Key::NumpadEquals, "Numpad ="),
+    (Key::PlayPause, "Play/Pause"),
+    (Key::NextTrack, "Next Track"),
+    (Key::PrevTrack, "Previous Track"),
+    (Key::VolumeUp, "Volume Up"),
+    (Key::VolumeDown, "Volume Down"),
+];
+
+impl Key {
+    /**
+    Get the key that types a character without shift
+
+    Keys are matched as on a US keyboard layout, except for keys that are
+    only on other layouts, like `Yen` and `At`. Letters are matched regardless of case.
+    */
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '0'..='9' => Key::from_name(&format!("Num{}", c)),
+            'a'..='z' | 'A'..='Z' => Key::from_name(&c.to_string()),
+            _ => KEY_CHARS
+                .iter()
+                .find(|&&(_, kc)| kc == c)
+                .map(|&(key, _)| key),
+        }
+    }
+    /**
+    Get the character that the key types without shift
+
+    Keys are matched as on a US keyboard layout, except for keys that are
+    only on other layouts, like `Yen` and `At`. Letters are uppercase.
+    Numpad keys do not have characters.
+    */
+    pub fn to_char(self) -> Option<char> {
+        let name = self.name();
+        match name.as_bytes() {
+            [c] if c.is_ascii_uppercase() => Some(*c as char),
+            [b'N', b'u', b'm', d] if d.is_ascii_digit() => Some(*d as char),
+            _ => KEY_CHARS
+                .iter()
+                .find(|&&(key, _)| key == self)
+                .map(|&(_, c)| c),
+        }
+    }
+}
+
+// Keys are displayed as names that can be shown to users.
+// Keys that type a character are shown as that character, except for space.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(&(_, label)) = KEY_LABELS.iter().find(|&&(key, _)| key == *self) {
+            write!(f, "{}", label)
+        } else if let (Some(c), false) = (self.to_char(), *self == Key::Space) {
+            write!(f, "{}", c)
+        } else {
+            write!(f, "{}", self.name())
+        }
+    }
+}
+
+// Keys can be parsed from their names, the character they type,
+// or the names they are displayed as, ignoring case.
+impl FromStr for Key {
+    type Err = crate::KuleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let single = chars.next().filter(|_| chars.next().is_none());
+        Key::from_name(s)
+            .or_else(|| single.and_then(Key::from_char))
+            .or_else(|| {
+                KEY_LABELS
+                    .iter()
+                    .find(|&&(_, label)| label.eq_ignore_ascii_case(s))
+                    .map(|&(key, _)| key)
+            })
+            .ok_or_else(|| crate::KuleError::InvalidKey(s.into()))
+    }
+}
@@ -1,26 +1,35 @@
 use std::{
-    cell::{Ref, RefCell},
-    collections::{HashMap, HashSet},
+    cell::{Cell, Ref, RefCell},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    convert::TryFrom,
+    fs, io,
     iter::once,
-    ops::{Deref, Index},
+    ops::{Deref, Index, Range},
+    rc::Rc,
+    sync::mpsc,
+    thread,
 };
 
-use fontdue::{layout::*, *};
+use fontdue::*;
+use glium::backend::Facade;
 use lyon_tessellation::{
     geom::math::{point, Point},
     geometry_builder::simple_builder,
-    path::Path,
+    path::{self, FillRule, Path},
     FillOptions, FillTessellator, VertexBuffers,
 };
 
-use crate::{KuleError, KuleResult, Trans, Transform, Vec2};
+use crate::{
+    bounded_vertices, find_named_system_font, find_system_font, CanFail, Col, Color,
+    FloatingVector2, KuleError, KuleResult, Mesh, Rect, Texture, Trans, Transform, Vec2,
+};
 
-pub use fontdue::Metrics;
+pub use fontdue::{LineMetrics, Metrics};
 
 /// Size information for rendering glyphs
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct GlyphSize {
-    /// The pixel resolution to use when rasterizing then vectorizing the glyph
+    /// The pixel resolution of the glyph's layout and rasterization
     pub resolution: u32,
     /// The actual text size to use
     pub scale: f32,
@@ -55,45 +64,1317 @@ impl From<f32> for GlyphSize {
     }
 }
 
-/// Information for rendering glyphs
-pub struct GlyphSpec<G = ()> {
-    /// The font id
-    pub font_id: G,
-    /// The size
-    pub size: GlyphSize,
+/// How text is aligned horizontally relative to its origin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HorizontalAlign {
+    /// The text starts at the origin
+    #[default]
+    Left,
+    /// The text is centered on the origin
+    Center,
+    /// The text ends at the origin
+    Right,
+}
+
+/// How text is aligned vertically relative to its origin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum VerticalAlign {
+    /// The top of the font's tallest glyphs is at the origin
+    Top,
+    /// The text is centered on the origin
+    Middle,
+    /// The baseline of the text is at the origin
+    Baseline,
+    /// The bottom of the font's lowest glyphs is at the origin
+    #[default]
+    Bottom,
+}
+
+/**
+The direction that text is laid out in
+
+In vertical text, characters are centered in their lines and each
+new line is placed to the right of the last one. Vertical alignment
+aligns each line on its own, and `VerticalAlign::Baseline` is the same
+as `VerticalAlign::Top`.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextDirection {
+    /// Characters are placed from left to right and lines from top to bottom
+    #[default]
+    LeftToRight,
+    /// Characters are placed from top to bottom and lines from left to right
+    TopToBottom,
+}
+
+/**
+How text is rendered
+
+Vector text is tessellated from the outlines of its glyphs, so it stays
+sharp at any scale, but building geometry for many different characters
+is slow. Atlas text is drawn as textured quads from a texture that glyphs
+are rasterized into as they are needed. It is much faster for long or
+frequently changing strings, but it becomes blurry when it is scaled
+far beyond its glyph resolution.
+
+Signed distance field text is also drawn from an atlas, but the atlas
+stores the distance to each glyph's edge rather than its coverage.
+This keeps edges sharp at much larger scales and allows cheap
+outlines and glows. Very thin details may be rounded off.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextMode {
+    /// Draw text as tessellated geometry
+    #[default]
+    Vector,
+    /// Draw text as textured quads from a glyph atlas
+    Atlas,
+    /// Draw text as textured quads from a signed distance field glyph atlas
+    Sdf,
+}
+
+/**
+Values for the axes of a variable font
+
+Axes are identified by their four byte OpenType tags. Axes that are not
+set use the font's default value, and values outside of an axis's range
+are clamped to it.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FontVariation {
+    /// The tag and value of each set axis
+    pub axes: Vec<([u8; 4], f32)>,
+}
+
+impl FontVariation {
+    /// Create a new `FontVariation` that uses the default value of every axis
+    pub fn new() -> Self {
+        FontVariation::default()
+    }
+    /// Set the value of an axis
+    pub fn axis(mut self, tag: [u8; 4], value: f32) -> Self {
+        self.axes.retain(|(t, _)| t != &tag);
+        self.axes.push((tag, value));
+        self
+    }
+    /// Set the weight, usually from `100` to `900`
+    pub fn weight(self, weight: f32) -> Self {
+        self.axis(*b"wght", weight)
+    }
+    /// Set the width as a percentage of normal
+    pub fn width(self, width: f32) -> Self {
+        self.axis(*b"wdth", width)
+    }
+    /// Set the slant in degrees, which is negative for text that leans right
+    pub fn slant(self, slant: f32) -> Self {
+        self.axis(*b"slnt", slant)
+    }
+    /// Check if no axes are set
+    pub fn is_default(&self) -> bool {
+        self.axes.is_empty()
+    }
+}
+
+/// An axis of a variable font
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontAxis {
+    /// The axis's four byte OpenType tag
+    pub tag: [u8; 4],
+    /// The minimum value
+    pub min: f32,
+    /// The default value
+    pub default: f32,
+    /// The maximum value
+    pub max: f32,
+}
+
+/// Information for rendering glyphs
+pub struct GlyphSpec<G = ()> {
+    /// The font id
+    pub font_id: G,
+    /// The size
+    pub size: GlyphSize,
+    /// The horizontal alignment of text
+    pub halign: HorizontalAlign,
+    /// The vertical alignment of text
+    pub valign: VerticalAlign,
+    /// The direction text is laid out in
+    pub direction: TextDirection,
+    /// The angle in radians that the text is rotated by about its origin
+    pub rotation: f32,
+    /// Extra space added after each character, in the same units as the size's scale
+    pub letter_spacing: f32,
+    /// Whether to adjust the space between pairs of characters using the font's kerning table
+    pub kerning: bool,
+    /// The multiplier for the font's line height
+    pub line_height: f32,
+    /// Extra space added between lines, in the same units as the size's scale
+    pub paragraph_spacing: f32,
+    /**
+    The distance between tab stops, in the same units as the size's scale
+
+    If this is `None`, tab stops are the width of four spaces apart.
+    */
+    pub tab_width: Option<f32>,
+    /**
+    The positions of tab stops from the start of each line, in the same units as the size's scale
+
+    Tab stops past the last one are spaced evenly by the tab width.
+    */
+    pub tab_stops: Vec<f32>,
+    /// How the text is rendered
+    ///
+    /// If this is `None`, the font's text mode is used
+    pub mode: Option<TextMode>,
+    /// The color and width of the text's outline
+    ///
+    /// This has no effect in `TextMode::Atlas`
+    pub outline: Option<(Col, f32)>,
+    /// The color and width of the text's glow
+    ///
+    /// This only has an effect in `TextMode::Sdf`
+    pub glow: Option<(Col, f32)>,
+    /// The color and offset of the text's shadow
+    pub shadow: Option<(Col, Vec2)>,
+    /// Whether the text is underlined
+    pub underline: bool,
+    /// Whether the text has a line through it
+    pub strikethrough: bool,
+}
+
+impl<G> GlyphSpec<G> {
+    /// Create a new `GlyphSpec`
+    pub fn new<S>(font_id: G, size: S) -> Self
+    where
+        S: Into<GlyphSize>,
+    {
+        GlyphSpec {
+            font_id,
+            size: size.into(),
+            halign: HorizontalAlign::default(),
+            valign: VerticalAlign::default(),
+            direction: TextDirection::default(),
+            rotation: 0.0,
+            letter_spacing: 0.0,
+            kerning: true,
+            line_height: 1.0,
+            paragraph_spacing: 0.0,
+            tab_width: None,
+            tab_stops: Vec::new(),
+            mode: None,
+            outline: None,
+            glow: None,
+            shadow: None,
+            underline: false,
+            strikethrough: false,
+        }
+    }
+    /// Set the horizontal alignment
+    pub fn halign(self, halign: HorizontalAlign) -> Self {
+        GlyphSpec { halign, ..self }
+    }
+    /// Set the vertical alignment
+    pub fn valign(self, valign: VerticalAlign) -> Self {
+        GlyphSpec { valign, ..self }
+    }
+    /// Set both the horizontal and vertical alignment
+    pub fn align(self, halign: HorizontalAlign, valign: VerticalAlign) -> Self {
+        GlyphSpec {
+            halign,
+            valign,
+            ..self
+        }
+    }
+    /// Set the layout direction
+    pub fn direction(self, direction: TextDirection) -> Self {
+        GlyphSpec { direction, ..self }
+    }
+    /**
+    Set the rotation
+
+    The text is rotated about its origin after it is aligned, so the
+    alignment point stays in place. This makes it easy to draw labels
+    along axes and gauges.
+    */
+    pub fn rotation(self, rotation: f32) -> Self {
+        GlyphSpec { rotation, ..self }
+    }
+    /// Set the letter spacing
+    pub fn letter_spacing(self, letter_spacing: f32) -> Self {
+        GlyphSpec {
+            letter_spacing,
+            ..self
+        }
+    }
+    /// Set whether kerning is used
+    pub fn kerning(self, kerning: bool) -> Self {
+        GlyphSpec { kerning, ..self }
+    }
+    /// Set the line height multiplier
+    pub fn line_height(self, line_height: f32) -> Self {
+        GlyphSpec {
+            line_height,
+            ..self
+        }
+    }
+    /// Set the paragraph spacing
+    pub fn paragraph_spacing(self, paragraph_spacing: f32) -> Self {
+        GlyphSpec {
+            paragraph_spacing,
+            ..self
+        }
+    }
+    /// Set the distance between tab stops
+    pub fn tab_width(self, tab_width: f32) -> Self {
+        GlyphSpec {
+            tab_width: Some(tab_width),
+            ..self
+        }
+    }
+    /// Set the positions of the tab stops
+    pub fn tab_stops<I>(self, tab_stops: I) -> Self
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        GlyphSpec {
+            tab_stops: tab_stops.into_iter().collect(),
+            ..self
+        }
+    }
+    /// Set the text mode, overriding the font's
+    pub fn mode(self, mode: TextMode) -> Self {
+        GlyphSpec {
+            mode: Some(mode),
+            ..self
+        }
+    }
+    /**
+    Set the outline color and width
+
+    The width is in the same units as the size's scale. In `TextMode::Vector`,
+    the outline is stroked around the glyph meshes. In `TextMode::Sdf`, it
+    cannot be wider than the distance field's spread. Outlines are not drawn
+    in `TextMode::Atlas`.
+    */
+    pub fn outline(self, color: Col, width: f32) -> Self {
+        GlyphSpec {
+            outline: Some((color, width)),
+            ..self
+        }
+    }
+    /**
+    Set the glow color and width
+
+    The width is in the same units as the size's scale. Glows are only
+    drawn in `TextMode::Sdf` and fade out at the distance field's spread.
+    */
+    pub fn glow(self, color: Col, width: f32) -> Self {
+        GlyphSpec {
+            glow: Some((color, width)),
+            ..self
+        }
+    }
+    /**
+    Set the shadow color and offset
+
+    The shadow is a hard copy of the text drawn behind it. The offset is in
+    the same units as the size's scale. In `TextMode::Vector`, the shadow
+    includes the outline. In `TextMode::Sdf`, the shadow is drawn with the
+    text's outline and glow.
+    */
+    pub fn shadow(self, color: Col, offset: Vec2) -> Self {
+        GlyphSpec {
+            shadow: Some((color, offset)),
+            ..self
+        }
+    }
+    /**
+    Set whether the text is underlined
+
+    The underline's thickness and offset come from the font's metrics.
+    Vertical text is not decorated.
+    */
+    pub fn underline(self, underline: bool) -> Self {
+        GlyphSpec { underline, ..self }
+    }
+    /**
+    Set whether the text has a line through it
+
+    The line's thickness and offset come from the font's metrics.
+    Vertical text is not decorated.
+    */
+    pub fn strikethrough(self, strikethrough: bool) -> Self {
+        GlyphSpec {
+            strikethrough,
+            ..self
+        }
+    }
+}
+
+impl From<f32> for GlyphSpec {
+    fn from(scale: f32) -> Self {
+        GlyphSpec::from(GlyphSize::from(scale))
+    }
+}
+
+impl From<GlyphSize> for GlyphSpec {
+    fn from(size: GlyphSize) -> Self {
+        GlyphSpec::new((), size)
+    }
+}
+
+/// A piece of text in [`RichText`] with its own style
+#[derive(Debug, Clone)]
+pub struct TextSpan<'a, G = ()> {
+    /// The text
+    pub text: &'a str,
+    /// The color, which overrides the color the text is drawn with
+    pub color: Option<Col>,
+    /// The size, which overrides the `GlyphSpec`'s size
+    pub size: Option<GlyphSize>,
+    /// The font id, which overrides the `GlyphSpec`'s font id
+    pub font_id: Option<G>,
+}
+
+impl<'a, G> TextSpan<'a, G> {
+    /// Create a new `TextSpan` that uses the text's default style
+    pub fn new(text: &'a str) -> Self {
+        TextSpan {
+            text,
+            color: None,
+            size: None,
+            font_id: None,
+        }
+    }
+    /// Set the color
+    pub fn color<C>(self, color: C) -> Self
+    where
+        C: Color,
+    {
+        TextSpan {
+            color: Some(color.map()),
+            ..self
+        }
+    }
+    /// Set the size
+    pub fn size<S>(self, size: S) -> Self
+    where
+        S: Into<GlyphSize>,
+    {
+        TextSpan {
+            size: Some(size.into()),
+            ..self
+        }
+    }
+    /// Set the font id
+    pub fn font(self, font_id: G) -> Self {
+        TextSpan {
+            font_id: Some(font_id),
+            ..self
+        }
+    }
+}
+
+impl<'a, G> From<&'a str> for TextSpan<'a, G> {
+    fn from(text: &'a str) -> Self {
+        TextSpan::new(text)
+    }
+}
+
+impl<'a, G> From<&'a String> for TextSpan<'a, G> {
+    fn from(text: &'a String) -> Self {
+        TextSpan::new(text)
+    }
+}
+
+/**
+Text made of spans with different colors, sizes, and fonts
+
+The spans are laid out one after another as a single piece of text,
+so they share lines and alignment. Lines are as tall as the largest
+span in them.
+*/
+#[derive(Debug, Clone)]
+pub struct RichText<'a, G = ()> {
+    /// The spans
+    pub spans: Vec<TextSpan<'a, G>>,
+}
+
+impl<'a, G> Default for RichText<'a, G> {
+    fn default() -> Self {
+        RichText::new()
+    }
+}
+
+impl<'a, G> RichText<'a, G> {
+    /// Create a new empty `RichText`
+    pub fn new() -> Self {
+        RichText { spans: Vec::new() }
+    }
+    /// Add a span
+    pub fn span<S>(mut self, span: S) -> Self
+    where
+        S: Into<TextSpan<'a, G>>,
+    {
+        self.push(span);
+        self
+    }
+    /// Add a span
+    pub fn push<S>(&mut self, span: S)
+    where
+        S: Into<TextSpan<'a, G>>,
+    {
+        self.spans.push(span.into());
+    }
+}
+
+impl<'a, G> From<TextSpan<'a, G>> for RichText<'a, G> {
+    fn from(span: TextSpan<'a, G>) -> Self {
+        RichText { spans: vec![span] }
+    }
+}
+
+impl<'a, G> From<&'a str> for RichText<'a, G> {
+    fn from(text: &'a str) -> Self {
+        RichText::from(TextSpan::new(text))
+    }
+}
+
+impl<'a, G> From<&'a String> for RichText<'a, G> {
+    fn from(text: &'a String) -> Self {
+        RichText::from(TextSpan::new(text))
+    }
+}
+
+/// A piece of text that is laid out with a single font and size
+pub(crate) struct TextRun<'a> {
+    pub glyphs: &'a GlyphCache,
+    pub text: &'a str,
+    pub size: GlyphSize,
+    /// The byte index of the start of the run in the whole text
+    pub start: usize,
+}
+
+/// A position between characters in laid out text
+#[derive(Debug, Clone, Copy)]
+struct Caret {
+    /// The byte index of the character after the caret
+    index: usize,
+    line: usize,
+    /// The position along the line
+    along: f32,
+}
+
+/**
+Text that has been laid out, which can be used to build text editors
+
+Character indices are byte indices into the text, so they can be used to
+edit a `String` directly. For rich text, the indices are into the spans'
+text joined together.
+
+Positions are relative to the text's origin before the
+[`GlyphSpec`](struct.GlyphSpec.html)'s rotation is applied.
+*/
+#[derive(Debug, Clone)]
+pub struct TextLayout {
+    size: Vec2,
+    vertical: bool,
+    carets: Vec<Caret>,
+    /// The start and end of each line across the layout direction
+    lines: Vec<[f32; 2]>,
+}
+
+impl TextLayout {
+    /// Get the size of the laid out text
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+    /// Get the number of lines
+    pub fn lines(&self) -> usize {
+        self.lines.len()
+    }
+    /// Get the last caret at or before an index
+    fn caret(&self, index: usize) -> Caret {
+        let i = match self
+            .carets
+            .binary_search_by_key(&index, |caret| caret.index)
+        {
+            Ok(i) => i,
+            Err(i) => i.saturating_sub(1),
+        };
+        self.carets[i]
+    }
+    /// Convert positions along and across a line to a point
+    fn point(&self, along: f32, across: f32) -> Vec2 {
+        if self.vertical {
+            [across, along]
+        } else {
+            [along, across]
+        }
+    }
+    /**
+    Get the index of the character boundary closest to a point
+
+    The point's line is found first, and then the closest boundary
+    in that line. Points past the end of a line map to the end of it.
+    */
+    pub fn index_at(&self, point: Vec2) -> usize {
+        let (along, across) = if self.vertical {
+            (point[1], point[0])
+        } else {
+            (point[0], point[1])
+        };
+        let distance = |[start, end]: [f32; 2]| (start - across).max(across - end).max(0.0);
+        let line = (0..self.lines.len())
+            .min_by(|&a, &b| distance(self.lines[a]).total_cmp(&distance(self.lines[b])))
+            .unwrap_or(0);
+        self.carets
+            .iter()
+            .filter(|caret| caret.line == line)
+            .min_by(|a, b| (a.along - along).abs().total_cmp(&(b.along - along).abs()))
+            .map(|caret| caret.index)
+            .unwrap_or(0)
+    }
+    /**
+    Get the position of the caret before the character at an index
+
+    For horizontal text, this is the top of the caret.
+    For vertical text, this is the left of the caret.
+    Indices that are not on a character boundary are moved back to one.
+    */
+    pub fn position_of(&self, index: usize) -> Vec2 {
+        let caret = self.caret(index);
+        self.point(caret.along, self.lines[caret.line][0])
+    }
+    /**
+    Get the caret before the character at an index as a line segment
+
+    The caret spans the whole height of horizontal lines,
+    or the whole width of vertical ones.
+    */
+    pub fn caret_line(&self, index: usize) -> [Vec2; 2] {
+        let caret = self.caret(index);
+        let [start, end] = self.lines[caret.line];
+        [self.point(caret.along, start), self.point(caret.along, end)]
+    }
+    /// Get the rectangles that cover the characters in a range, one for each line
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let (start, end) = (self.caret(range.start), self.caret(range.end));
+        (start.line..=end.line)
+            .filter_map(|line| {
+                let mut carets = self
+                    .carets
+                    .iter()
+                    .filter(|caret| caret.line == line)
+                    .filter(|caret| caret.index >= start.index && caret.index <= end.index);
+                let first = carets.next()?.along;
+                let last = carets.next_back().map_or(first, |caret| caret.along);
+                let [across_start, across_end] = self.lines[line];
+                let [x, y] = self.point(first, across_start);
+                let [w, h] = self.point(last - first, across_end - across_start);
+                if w == 0.0 || h == 0.0 {
+                    None
+                } else {
+                    Some([x, y, w, h])
+                }
+            })
+            .collect()
+    }
+}
+
+/// A glyph positioned by text layout
+pub(crate) struct LaidOutGlyph {
+    pub id: GlyphId,
+    /// The transform that positions and scales the glyph's geometry
+    pub transform: Trans,
+    /// The index of the run that the character is from
+    pub run: usize,
+}
+
+/// An underline or strikethrough in laid out text
+pub(crate) struct LaidOutDecoration {
+    /// The rectangle of the line before the text is rotated
+    pub rect: Rect,
+    /// The index of the run that the decoration is from
+    pub run: usize,
+}
+
+/// The position and thickness of a font's decoration lines
+pub(crate) struct DecorationMetrics {
+    pub underline: [f32; 2],
+    pub strikethrough: [f32; 2],
+}
+
+/// A piece of a line of text that is all from one run
+struct LineSegment {
+    line: usize,
+    run: usize,
+    start: f32,
+    end: f32,
+}
+
+/// The extent of a line of text
+#[derive(Clone, Copy, Default)]
+struct LineBox {
+    /// The length of the line in the layout direction
+    length: f32,
+    ascent: f32,
+    descent: f32,
+    advance: f32,
+}
+
+impl LineBox {
+    /// Grow the line to fit text with some metrics
+    fn fit(&mut self, other: &LineBox) {
+        self.ascent = self.ascent.max(other.ascent);
+        self.descent = self.descent.min(other.descent);
+        self.advance = self.advance.max(other.advance);
+    }
+}
+
+/**
+A reference to a glyph in a font
+
+Most glyphs are referred to by the character they are for. Shaped text is
+made of glyphs rather than characters, and a glyph may not correspond to
+any single character, so shaped glyphs are referred to by their index in the font.
+
+A `char` can be used anywhere a `GlyphId` is expected.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GlyphId {
+    /// The glyph for a character
+    Char(char),
+    /// A glyph by its index in the font
+    Index(u16),
+}
+
+impl From<char> for GlyphId {
+    fn from(ch: char) -> Self {
+        GlyphId::Char(ch)
+    }
+}
+
+impl GlyphId {
+    /// Check if the glyph is for a control character, which is not drawn
+    pub fn is_control(self) -> bool {
+        matches!(self, GlyphId::Char(ch) if ch as u32 <= 0x1F)
+    }
+    /// Get the glyph's id in a face
+    fn face_id(self, face: &ttf_parser::Face) -> Option<ttf_parser::GlyphId> {
+        match self {
+            GlyphId::Char(ch) => face.glyph_index(ch),
+            GlyphId::Index(index) => Some(ttf_parser::GlyphId(index)),
+        }
+    }
+}
+
+/// A glyph of a line of text before it is positioned
+pub(crate) struct ShapedGlyph {
+    /// The byte index in the line of the first character the glyph is from
+    pub index: usize,
+    /// The glyph
+    pub id: GlyphId,
+    /// The horizontal advance at the glyph resolution
+    pub advance: f32,
+    /// The offset of the glyph from its position at the glyph resolution, with y up
+    pub offset: Vec2,
+    /// Whether the glyph is from right-to-left text
+    pub rtl: bool,
+}
+
+/// A visible glyph positioned by text layout before alignment
+struct PlacedGlyph {
+    id: GlyphId,
+    /**
+    The position of the top-left of the glyph within its line
+
+    For horizontal text, this is relative to the start of the line's baseline.
+    For vertical text, this is relative to the top of the line's center.
+    */
+    pos: Vec2,
+    ratio: f32,
+    line: usize,
+    run: usize,
+}
+
+/**
+Lay out runs of text one after another
+
+Lines are separated by newlines. Positions are in the same units as the
+runs' scales. Returns the laid out glyphs along with the layout of the text.
+*/
+pub(crate) fn layout_runs<G>(
+    runs: &[TextRun],
+    spec: &GlyphSpec<G>,
+) -> (Vec<LaidOutGlyph>, Vec<LaidOutDecoration>, TextLayout) {
+    let vertical = spec.direction == TextDirection::TopToBottom;
+    let mut glyphs = Vec::new();
+    let mut segments = Vec::new();
+    let mut carets = Vec::new();
+    let mut lines = vec![LineBox::default()];
+    let mut caret = 0.0;
+    for (r, run) in runs.iter().enumerate() {
+        let px = run.size.resolution as f32;
+        let ratio = run.size.ratio();
+        let metrics = run.glyphs.resolution_line_metrics(run.size.resolution);
+        let run_box = LineBox {
+            length: 0.0,
+            ascent: metrics.ascent.ceil() * ratio,
+            descent: metrics.descent * ratio,
+            advance: metrics.new_line_size.ceil() * ratio * spec.line_height,
+        };
+        lines.last_mut().unwrap().fit(&run_box);
+        let mut line_start = 0;
+        for (l, text) in run.text.split('\n').enumerate() {
+            if l > 0 {
+                carets.push(Caret {
+                    index: run.start + line_start - 1,
+                    line: lines.len() - 1,
+                    along: caret,
+                });
+                lines.push(run_box);
+                caret = 0.0;
+            }
+            let segment_start = caret;
+            for glyph in run.glyphs.shape(text, px, spec.kerning, vertical) {
+                let metrics = run.glyphs.metrics(glyph.id, run.size.resolution);
+                let advance = glyph.advance * ratio;
+                carets.push(Caret {
+                    index: run.start + line_start + glyph.index,
+                    line: lines.len() - 1,
+                    along: if glyph.rtl { caret + advance } else { caret },
+                });
+                if glyph.id == GlyphId::Char('\t') {
+                    let tab_width = spec.tab_width.unwrap_or_else(|| {
+                        let space = run.glyphs.metrics(' ', run.size.resolution);
+                        space.advance_width.ceil() * ratio * 4.0
+                    });
+                    caret = next_tab_stop(caret, &spec.tab_stops, tab_width);
+                    continue;
+                }
+                if glyph.id.is_control() {
+                    continue;
+                }
+                let top = (metrics.bounds.ymin.floor() + metrics.height as f32) * ratio
+                    + glyph.offset[1] * ratio;
+                let left = metrics.bounds.xmin.floor() * ratio + glyph.offset[0] * ratio;
+                // Vertical glyphs are centered in the line and stacked in cells as tall as the font
+                let (pos, end, next) = if vertical {
+                    let baseline = caret + run_box.ascent;
+                    let height = run_box.ascent - run_box.descent;
+                    (
+                        [left - advance / 2.0, baseline - top],
+                        caret + height,
+                        caret + height,
+                    )
+                } else {
+                    (
+                        [caret + left, -top],
+                        caret + left + metrics.width as f32 * ratio,
+                        caret + advance,
+                    )
+                };
+                if metrics.width != 0 || run.glyphs.has_color_glyph(glyph.id) {
+                    let line = lines.last_mut().unwrap();
+                    line.length = line.length.max(end);
+                    glyphs.push(PlacedGlyph {
+                        id: glyph.id,
+                        pos,
+                        ratio,
+                        line: lines.len() - 1,
+                        run: r,
+                    });
+                }
+                caret = next + spec.letter_spacing;
+            }
+            if caret > segment_start {
+                segments.push(LineSegment {
+                    line: lines.len() - 1,
+                    run: r,
+                    start: segment_start,
+                    end: caret,
+                });
+            }
+            line_start += text.len() + 1;
+        }
+    }
+    carets.push(Caret {
+        index: runs.last().map_or(0, |run| run.start + run.text.len()),
+        line: lines.len() - 1,
+        along: caret,
+    });
+    // Shaped right-to-left text has its carets in visual order
+    carets.sort_by_key(|caret| caret.index);
+    carets.dedup_by_key(|caret| caret.index);
+    // Each line is far enough from the last to fit whichever of them is bigger
+    let mut offsets = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        offsets.push(if i == 0 {
+            if vertical {
+                line.advance / 2.0
+            } else {
+                line.ascent
+            }
+        } else if vertical {
+            offsets[i - 1] + (lines[i - 1].advance + line.advance) / 2.0 + spec.paragraph_spacing
+        } else {
+            offsets[i - 1] + lines[i - 1].advance.max(line.advance) + spec.paragraph_spacing
+        });
+    }
+    let last = lines.len() - 1;
+    let longest = lines.iter().map(|line| line.length).fold(0.0, f32::max);
+    let size = if vertical {
+        [offsets[last] + lines[last].advance / 2.0, longest]
+    } else {
+        [longest, offsets[last] - lines[last].descent]
+    };
+    let x_shift = |width: f32| match spec.halign {
+        HorizontalAlign::Left => 0.0,
+        HorizontalAlign::Center => -width / 2.0,
+        HorizontalAlign::Right => -width,
+    };
+    let y_shift = |height: f32| match spec.valign {
+        VerticalAlign::Top => 0.0,
+        VerticalAlign::Middle => -height / 2.0,
+        VerticalAlign::Baseline if vertical => 0.0,
+        VerticalAlign::Baseline => -lines[0].ascent,
+        VerticalAlign::Bottom => -height,
+    };
+    let line_shift = |line: usize| {
+        let length = lines[line].length;
+        if vertical {
+            [offsets[line] + x_shift(size[0]), y_shift(length)]
+        } else {
+            [x_shift(length), offsets[line] + y_shift(size[1])]
+        }
+    };
+    let layout = TextLayout {
+        size,
+        vertical,
+        carets: carets
+            .into_iter()
+            .map(|caret| Caret {
+                along: caret.along + line_shift(caret.line)[vertical as usize],
+                ..caret
+            })
+            .collect(),
+        lines: lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let across = line_shift(i)[!vertical as usize];
+                if vertical {
+                    [across - line.advance / 2.0, across + line.advance / 2.0]
+                } else {
+                    [across - line.ascent, across - line.descent]
+                }
+            })
+            .collect(),
+    };
+    // Decorations span each segment of a line at the offsets the segment's font gives
+    let mut decorations = Vec::new();
+    if !vertical && (spec.underline || spec.strikethrough) {
+        for segment in segments {
+            let run = &runs[segment.run];
+            let ratio = run.size.ratio();
+            let metrics = run.glyphs.decoration_metrics(run.size.resolution);
+            let [x, baseline] = line_shift(segment.line);
+            let lines = once((spec.underline, metrics.underline))
+                .chain(once((spec.strikethrough, metrics.strikethrough)));
+            for (_, [position, thickness]) in lines.filter(|(enabled, _)| *enabled) {
+                decorations.push(LaidOutDecoration {
+                    rect: [
+                        x + segment.start,
+                        baseline - position * ratio,
+                        segment.end - segment.start,
+                        thickness * ratio,
+                    ],
+                    run: segment.run,
+                });
+            }
+        }
+    }
+    let rotation = Trans::new_rotate(spec.rotation);
+    let glyphs = glyphs
+        .into_iter()
+        .map(|glyph| {
+            let offset = line_shift(glyph.line);
+            LaidOutGlyph {
+                id: glyph.id,
+                transform: Trans::identity()
+                    .zoom(glyph.ratio)
+                    .translate([glyph.pos[0] + offset[0], glyph.pos[1] + offset[1]])
+                    .then(rotation),
+                run: glyph.run,
+            }
+        })
+        .collect();
+    (glyphs, decorations, layout)
+}
+
+/// Get the position of the first tab stop after a position
+fn next_tab_stop(pos: f32, tab_stops: &[f32], tab_width: f32) -> f32 {
+    if let Some(&stop) = tab_stops.iter().find(|&&stop| stop > pos) {
+        return stop;
+    }
+    if tab_width <= 0.0 {
+        return pos;
+    }
+    let start = tab_stops.last().copied().unwrap_or(0.0);
+    start + ((pos - start) / tab_width).floor() * tab_width + tab_width
+}
+
+/// Get the kerning between two characters at some resolution
+fn kerning(face: &ttf_parser::Face, left: GlyphId, right: GlyphId, px: f32) -> f32 {
+    let (left, right) = match (left.face_id(face), right.face_id(face)) {
+        (Some(left), Some(right)) => (left, right),
+        _ => return 0.0,
+    };
+    let units_per_em = face.units_per_em().unwrap_or(1000) as f32;
+    face.kerning_subtables()
+        .filter(|subtable| subtable.is_horizontal() && !subtable.is_variable())
+        .find_map(|subtable| subtable.glyphs_kerning(left, right))
+        .map(|kern| kern as f32 * px / units_per_em)
+        .unwrap_or(0.0)
+}
+
+/// The width and height of a glyph atlas texture
+const ATLAS_SIZE: u32 = 1024;
+/// The space between glyphs in an atlas, which keeps them from bleeding into each other
+const ATLAS_PADDING: u32 = 1;
+
+/// A texture that glyphs are rasterized into
+struct GlyphAtlas {
+    texture: Texture,
+    glyphs: HashMap<(GlyphId, u32), Rect>,
+    /// The top-left corner of the next glyph
+    cursor: [u32; 2],
+    /// The height of the tallest glyph in the current row
+    row_height: u32,
+}
+
+impl GlyphAtlas {
+    fn new<F>(facade: &F) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        let bytes = vec![0; (ATLAS_SIZE * ATLAS_SIZE * 4) as usize];
+        Ok(GlyphAtlas {
+            texture: Texture::from_rgba(facade, ATLAS_SIZE, ATLAS_SIZE, &bytes)?,
+            glyphs: HashMap::new(),
+            cursor: [ATLAS_PADDING; 2],
+            row_height: 0,
+        })
+    }
+    /// Find space for a glyph, returning its top-left corner if it fits
+    fn allocate(&mut self, [width, height]: [u32; 2]) -> Option<[u32; 2]> {
+        if self.cursor[0] + width + ATLAS_PADDING > ATLAS_SIZE {
+            self.cursor = [
+                ATLAS_PADDING,
+                self.cursor[1] + self.row_height + ATLAS_PADDING,
+            ];
+            self.row_height = 0;
+        }
+        if self.cursor[0] + width + ATLAS_PADDING > ATLAS_SIZE
+            || self.cursor[1] + height + ATLAS_PADDING > ATLAS_SIZE
+        {
+            return None;
+        }
+        let corner = self.cursor;
+        self.cursor[0] += width + ATLAS_PADDING;
+        self.row_height = self.row_height.max(height);
+        Some(corner)
+    }
+}
+
+/// The first bytes of a glyph geometry file, which identify its format
+const GEOMETRY_FILE_MAGIC: &[u8; 8] = b"KULEGEO2";
+/// The kind of glyph in a geometry file that is referred to by its character
+const GEOMETRY_FILE_CHAR: u32 = 0;
+/// The kind of glyph in a geometry file that is referred to by its index
+const GEOMETRY_FILE_INDEX: u32 = 1;
+/// The extension of glyph geometry files
+const GEOMETRY_FILE_EXTENSION: &str = ".glyphs";
+
+/// Get a hash of font data that is the same between runs
+fn font_hash(data: &[u8]) -> u64 {
+    // 64-bit FNV-1a
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Get the name of the glyph geometry file for a font hash and resolution
+fn geometry_file_name(hash: u64, resolution: u32) -> String {
+    format!("{:016x}-{}{}", hash, resolution, GEOMETRY_FILE_EXTENSION)
+}
+
+/// Reads little-endian values from bytes
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(taken)
+    }
+    fn u16(&mut self) -> Option<u16> {
+        let mut buf = [0; 2];
+        buf.copy_from_slice(self.take(2)?);
+        Some(u16::from_le_bytes(buf))
+    }
+    fn u32(&mut self) -> Option<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Some(u32::from_le_bytes(buf))
+    }
+}
+
+/// Parse the glyphs in a glyph geometry file, returning `None` if the file is invalid
+fn parse_geometry_file(bytes: &[u8]) -> Option<Vec<(GlyphId, GlyphGeometry)>> {
+    let mut reader = ByteReader(bytes.strip_prefix(GEOMETRY_FILE_MAGIC.as_ref())?);
+    let count = reader.u32()?;
+    let mut glyphs = Vec::new();
+    for _ in 0..count {
+        let kind = reader.u32()?;
+        let value = reader.u32()?;
+        let id = match kind {
+            GEOMETRY_FILE_CHAR => GlyphId::Char(std::char::from_u32(value)?),
+            GEOMETRY_FILE_INDEX => GlyphId::Index(u16::try_from(value).ok()?),
+            _ => return None,
+        };
+        let vertex_count = reader.u32()?;
+        let mut vertices = Vec::new();
+        for _ in 0..vertex_count {
+            let x = f32::from_bits(reader.u32()?);
+            let y = f32::from_bits(reader.u32()?);
+            vertices.push([x, y]);
+        }
+        let index_count = reader.u32()?;
+        if index_count % 3 != 0 {
+            return None;
+        }
+        let mut indices = Vec::new();
+        for _ in 0..index_count {
+            let index = reader.u16()?;
+            if index as u32 >= vertex_count {
+                return None;
+            }
+            indices.push(index);
+        }
+        glyphs.push((id, GlyphGeometry { vertices, indices }));
+    }
+    Some(glyphs)
+}
+
+/// The key of a glyph in a glyph cache
+type GlyphKey = (GlyphId, u32);
+
+/**
+Remove the glyph that was used least recently from a geometry cache
+
+Entries in the recency queue that are older than their glyph's
+last use are outdated, so they are skipped.
+*/
+fn evict_least_recent(
+    geometry: &mut HashMap<GlyphKey, CachedGlyph>,
+    recency: &mut VecDeque<(GlyphKey, u64)>,
+) {
+    while let Some((key, used)) = recency.pop_front() {
+        if matches!(geometry.get(&key), Some(cached) if cached.last_used.get() == used) {
+            geometry.remove(&key);
+            return;
+        }
+    }
+}
+
+/// Get the distance in pixels over which a signed distance field glyph fades from inside to outside
+pub(crate) fn sdf_spread(resolution: u32) -> u32 {
+    (resolution / 8).max(2)
+}
+
+/**
+Build a signed distance field from a glyph's coverage
+
+The field is padded by the spread on each side. Each value maps the
+distance to the glyph's edge from `-spread..spread` onto `0..255`,
+so the edge itself is at `128`.
+*/
+fn distance_field(coverage: &[u8], width: usize, height: usize, spread: usize) -> Vec<u8> {
+    let padded_width = width + spread * 2;
+    let padded_height = height + spread * 2;
+    let inside = |x: usize, y: usize| {
+        x >= spread
+            && y >= spread
+            && x < width + spread
+            && y < height + spread
+            && coverage[(y - spread) * width + x - spread] >= 128
+    };
+    let mut to_inside = vec![0.0; padded_width * padded_height];
+    let mut to_outside = vec![0.0; padded_width * padded_height];
+    for y in 0..padded_height {
+        for x in 0..padded_width {
+            let i = y * padded_width + x;
+            if inside(x, y) {
+                to_outside[i] = FAR;
+            } else {
+                to_inside[i] = FAR;
+            }
+        }
+    }
+    squared_distance_transform(&mut to_inside, padded_width, padded_height);
+    squared_distance_transform(&mut to_outside, padded_width, padded_height);
+    to_inside
+        .into_iter()
+        .zip(to_outside)
+        .map(|(to_inside, to_outside)| {
+            // Distances are measured between pixel centers, so the edge is half a pixel away
+            let dist = if to_inside == 0.0 {
+                to_outside.sqrt() - 0.5
+            } else {
+                0.5 - to_inside.sqrt()
+            };
+            let value = 0.5 + dist / (spread as f32 * 2.0);
+            (value.clamp(0.0, 1.0) * 255.0) as u8
+        })
+        .collect()
+}
+
+/// The squared distance used for pixels that have not been reached by a distance transform
+const FAR: f32 = 1e20;
+
+/**
+Compute the squared euclidean distance transform of a grid in place
+
+Each cell should start as either `0` or `FAR` and ends as the squared
+distance to the nearest cell that started as `0`.
+*/
+fn squared_distance_transform(grid: &mut [f32], width: usize, height: usize) {
+    let n = width.max(height);
+    let mut f = vec![0.0; n];
+    let mut d = vec![0.0; n];
+    let mut v = vec![0; n];
+    let mut z = vec![0.0; n + 1];
+    for x in 0..width {
+        for y in 0..height {
+            f[y] = grid[y * width + x];
+        }
+        distance_transform_1d(&f[..height], &mut d, &mut v, &mut z);
+        for y in 0..height {
+            grid[y * width + x] = d[y];
+        }
+    }
+    for y in 0..height {
+        let row = y * width..(y + 1) * width;
+        f[..width].copy_from_slice(&grid[row.clone()]);
+        distance_transform_1d(&f[..width], &mut d, &mut v, &mut z);
+        grid[row].copy_from_slice(&d[..width]);
+    }
+}
+
+/// Compute a 1d squared distance transform using the lower envelope of parabolas
+fn distance_transform_1d(f: &[f32], d: &mut [f32], v: &mut [usize], z: &mut [f32]) {
+    let intersection = |q: usize, p: usize| {
+        ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2 * q - 2 * p) as f32
+    };
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = f32::NEG_INFINITY;
+    z[1] = f32::INFINITY;
+    for q in 1..f.len() {
+        let mut s = intersection(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f32::INFINITY;
+    }
+    k = 0;
+    for (q, dq) in d.iter_mut().enumerate().take(f.len()) {
+        while z[k + 1] < q as f32 {
+            k += 1;
+        }
+        let offset = q as f32 - v[k] as f32;
+        *dq = offset * offset + f[v[k]];
+    }
+}
+
+/// The state of a font that is being loaded in the background
+#[derive(Debug, Clone)]
+pub enum FontLoadState {
+    /// The font is still being loaded
+    Loading,
+    /// The font is loaded and can be used
+    Loaded,
+    /// The font failed to load
+    Failed(Rc<KuleError>),
 }
 
-impl<G> GlyphSpec<G> {
-    /// Create a new `GlyphSpec`
-    pub fn new<S>(font_id: G, size: S) -> Self
-    where
-        S: Into<GlyphSize>,
-    {
-        GlyphSpec {
-            font_id,
-            size: size.into(),
-        }
-    }
+/**
+A handle to a font that is being loaded in the background
+
+Returned by [`Fonts::load_file_async`](struct.Fonts.html#method.load_file_async).
+Once the font is loaded, it can be used with the handle's id.
+*/
+#[derive(Debug, Clone)]
+pub struct FontHandle<G = ()> {
+    id: G,
+    state: Rc<RefCell<FontLoadState>>,
 }
 
-impl From<f32> for GlyphSpec {
-    fn from(scale: f32) -> Self {
-        GlyphSpec::from(GlyphSize::from(scale))
+impl<G> FontHandle<G> {
+    /// Get the id the font is loaded with
+    pub fn id(&self) -> &G {
+        &self.id
+    }
+    /// Get the loading state of the font
+    pub fn state(&self) -> FontLoadState {
+        self.state.borrow().clone()
+    }
+    /// Check if the font is loaded and can be used
+    pub fn is_loaded(&self) -> bool {
+        matches!(*self.state.borrow(), FontLoadState::Loaded)
+    }
+    /// Check if the font is still being loaded
+    pub fn is_loading(&self) -> bool {
+        matches!(*self.state.borrow(), FontLoadState::Loading)
+    }
+    /// Get the error that occured while loading the font, if any
+    pub fn error(&self) -> Option<Rc<KuleError>> {
+        if let FontLoadState::Failed(e) = &*self.state.borrow() {
+            Some(Rc::clone(e))
+        } else {
+            None
+        }
     }
 }
 
-impl From<GlyphSize> for GlyphSpec {
-    fn from(size: GlyphSize) -> Self {
-        GlyphSpec::new((), size)
-    }
+/// A font that is being read and parsed on another thread
+struct PendingFont<G> {
+    id: G,
+    receiver: mpsc::Receiver<io::Result<(Font, Vec<u8>)>>,
+    state: Rc<RefCell<FontLoadState>>,
 }
 
 /// A cache of glyphs for each loaded font
-pub struct Fonts<G = ()>(HashMap<G, GlyphCache>);
+pub struct Fonts<G = ()> {
+    caches: HashMap<G, GlyphCache>,
+    pending: Vec<PendingFont<G>>,
+}
 
 impl<G> Default for Fonts<G> {
     fn default() -> Self {
-        Fonts(HashMap::default())
+        Fonts {
+            caches: HashMap::default(),
+            pending: Vec::new(),
+        }
     }
 }
 
@@ -101,19 +1382,212 @@ impl<G> Fonts<G>
 where
     G: Eq + std::hash::Hash,
 {
+    /**
+    Split rich text into runs for layout
+
+    Spans with fonts that are not loaded are skipped.
+    Also returns the font id and color of each run.
+    */
+    pub(crate) fn runs<'a>(
+        &'a self,
+        text: &RichText<'a, G>,
+        spec: &GlyphSpec<G>,
+    ) -> (Vec<TextRun<'a>>, Vec<(G, Option<Col>)>)
+    where
+        G: Copy,
+    {
+        let mut runs = Vec::new();
+        let mut styles = Vec::new();
+        let mut start = 0;
+        for span in &text.spans {
+            let font_id = span.font_id.unwrap_or(spec.font_id);
+            if let Some(glyphs) = self.get(font_id) {
+                runs.push(TextRun {
+                    start,
+                    ..glyphs.run(span.text, span.size.unwrap_or(spec.size))
+                });
+                styles.push((font_id, span.color));
+            }
+            start += span.text.len();
+        }
+        (runs, styles)
+    }
+    /**
+    Lay out some text so that positions in it can be found
+
+    Returns `None` if none of the text's fonts are loaded.
+    */
+    pub fn text_layout<'a, S>(&self, text: S, spec: &GlyphSpec<G>) -> Option<TextLayout>
+    where
+        G: Copy,
+        S: Into<RichText<'a, G>>,
+    {
+        let text = text.into();
+        let (runs, _) = self.runs(&text, spec);
+        if runs.is_empty() {
+            None
+        } else {
+            Some(layout_runs(&runs, spec).2)
+        }
+    }
     /// Load a font
     pub fn load(&mut self, id: G, data: &[u8]) -> KuleResult<()> {
-        self.0.insert(
+        self.caches.insert(
             id,
-            Font::from_bytes(data, Default::default())
-                .map_err(KuleError::Static)?
-                .into(),
+            GlyphCache {
+                data: Some(data.to_vec()),
+                ..Font::from_bytes(data, Default::default())
+                    .map_err(KuleError::Static)?
+                    .into()
+            },
         );
         Ok(())
     }
+    /**
+    Load a variable font with some values for its axes
+
+    The same font data can be loaded with different font ids to use
+    several of its variations at once.
+    See [`GlyphCache::set_variation`](struct.GlyphCache.html#method.set_variation)
+    for which parts of the text the axes affect.
+    */
+    pub fn load_variation(&mut self, id: G, data: &[u8], variation: FontVariation) -> KuleResult<()>
+    where
+        G: Copy,
+    {
+        self.load(id, data)?;
+        if let Some(glyphs) = self.get_mut(id) {
+            glyphs.set_variation(variation);
+        }
+        Ok(())
+    }
+    /// Load a font from a file
+    pub fn load_file<P>(&mut self, id: G, path: P) -> KuleResult<()>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let data = fs::read(path)?;
+        self.load(id, &data)
+    }
+    /**
+    Load a font from a file in the background
+
+    The file is read and parsed on another thread so that the frame is not blocked.
+    The font is added once it is finished loading and
+    [`Fonts::poll_loading`](struct.Fonts.html#method.poll_loading) is called,
+    which happens automatically before every update. Until then, the font id
+    has no font loaded for it, and text drawn with it is skipped.
+
+    The returned handle can be used to check when the font is ready.
+    */
+    pub fn load_file_async<P>(&mut self, id: G, path: P) -> FontHandle<G>
+    where
+        G: Clone,
+        P: AsRef<std::path::Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let res = fs::read(path).and_then(|data| {
+                Font::from_bytes(data.as_slice(), Default::default())
+                    .map(|font| (font, data))
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            });
+            let _ = sender.send(res);
+        });
+        let state = Rc::new(RefCell::new(FontLoadState::Loading));
+        self.pending.push(PendingFont {
+            id: id.clone(),
+            receiver,
+            state: Rc::clone(&state),
+        });
+        FontHandle { id, state }
+    }
+    /**
+    Add any fonts that have finished loading in the background
+
+    This is called automatically before every update.
+    */
+    pub fn poll_loading(&mut self) {
+        let mut i = 0;
+        while i < self.pending.len() {
+            let res = match self.pending[i].receiver.try_recv() {
+                Ok(res) => res.map_err(KuleError::from),
+                Err(mpsc::TryRecvError::Empty) => {
+                    i += 1;
+                    continue;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => Err(KuleError::Static(
+                    "Font loading thread stopped unexpectedly",
+                )),
+            };
+            let pending = self.pending.swap_remove(i);
+            *pending.state.borrow_mut() = match res {
+                Ok((font, data)) => {
+                    self.caches.insert(
+                        pending.id,
+                        GlyphCache {
+                            data: Some(data),
+                            ..font.into()
+                        },
+                    );
+                    FontLoadState::Loaded
+                }
+                Err(e) => FontLoadState::Failed(Rc::new(e)),
+            };
+        }
+    }
+    /// Check if any fonts are still being loaded in the background
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+    /**
+    Load an installed font by name, or the platform's default font if it is not installed
+
+    See [`find_system_font`](fn.find_system_font.html) for how fonts are found.
+    */
+    pub fn load_system(&mut self, id: G, name: &str) -> KuleResult<()> {
+        let data = find_system_font(name).ok_or_else(|| KuleError::FontNotFound(name.into()))?;
+        self.load(id, &data)
+    }
+    /**
+    Load an installed font by name, or a fallback font if it is not installed
+
+    The fallback is usually a font that is bundled with the app.
+    */
+    pub fn load_system_or(&mut self, id: G, name: &str, fallback: &[u8]) -> KuleResult<()> {
+        match find_named_system_font(name) {
+            Some(data) => self.load(id, &data),
+            None => self.load(id, fallback),
+        }
+    }
     /// Get a glyph cache with the given id
     pub fn get(&self, id: G) -> Option<&GlyphCache> {
-        self.0.get(&id)
+        self.caches.get(&id)
+    }
+    /// Get a mutable reference to the glyph cache with the given id
+    pub fn get_mut(&mut self, id: G) -> Option<&mut GlyphCache> {
+        self.caches.get_mut(&id)
+    }
+    /// Save the cached glyph geometry of every font to files in a directory
+    pub fn save_geometry<P>(&self, dir: P) -> CanFail
+    where
+        P: AsRef<std::path::Path>,
+    {
+        for glyphs in self.caches.values() {
+            glyphs.save_geometry(dir.as_ref())?;
+        }
+        Ok(())
+    }
+    /// Load the saved glyph geometry of every font from files in a directory
+    pub fn load_geometry<P>(&self, dir: P) -> CanFail
+    where
+        P: AsRef<std::path::Path>,
+    {
+        for glyphs in self.caches.values() {
+            glyphs.load_geometry(dir.as_ref())?;
+        }
+        Ok(())
     }
 }
 
@@ -134,8 +1608,14 @@ impl Deref for Fonts {
     }
 }
 
-/// Geometry defining a character glyph
-#[derive(Debug, Clone)]
+/**
+Geometry defining a character glyph
+
+The geometry is a list of triangles. The vertices are in pixels at the
+glyph's resolution, relative to the top-left of the glyph's bounding box,
+with y pointing down. Every 3 indices form a triangle.
+*/
+#[derive(Debug, Clone, Default)]
 pub struct GlyphGeometry {
     /// The vertices
     pub vertices: Vec<Vec2>,
@@ -143,23 +1623,159 @@ pub struct GlyphGeometry {
     pub indices: Vec<u16>,
 }
 
+impl GlyphGeometry {
+    /// Get the triangles
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec2; 3]> + '_ {
+        self.indices.chunks_exact(3).map(move |tri| {
+            [
+                self.vertices[tri[0] as usize],
+                self.vertices[tri[1] as usize],
+                self.vertices[tri[2] as usize],
+            ]
+        })
+    }
+    /// Check if the geometry has no triangles
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/**
+A glyph of laid out text along with its geometry
+
+This is returned by [`GlyphCache::glyphs`](struct.GlyphCache.html#method.glyphs).
+It owns its geometry, so it can be kept and modified freely, for example to
+make each letter of some text a separate physics object.
+*/
+#[derive(Debug, Clone)]
+pub struct PositionedGlyph {
+    /// The glyph
+    pub id: GlyphId,
+    /// The glyph's metrics at its resolution
+    pub metrics: Metrics,
+    /// The glyph's geometry at its resolution
+    pub geometry: GlyphGeometry,
+    /// The transform from the glyph's geometry to the text's coordinates
+    pub transform: Trans,
+}
+
+impl PositionedGlyph {
+    /// Get the vertices in the text's coordinates
+    pub fn vertices(&self) -> impl Iterator<Item = Vec2> + '_ {
+        let transform = self.transform;
+        self.geometry
+            .vertices
+            .iter()
+            .map(move |&v| v.transform(transform))
+    }
+    /// Get the triangles in the text's coordinates
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec2; 3]> + '_ {
+        let transform = self.transform;
+        self.geometry.triangles().map(move |[a, b, c]| {
+            [
+                a.transform(transform),
+                b.transform(transform),
+                c.transform(transform),
+            ]
+        })
+    }
+}
+
 /**
 A cache of glyph geometry for a single font
 
 Unlike most libraries, kule uses vectorized glyphs rather than rasterized ones.
-Currently, this is achieved by first rasterizing the glyph, then using an algorithm
-to vectorize the image.
+The outlines of glyphs are read from the font and tessellated. The outlines
+are placed on the same pixel grid that the glyph would be rasterized to at
+its resolution. If the font data is not available, such as when the cache
+is created from a `Font`, glyphs are rasterized and their edges are traced.
+
+Fonts can also be drawn from a texture atlas of rasterized glyphs.
+See [`TextMode`](enum.TextMode.html) for more information.
 */
 pub struct GlyphCache {
     font: Font,
-    geometry: RefCell<HashMap<(char, u32), (Metrics, GlyphGeometry)>>,
+    /// The raw font data, which is used to look up kerning
+    data: Option<Vec<u8>>,
+    /// The values of the font's variation axes
+    variation: FontVariation,
+    geometry: RefCell<HashMap<GlyphKey, CachedGlyph>>,
+    /// The keys of cached glyphs in the order they were used, along with when they were used
+    ///
+    /// This is only kept when there is a capacity
+    recency: RefCell<VecDeque<(GlyphKey, u64)>>,
+    /// The maximum number of glyphs to keep geometry for
+    capacity: Option<usize>,
+    /// Incremented every time a glyph is accessed, for tracking which was used least recently
+    clock: Cell<u64>,
+    atlas: RefCell<Option<GlyphAtlas>>,
+    sdf_atlas: RefCell<Option<GlyphAtlas>>,
+    /// Color bitmap glyphs, or `None` for glyphs that do not have them
+    color_glyphs: RefCell<HashMap<GlyphId, Option<Rc<ColorGlyph>>>>,
+    mode: TextMode,
+}
+
+/// A color bitmap glyph, such as an emoji
+pub(crate) struct ColorGlyph {
+    /// The left, bottom, width, and height of the image relative to the
+    /// glyph's origin at the image's size, with y up
+    bounds: [f32; 4],
+    /// The size that the image was made for
+    pixels_per_em: f32,
+    size: [u32; 2],
+    rgba: Vec<u8>,
+    texture: RefCell<Option<Texture>>,
+}
+
+impl ColorGlyph {
+    /// Get the rectangle that the image covers relative to the glyph's geometry at some resolution
+    pub fn rect(&self, metrics: &Metrics, resolution: u32) -> Rect {
+        let scale = resolution as f32 / self.pixels_per_em;
+        let [x, y, width, height] = self.bounds;
+        let top = metrics.bounds.ymin.floor() + metrics.height as f32;
+        [
+            x * scale - metrics.bounds.xmin.floor(),
+            top - (y + height) * scale,
+            width * scale,
+            height * scale,
+        ]
+    }
+    /// Get the image's texture, creating it if necessary
+    pub fn texture<F>(&self, facade: &F) -> KuleResult<Texture>
+    where
+        F: Facade,
+    {
+        let mut texture = self.texture.borrow_mut();
+        if texture.is_none() {
+            let [width, height] = self.size;
+            *texture = Some(Texture::from_rgba(facade, width, height, &self.rgba)?);
+        }
+        Ok(texture.clone().unwrap())
+    }
+}
+
+/// Glyph geometry along with when it was last used
+struct CachedGlyph {
+    glyph: (Metrics, GlyphGeometry),
+    /// The mesh that the glyph is drawn with, which is built the first time it is drawn
+    mesh: Option<Mesh>,
+    last_used: Cell<u64>,
 }
 
 impl From<Font> for GlyphCache {
     fn from(font: Font) -> Self {
         GlyphCache {
             font,
+            data: None,
+            variation: FontVariation::default(),
             geometry: RefCell::new(HashMap::new()),
+            recency: RefCell::new(VecDeque::new()),
+            capacity: None,
+            clock: Cell::new(0),
+            atlas: RefCell::new(None),
+            sdf_atlas: RefCell::new(None),
+            color_glyphs: RefCell::new(HashMap::new()),
+            mode: TextMode::default(),
         }
     }
 }
@@ -169,41 +1785,681 @@ impl GlyphCache {
     pub fn font(&self) -> &Font {
         &self.font
     }
-    /// Get the metrics of a character at some resolution
-    pub fn metrics(&self, ch: char, resolution: u32) -> Metrics {
-        self.glyph(ch, resolution).0
+    /// Get the axes of the font if it is a variable font
+    pub fn variation_axes(&self) -> Vec<FontAxis> {
+        self.face()
+            .map(|face| {
+                face.variation_axes()
+                    .map(|axis| FontAxis {
+                        tag: axis.tag.to_bytes(),
+                        min: axis.min_value,
+                        default: axis.def_value,
+                        max: axis.max_value,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    /// Get the values of the font's variation axes
+    pub fn variation(&self) -> &FontVariation {
+        &self.variation
+    }
+    /**
+    Set the values of the font's variation axes
+
+    This clears all cached glyphs. The axes change the outlines and advances
+    of glyphs and the positions of decorations. Atlas text, and fonts that
+    were not loaded from font data, are rasterized from the font's default
+    instance and only have their advances changed.
+    */
+    pub fn set_variation(&mut self, variation: FontVariation) {
+        self.variation = variation;
+        self.clear();
+        *self.atlas.get_mut() = None;
+        *self.sdf_atlas.get_mut() = None;
+    }
+    /// Parse the font data with the variation applied
+    fn face(&self) -> Option<ttf_parser::Face<'_>> {
+        let mut face = ttf_parser::Face::from_slice(self.data.as_ref()?, 0).ok()?;
+        for &(tag, value) in &self.variation.axes {
+            face.set_variation(ttf_parser::Tag::from_bytes(&tag), value);
+        }
+        Some(face)
+    }
+    /// Get a hash of the font data and variation that identifies saved geometry
+    fn geometry_hash(&self) -> Option<u64> {
+        let hash = font_hash(self.data.as_ref()?);
+        Some(self.variation.axes.iter().fold(hash, |hash, (tag, value)| {
+            tag.iter()
+                .chain(&value.to_bits().to_le_bytes())
+                .fold(hash, |hash, &byte| {
+                    (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+                })
+        }))
+    }
+    /// Get the text mode used when a draw does not specify one
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+    /// Set the text mode used when a draw does not specify one
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+    /// Get the maximum number of glyphs whose geometry is cached
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+    /**
+    Set the maximum number of glyphs whose geometry is cached
+
+    When the cache is full, the glyph that was used least recently is removed
+    to make room for a new one. If the capacity is `None`, the cache grows
+    without limit. Each character at each resolution counts as a separate glyph.
+    A glyph's mesh is removed along with its geometry.
+    */
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        let geometry = self.geometry.get_mut();
+        let recency = self.recency.get_mut();
+        recency.clear();
+        if let Some(capacity) = capacity {
+            let mut used: Vec<_> = geometry
+                .iter()
+                .map(|(&key, cached)| (key, cached.last_used.get()))
+                .collect();
+            used.sort_by_key(|&(_, used)| used);
+            recency.extend(used);
+            while geometry.len() > capacity {
+                evict_least_recent(geometry, recency);
+            }
+        }
+    }
+    /// Get the number of glyphs whose geometry is cached
+    pub fn cached_len(&self) -> usize {
+        self.geometry.borrow().len()
+    }
+    /// Remove all cached glyph geometry
+    pub fn clear(&mut self) {
+        self.geometry.get_mut().clear();
+        self.recency.get_mut().clear();
+        self.color_glyphs.get_mut().clear();
+    }
+    /**
+    Check if a character has a color bitmap glyph, such as an emoji
+
+    Color glyphs are read from a font's `sbix` or `CBDT` table and are
+    drawn as images instead of vector geometry. They require the `image`
+    feature, and are not drawn in `TextMode::Sdf`.
+    */
+    pub fn has_color_glyph<C>(&self, id: C) -> bool
+    where
+        C: Into<GlyphId>,
+    {
+        self.color_glyph(id.into()).is_some()
     }
-    /// Get a reference to the metrics and geometry of a character glyph at some resolution
-    pub fn glyph(&self, ch: char, resolution: u32) -> Ref<(Metrics, GlyphGeometry)> {
-        if !self.geometry.borrow().contains_key(&(ch, resolution)) {
-            let glyph_data = self.vectorize(ch, resolution);
-            self.geometry
-                .borrow_mut()
-                .insert((ch, resolution), glyph_data);
+    /// Get a glyph's color bitmap, loading it if necessary
+    pub(crate) fn color_glyph(&self, id: GlyphId) -> Option<Rc<ColorGlyph>> {
+        if let Some(color_glyph) = self.color_glyphs.borrow().get(&id) {
+            return color_glyph.clone();
         }
-        Ref::map(self.geometry.borrow(), |geometry| {
-            geometry.get(&(ch, resolution)).unwrap()
+        let color_glyph = self.load_color_glyph(id).map(Rc::new);
+        self.color_glyphs
+            .borrow_mut()
+            .insert(id, color_glyph.clone());
+        color_glyph
+    }
+    /// Decode a glyph's color bitmap from the largest size in the font
+    #[cfg(feature = "image")]
+    fn load_color_glyph(&self, id: GlyphId) -> Option<ColorGlyph> {
+        let face = ttf_parser::Face::from_slice(self.data.as_ref()?, 0).ok()?;
+        let raster = face.glyph_raster_image(id.face_id(&face)?, u16::MAX)?;
+        let image = image::load_from_memory(raster.data).ok()?.to_rgba8();
+        Some(ColorGlyph {
+            bounds: [
+                raster.x as f32,
+                raster.y as f32,
+                raster.width as f32,
+                raster.height as f32,
+            ],
+            pixels_per_em: raster.pixels_per_em as f32,
+            size: [image.width(), image.height()],
+            rgba: image.into_raw(),
+            texture: RefCell::new(None),
         })
     }
+    /// Color glyphs cannot be decoded without the `image` feature
+    #[cfg(not(feature = "image"))]
+    fn load_color_glyph(&self, _id: GlyphId) -> Option<ColorGlyph> {
+        None
+    }
+    /**
+    Build the geometry of some characters at some resolution ahead of time
+
+    This avoids hitches the first time the characters are drawn.
+    For example, `glyphs.prewarm(' '..='~', 100)` prepares all
+    printable ASCII characters.
+    */
+    pub fn prewarm<I>(&self, chars: I, resolution: u32)
+    where
+        I: IntoIterator<Item = char>,
+    {
+        for ch in chars {
+            self.glyph(ch, resolution);
+        }
+    }
+    /**
+    Get the atlas texture and source rectangle of a glyph at some resolution
+
+    The glyph is rasterized into the atlas if it is not already there.
+    Signed distance field glyphs are padded by their spread on each side.
+    */
+    pub(crate) fn atlas_glyph<F>(
+        &self,
+        facade: &F,
+        id: GlyphId,
+        resolution: u32,
+        sdf: bool,
+    ) -> KuleResult<(Texture, Rect)>
+    where
+        F: Facade,
+    {
+        let mut atlas = if sdf {
+            self.sdf_atlas.borrow_mut()
+        } else {
+            self.atlas.borrow_mut()
+        };
+        if atlas.is_none() {
+            *atlas = Some(GlyphAtlas::new(facade)?);
+        }
+        let atlas_ref = atlas.as_mut().unwrap();
+        if let Some(src) = atlas_ref.glyphs.get(&(id, resolution)) {
+            return Ok((atlas_ref.texture.clone(), *src));
+        }
+        let (metrics, coverage) = self
+            .font
+            .rasterize_indexed(self.font_index(id), resolution as f32);
+        let (size, alpha) = if sdf {
+            let spread = sdf_spread(resolution);
+            let size = [
+                metrics.width as u32 + spread * 2,
+                metrics.height as u32 + spread * 2,
+            ];
+            let field = distance_field(&coverage, metrics.width, metrics.height, spread as usize);
+            (size, field)
+        } else {
+            ([metrics.width as u32, metrics.height as u32], coverage)
+        };
+        let corner = match atlas_ref.allocate(size) {
+            Some(corner) => corner,
+            None => {
+                // Start a new atlas. Draws that still use the old one keep it alive.
+                *atlas_ref = GlyphAtlas::new(facade)?;
+                atlas_ref
+                    .allocate(size)
+                    .ok_or(KuleError::Static("Glyph is too large for the atlas"))?
+            }
+        };
+        let bytes: Vec<u8> = alpha.iter().flat_map(|&a| vec![255, 255, 255, a]).collect();
+        atlas_ref
+            .texture
+            .write_rgba_region([corner[0], corner[1], size[0], size[1]], &bytes)?;
+        let src = [
+            corner[0] as f32,
+            corner[1] as f32,
+            size[0] as f32,
+            size[1] as f32,
+        ];
+        atlas_ref.glyphs.insert((id, resolution), src);
+        Ok((atlas_ref.texture.clone(), src))
+    }
+    /// Get the metrics of a glyph at some resolution
+    ///
+    /// This does not rasterize the glyph
+    pub fn metrics<C>(&self, id: C, resolution: u32) -> Metrics
+    where
+        C: Into<GlyphId>,
+    {
+        self.font
+            .metrics_indexed(self.font_index(id.into()), resolution as f32)
+    }
+    /// Get the index of a glyph in the font
+    fn font_index(&self, id: GlyphId) -> usize {
+        match id {
+            GlyphId::Char(ch) => self.font.lookup_glyph_index(ch),
+            GlyphId::Index(index) => index as usize,
+        }
+    }
+    /**
+    Turn a line of text into glyphs
+
+    With the `shaping` feature, horizontal text is shaped with the font's
+    OpenType tables. Otherwise, there is one glyph for each character.
+    */
+    fn shape(&self, text: &str, px: f32, kern: bool, vertical: bool) -> Vec<ShapedGlyph> {
+        #[cfg(feature = "shaping")]
+        {
+            let shaped = self.data.as_ref().filter(|_| !vertical).and_then(|data| {
+                crate::shaping::shape_line(
+                    data,
+                    &self.variation,
+                    text,
+                    self.font.scale_factor(px),
+                    kern,
+                )
+            });
+            if let Some(shaped) = shaped {
+                return shaped;
+            }
+        }
+        let varied = !vertical && !self.variation.is_default();
+        let face = if (kern || varied) && !vertical {
+            self.face()
+        } else {
+            None
+        };
+        let mut glyphs: Vec<ShapedGlyph> = Vec::new();
+        for (index, ch) in text.char_indices() {
+            if let (Some(face), Some(prev), true) = (&face, glyphs.last_mut(), kern) {
+                prev.advance += kerning(face, prev.id, ch.into(), px);
+            }
+            // Variable fonts can change the advances, which fontdue does not know about
+            let varied_advance = face.as_ref().filter(|_| varied).and_then(|face| {
+                face.glyph_hor_advance(face.glyph_index(ch)?)
+                    .map(|advance| (advance as f32 * self.font.scale_factor(px)).ceil())
+            });
+            let advance = if ch as u32 <= 0x1F {
+                0.0
+            } else if let Some(advance) = varied_advance {
+                advance
+            } else {
+                self.font.metrics(ch, px).advance_width.ceil()
+            };
+            glyphs.push(ShapedGlyph {
+                index,
+                id: ch.into(),
+                advance,
+                offset: [0.0; 2],
+                rtl: false,
+            });
+        }
+        glyphs
+    }
+    /**
+    Lay out a string of text
+
+    Returns each glyph along with the transform that positions
+    and scales its geometry
+    */
+    pub(crate) fn layout<G>(&self, string: &str, spec: &GlyphSpec<G>) -> Vec<(Trans, GlyphId)> {
+        let (glyphs, ..) = layout_runs(&[self.run(string, spec.size)], spec);
+        glyphs
+            .into_iter()
+            .map(|glyph| (glyph.transform, glyph.id))
+            .collect()
+    }
+    /// Make a run of text for layout with this font
+    pub(crate) fn run<'a>(&'a self, text: &'a str, size: GlyphSize) -> TextRun<'a> {
+        TextRun {
+            glyphs: self,
+            text,
+            size,
+            start: 0,
+        }
+    }
+    /**
+    Get the font's vertical metrics at some text size
+
+    The metrics are in the same units as the size's scale. The ascent
+    is above the baseline and is typically positive, while the descent
+    is below the baseline and is typically negative.
+    */
+    pub fn line_metrics<S>(&self, size: S) -> LineMetrics
+    where
+        S: Into<GlyphSize>,
+    {
+        let size = size.into();
+        let metrics = self.resolution_line_metrics(size.resolution);
+        let ratio = size.ratio();
+        LineMetrics {
+            ascent: metrics.ascent * ratio,
+            descent: metrics.descent * ratio,
+            line_gap: metrics.line_gap * ratio,
+            new_line_size: metrics.new_line_size * ratio,
+        }
+    }
+    /**
+    Get the position and thickness of the font's underline and strikethrough at some resolution
+
+    Positions are of the top of each line relative to the baseline, with y up.
+    If the font data is not available or does not specify them, they are
+    estimated from the line metrics.
+    */
+    pub(crate) fn decoration_metrics(&self, resolution: u32) -> DecorationMetrics {
+        let line_metrics = self.resolution_line_metrics(resolution);
+        let thickness = (line_metrics.ascent / 12.0).max(1.0);
+        let estimated = DecorationMetrics {
+            underline: [line_metrics.descent / 3.0, thickness],
+            strikethrough: [line_metrics.ascent * 0.3 + thickness / 2.0, thickness],
+        };
+        let face = match self.face() {
+            Some(face) => face,
+            None => return estimated,
+        };
+        let scale = resolution as f32 / face.units_per_em().unwrap_or(1000) as f32;
+        let line = |metrics: Option<ttf_parser::LineMetrics>, estimated: [f32; 2]| {
+            metrics
+                .filter(|metrics| metrics.thickness > 0)
+                .map(|metrics| {
+                    [
+                        metrics.position as f32 * scale,
+                        metrics.thickness as f32 * scale,
+                    ]
+                })
+                .unwrap_or(estimated)
+        };
+        DecorationMetrics {
+            underline: line(face.underline_metrics(), estimated.underline),
+            strikethrough: line(face.strikeout_metrics(), estimated.strikethrough),
+        }
+    }
+    /// Get the font's line metrics at some resolution
+    fn resolution_line_metrics(&self, resolution: u32) -> LineMetrics {
+        let px = resolution as f32;
+        self.font
+            .horizontal_line_metrics(px)
+            .unwrap_or(LineMetrics {
+                ascent: px,
+                descent: 0.0,
+                line_gap: 0.0,
+                new_line_size: px,
+            })
+    }
+    /**
+    Get a reference to the metrics and geometry of a glyph at some resolution
+
+    The reference borrows the cache, so it should not be held while drawing text
+    with the same font. Use [`GlyphCache::glyph_geometry`](struct.GlyphCache.html#method.glyph_geometry)
+    to get a copy of the geometry instead.
+    */
+    pub fn glyph<C>(&self, id: C, resolution: u32) -> Ref<'_, (Metrics, GlyphGeometry)>
+    where
+        C: Into<GlyphId>,
+    {
+        let key = (id.into(), resolution);
+        self.use_glyph(key);
+        Ref::map(self.geometry.borrow(), |geometry| &geometry[&key].glyph)
+    }
+    /// Get the mesh that a glyph is drawn with at some resolution
+    pub(crate) fn glyph_mesh(&self, id: GlyphId, resolution: u32) -> Mesh {
+        let key = (id, resolution);
+        self.use_glyph(key);
+        let mut geometry = self.geometry.borrow_mut();
+        let cached = geometry.get_mut(&key).unwrap();
+        let glyph = &cached.glyph.1;
+        cached
+            .mesh
+            .get_or_insert_with(|| {
+                Mesh::new(
+                    bounded_vertices(glyph.vertices.iter().copied()),
+                    glyph.indices.iter().map(|&i| i as u32).collect(),
+                )
+            })
+            .clone()
+    }
+    /// Mark a glyph as used, building its geometry if it is not cached
+    fn use_glyph(&self, key: GlyphKey) {
+        let now = self.clock.get() + 1;
+        self.clock.set(now);
+        if let Some(cached) = self.geometry.borrow().get(&key) {
+            cached.last_used.set(now);
+            if self.capacity.is_some() {
+                self.push_recency(key, now);
+            }
+            return;
+        }
+        let glyph = self.vectorize(key.0, key.1);
+        self.insert_glyph(key, glyph, now);
+    }
+    /// Record that a glyph was used, removing outdated entries if there are too many
+    fn push_recency(&self, key: GlyphKey, now: u64) {
+        let mut recency = self.recency.borrow_mut();
+        recency.push_back((key, now));
+        let geometry = self.geometry.borrow();
+        if recency.len() > geometry.len() * 2 + 64 {
+            recency.retain(|(key, used)| {
+                matches!(geometry.get(key), Some(cached) if cached.last_used.get() == *used)
+            });
+        }
+    }
+    /// Get a copy of the metrics and geometry of a glyph at some resolution
+    pub fn glyph_geometry<C>(&self, id: C, resolution: u32) -> (Metrics, GlyphGeometry)
+    where
+        C: Into<GlyphId>,
+    {
+        self.glyph(id, resolution).clone()
+    }
+    /**
+    Lay out some text and get the geometry of each of its glyphs
+
+    The glyphs are positioned the same way they would be drawn with
+    `Drawer::text`. Characters without geometry, such as spaces, are skipped.
+    */
+    pub fn glyphs<G>(&self, text: &str, spec: &GlyphSpec<G>) -> Vec<PositionedGlyph> {
+        let resolution = spec.size.resolution;
+        self.layout(text, spec)
+            .into_iter()
+            .map(|(transform, id)| {
+                let (metrics, geometry) = self.glyph_geometry(id, resolution);
+                PositionedGlyph {
+                    id,
+                    metrics,
+                    geometry,
+                    transform,
+                }
+            })
+            .filter(|glyph| !glyph.geometry.is_empty())
+            .collect()
+    }
+    /// Add glyph geometry to the cache, making room for it if necessary
+    fn insert_glyph(&self, key: GlyphKey, glyph: (Metrics, GlyphGeometry), now: u64) {
+        {
+            let mut geometry = self.geometry.borrow_mut();
+            if let Some(capacity) = self.capacity {
+                let mut recency = self.recency.borrow_mut();
+                while !geometry.is_empty() && geometry.len() >= capacity {
+                    evict_least_recent(&mut geometry, &mut recency);
+                }
+            }
+            geometry.insert(
+                key,
+                CachedGlyph {
+                    glyph,
+                    mesh: None,
+                    last_used: Cell::new(now),
+                },
+            );
+        }
+        if self.capacity.is_some() {
+            self.push_recency(key, now);
+        }
+    }
+    /**
+    Save all cached glyph geometry to files in a directory
+
+    There is one file for each resolution. The files are named with a hash
+    of the font data, so caches for several fonts can share a directory.
+    Load the geometry in a later run with
+    [`GlyphCache::load_geometry`](struct.GlyphCache.html#method.load_geometry)
+    to skip building it again.
+
+    This does nothing if the glyph cache was not created from font data.
+    */
+    pub fn save_geometry<P>(&self, dir: P) -> CanFail
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let hash = match self.geometry_hash() {
+            Some(hash) => hash,
+            None => return Ok(()),
+        };
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let geometry = self.geometry.borrow();
+        let mut by_resolution: BTreeMap<u32, Vec<(GlyphId, &GlyphGeometry)>> = BTreeMap::new();
+        for (&(id, resolution), cached) in geometry.iter() {
+            by_resolution
+                .entry(resolution)
+                .or_default()
+                .push((id, &cached.glyph.1));
+        }
+        for (resolution, glyphs) in by_resolution {
+            let mut bytes = GEOMETRY_FILE_MAGIC.to_vec();
+            bytes.extend_from_slice(&(glyphs.len() as u32).to_le_bytes());
+            for (id, geometry) in glyphs {
+                let (kind, value) = match id {
+                    GlyphId::Char(ch) => (GEOMETRY_FILE_CHAR, ch as u32),
+                    GlyphId::Index(index) => (GEOMETRY_FILE_INDEX, index as u32),
+                };
+                bytes.extend_from_slice(&kind.to_le_bytes());
+                bytes.extend_from_slice(&value.to_le_bytes());
+                bytes.extend_from_slice(&(geometry.vertices.len() as u32).to_le_bytes());
+                for v in &geometry.vertices {
+                    bytes.extend_from_slice(&v[0].to_le_bytes());
+                    bytes.extend_from_slice(&v[1].to_le_bytes());
+                }
+                bytes.extend_from_slice(&(geometry.indices.len() as u32).to_le_bytes());
+                for i in &geometry.indices {
+                    bytes.extend_from_slice(&i.to_le_bytes());
+                }
+            }
+            fs::write(dir.join(geometry_file_name(hash, resolution)), bytes)?;
+        }
+        Ok(())
+    }
+    /**
+    Load glyph geometry that was saved with
+    [`GlyphCache::save_geometry`](struct.GlyphCache.html#method.save_geometry)
+
+    Only files that were saved from the same font data are loaded.
+    Glyphs that are already cached are kept. It is not an error
+    for the directory to not exist. Files that are invalid, such as
+    ones saved by an older version, are skipped.
+    */
+    pub fn load_geometry<P>(&self, dir: P) -> CanFail
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let hash = match self.geometry_hash() {
+            Some(hash) => hash,
+            None => return Ok(()),
+        };
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let prefix = format!("{:016x}-", hash);
+        for entry in entries {
+            let path = entry?.path();
+            let resolution = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|name| name.strip_suffix(GEOMETRY_FILE_EXTENSION))
+                .and_then(|resolution| resolution.parse::<u32>().ok());
+            if let Some(resolution) = resolution {
+                let bytes = fs::read(&path)?;
+                // The geometry can always be built again, so invalid files are not an error
+                let glyphs = match parse_geometry_file(&bytes) {
+                    Some(glyphs) => glyphs,
+                    None => continue,
+                };
+                let now = self.clock.get();
+                for (id, geometry) in glyphs {
+                    if !self.geometry.borrow().contains_key(&(id, resolution)) {
+                        let metrics = self.metrics(id, resolution);
+                        self.insert_glyph((id, resolution), (metrics, geometry), now);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
     /// Get the width of some text
     pub fn width<S>(&self, text: &str, size: S) -> f32
     where
         S: Into<GlyphSize>,
     {
-        let size = size.into();
-        let mut gps = Vec::new();
-        Layout::new().layout_horizontal(
-            &[self.font()],
-            &[&TextStyle::new(text, size.resolution as f32, 0)],
-            &LayoutSettings {
-                ..Default::default()
-            },
-            &mut gps,
-        );
-        gps.last().map(|gp| gp.x + gp.width as f32).unwrap_or(0.0) * size.ratio()
+        self.width_with(text, &GlyphSpec::new((), size))
+    }
+    /**
+    Get the width of some text with spacing options
+
+    If the text has multiple lines, this is the width of the longest one.
+    The width is not affected by the spec's rotation.
+    */
+    pub fn width_with<G>(&self, text: &str, spec: &GlyphSpec<G>) -> f32 {
+        self.text_layout(text, spec).size[0]
+    }
+    /// Lay out some text so that positions in it can be found
+    pub fn text_layout<G>(&self, text: &str, spec: &GlyphSpec<G>) -> TextLayout {
+        layout_runs(&[self.run(text, spec.size)], spec).2
     }
-    fn vectorize(&self, ch: char, resolution: u32) -> (Metrics, GlyphGeometry) {
-        let (metrics, bytes) = self.font.rasterize(ch, resolution as f32);
+    /**
+    Build the geometry of a glyph
+
+    The glyph's outline is read directly from the font data if it is available.
+    Otherwise, the glyph is rasterized and its edges are traced.
+    */
+    fn vectorize(&self, id: GlyphId, resolution: u32) -> (Metrics, GlyphGeometry) {
+        let outlined = self.face().map(|face| self.outline(&face, id, resolution));
+        match outlined {
+            Some(outlined) => outlined,
+            None => self.trace(id, resolution),
+        }
+    }
+    /// Tessellate a glyph's outline
+    fn outline(
+        &self,
+        face: &ttf_parser::Face,
+        id: GlyphId,
+        resolution: u32,
+    ) -> (Metrics, GlyphGeometry) {
+        let px = resolution as f32;
+        let metrics = self.metrics(id, resolution);
+        let mut outline = GlyphOutline {
+            builder: Path::builder(),
+            scale: self.font.scale_factor(px),
+            origin: [
+                metrics.bounds.xmin.floor(),
+                metrics.bounds.ymin.floor() + metrics.height as f32,
+            ],
+        };
+        let mut buffers: VertexBuffers<Point, u16> = VertexBuffers::new();
+        let has_outline = id
+            .face_id(face)
+            .and_then(|id| face.outline_glyph(id, &mut outline))
+            .is_some();
+        if has_outline {
+            let path = outline.builder.build();
+            let mut vertex_builder = simple_builder(&mut buffers);
+            let mut tessellator = FillTessellator::new();
+            // Glyphs whose outlines cannot be tessellated are left empty
+            let _ = tessellator.tessellate_path(
+                &path,
+                &FillOptions::default().with_fill_rule(FillRule::NonZero),
+                &mut vertex_builder,
+            );
+        }
+        let indices = buffers.indices;
+        let vertices: Vec<Vec2> = buffers.vertices.into_iter().map(|v| [v.x, v.y]).collect();
+        (metrics, GlyphGeometry { indices, vertices })
+    }
+    /// Rasterize a glyph and trace the edges of the result
+    fn trace(&self, id: GlyphId, resolution: u32) -> (Metrics, GlyphGeometry) {
+        let (metrics, bytes) = self
+            .font
+            .rasterize_indexed(self.font_index(id), resolution as f32);
         let get = |[x, y]: [usize; 2]| bytes[y * metrics.width + x] > 0;
         let mut edges = HashSet::new();
         // Collect relevant edge pixels
@@ -273,6 +2529,46 @@ impl GlyphCache {
     }
 }
 
+/// Builds a path from a glyph outline in font units, converted to glyph pixel space
+struct GlyphOutline {
+    builder: path::Builder,
+    /// The number of pixels per font unit
+    scale: f32,
+    /// The position of the top-left corner of the glyph's pixel bounds
+    origin: [f32; 2],
+}
+
+impl GlyphOutline {
+    fn point(&self, x: f32, y: f32) -> Point {
+        point(
+            x * self.scale - self.origin[0],
+            self.origin[1] - y * self.scale,
+        )
+    }
+}
+
+impl ttf_parser::OutlineBuilder for GlyphOutline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.builder.move_to(to);
+    }
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.builder.line_to(to);
+    }
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (ctrl, to) = (self.point(x1, y1), self.point(x, y));
+        self.builder.quadratic_bezier_to(ctrl, to);
+    }
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (ctrl1, ctrl2, to) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.builder.cubic_bezier_to(ctrl1, ctrl2, to);
+    }
+    fn close(&mut self) {
+        self.builder.close();
+    }
+}
+
 #[allow(clippy::many_single_char_names)]
 fn adj_neighbors_array(p: [usize; 2], width: usize, height: usize) -> [Option<[usize; 2]>; 4] {
     let [x, y] = p;
@@ -322,3 +2618,54 @@ fn neighbors(
         .chain(once(bl))
         .chain(once(br))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The bytes of a geometry file with one triangle glyph for `'a'`
+    fn triangle_file() -> Vec<u8> {
+        let mut bytes = GEOMETRY_FILE_MAGIC.to_vec();
+        for n in &[1, GEOMETRY_FILE_CHAR, 'a' as u32, 3] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        for v in &[0.0f32, 0.0, 1.0, 0.0, 0.0, 1.0] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        for i in &[0u16, 1, 2] {
+            bytes.extend_from_slice(&i.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn geometry_file() {
+        let glyphs = parse_geometry_file(&triangle_file()).unwrap();
+        assert_eq!(glyphs.len(), 1);
+        let (id, geometry) = &glyphs[0];
+        assert_eq!(*id, GlyphId::Char('a'));
+        assert_eq!(geometry.vertices, vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+        assert_eq!(geometry.indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn invalid_geometry_file() {
+        let bytes = triangle_file();
+        // Truncated
+        assert!(parse_geometry_file(&bytes[..bytes.len() - 1]).is_none());
+        // Wrong magic, like a file from an older version
+        let mut old = bytes.clone();
+        old[7] = b'1';
+        assert!(parse_geometry_file(&old).is_none());
+        // Index out of range
+        let mut out_of_range = bytes.clone();
+        let last = out_of_range.len() - 2;
+        out_of_range[last] = 3;
+        assert!(parse_geometry_file(&out_of_range).is_none());
+        // Unknown glyph id kind
+        let mut unknown = bytes;
+        unknown[12] = 2;
+        assert!(parse_geometry_file(&unknown).is_none());
+    }
+}
@@ -0,0 +1,599 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, EffectBuilder, Repeat, Replay, Ticks},
+    EventType, GamepadId, Gilrs,
+};
+
+use crate::{event::age_inputs, ButtonState, Context, Event, KuleResult, Resources, StateTracker};
+
+macro_rules! gamepad_enum {
+    ($doc:literal, $name:ident, $gilrs:ident, $(($variant:ident, $gilrsvariant:ident),)*) => {
+        #[doc = $doc]
+        #[allow(missing_docs)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(
+            feature = "ser",
+            derive(serde_derive::Serialize, serde_derive::Deserialize)
+        )]
+        pub enum $name {
+            $($variant,)*
+            Unknown
+        }
+
+        impl $name {
+            fn from_gilrs(item: gilrs::$gilrs) -> Self {
+                match item {
+                    $(gilrs::$gilrs::$gilrsvariant => $name::$variant,)*
+                    gilrs::$gilrs::Unknown => $name::Unknown,
+                }
+            }
+        }
+    };
+}
+
+gamepad_enum!(
+    "A button on a gamepad",
+    GamepadButton,
+    Button,
+    (South, South),
+    (East, East),
+    (North, North),
+    (West, West),
+    (C, C),
+    (Z, Z),
+    (LeftTrigger, LeftTrigger),
+    (LeftTrigger2, LeftTrigger2),
+    (RightTrigger, RightTrigger),
+    (RightTrigger2, RightTrigger2),
+    (Select, Select),
+    (Start, Start),
+    (Mode, Mode),
+    (LeftThumb, LeftThumb),
+    (RightThumb, RightThumb),
+    (DPadUp, DPadUp),
+    (DPadDown, DPadDown),
+    (DPadLeft, DPadLeft),
+    (DPadRight, DPadRight),
+);
+
+gamepad_enum!(
+    "An axis on a gamepad",
+    GamepadAxis,
+    Axis,
+    (LeftX, LeftStickX),
+    (LeftY, LeftStickY),
+    (LeftZ, LeftZ),
+    (RightX, RightStickX),
+    (RightY, RightStickY),
+    (RightZ, RightZ),
+    (DPadX, DPadX),
+    (DPadY, DPadY),
+);
+
+/// An input event from a gamepad
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum GamepadEvent {
+    /// A button's state has changed
+    Button {
+        /// The button
+        button: GamepadButton,
+        /// The new state
+        state: ButtonState,
+    },
+    /// An axis's value has changed
+    Axis {
+        /// The axis
+        axis: GamepadAxis,
+        /// The new value, from `-1.0` to `1.0`
+        value: f32,
+    },
+    /// The gamepad was connected
+    Connected,
+    /// The gamepad was disconnected
+    Disconnected,
+}
+
+/// How an axis's value changes as it is pushed further
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum AxisCurve {
+    /// The value is proportional to how far the axis is pushed
+    Linear,
+    /**
+    The value is raised to a power
+
+    Powers greater than `1.0` give finer control near the center.
+    */
+    Power(f32),
+}
+
+/// How the raw value of a gamepad axis is turned into the value apps see
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct AxisSettings {
+    /// Raw values closer to `0.0` than this are `0.0`
+    pub dead_zone: f32,
+    /// Raw values further from `0.0` than this are `1.0` or `-1.0`
+    pub max: f32,
+    /// Whether the value is negated
+    pub invert: bool,
+    /// The response curve applied between the dead zone and the max
+    pub curve: AxisCurve,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        AxisSettings::new()
+    }
+}
+
+impl AxisSettings {
+    /// Create a new `AxisSettings` with no dead zone, a max of `1.0`, and a linear curve
+    pub const fn new() -> Self {
+        AxisSettings {
+            dead_zone: 0.0,
+            max: 1.0,
+            invert: false,
+            curve: AxisCurve::Linear,
+        }
+    }
+    /// Set the dead zone
+    pub const fn dead_zone(self, dead_zone: f32) -> Self {
+        AxisSettings { dead_zone, ..self }
+    }
+    /// Set the max
+    pub const fn max(self, max: f32) -> Self {
+        AxisSettings { max, ..self }
+    }
+    /// Set whether the value is negated
+    pub const fn invert(self, invert: bool) -> Self {
+        AxisSettings { invert, ..self }
+    }
+    /// Set the response curve
+    pub const fn curve(self, curve: AxisCurve) -> Self {
+        AxisSettings { curve, ..self }
+    }
+    /// Turn a raw axis value into the value apps see
+    pub fn apply(&self, raw: f32) -> f32 {
+        let magnitude = raw.abs();
+        if magnitude <= self.dead_zone {
+            return 0.0;
+        }
+        let range = self.max - self.dead_zone;
+        let t = if range > 0.0 {
+            ((magnitude - self.dead_zone) / range).min(1.0)
+        } else {
+            1.0
+        };
+        let t = match self.curve {
+            AxisCurve::Linear => t,
+            AxisCurve::Power(power) => t.powf(power),
+        };
+        let value = t * raw.signum();
+        if self.invert {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+/**
+Settings for processing gamepad axes
+
+The settings are applied before axis values are sent in events and stored
+in the [`StateTracker`](struct.StateTracker.html). Changing them affects
+each axis the next time its value changes.
+*/
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct GamepadSettings {
+    /// The settings for axes that do not have their own
+    pub default: AxisSettings,
+    /// The settings for specific axes
+    pub axes: HashMap<GamepadAxis, AxisSettings>,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        GamepadSettings {
+            default: AxisSettings::new().dead_zone(0.1),
+            axes: HashMap::new(),
+        }
+    }
+}
+
+impl GamepadSettings {
+    /// Get the settings for an axis
+    pub fn axis(&self, axis: GamepadAxis) -> &AxisSettings {
+        self.axes.get(&axis).unwrap_or(&self.default)
+    }
+    /// Set the settings for an axis
+    pub fn set_axis(&mut self, axis: GamepadAxis, settings: AxisSettings) {
+        self.axes.insert(axis, settings);
+    }
+}
+
+/**
+A helper for finding the dead zones and ranges of a gamepad's axes
+
+Sample the gamepads with [`GamepadCalibration::sample_rest`](struct.GamepadCalibration.html#method.sample_rest)
+every update while the player leaves the sticks alone, then with
+[`GamepadCalibration::sample_range`](struct.GamepadCalibration.html#method.sample_range)
+while they move the sticks all the way around. The results can then be applied to
+the context's [`GamepadSettings`](struct.GamepadSettings.html).
+*/
+#[derive(Debug, Clone, Default)]
+pub struct GamepadCalibration {
+    /// The furthest each axis got from `0.0` while at rest
+    rest: HashMap<GamepadAxis, f32>,
+    /// The furthest each axis got from `0.0` while being moved
+    range: HashMap<GamepadAxis, f32>,
+}
+
+impl GamepadCalibration {
+    /// Create a new empty `GamepadCalibration`
+    pub fn new() -> Self {
+        GamepadCalibration::default()
+    }
+    /// Record the raw axis values of every gamepad while the axes are at rest
+    pub fn sample_rest(&mut self, tracker: &StateTracker) {
+        Self::sample(&mut self.rest, tracker);
+    }
+    /// Record the raw axis values of every gamepad while the axes are being moved
+    pub fn sample_range(&mut self, tracker: &StateTracker) {
+        Self::sample(&mut self.range, tracker);
+    }
+    fn sample(extents: &mut HashMap<GamepadAxis, f32>, tracker: &StateTracker) {
+        for state in tracker.gamepads.values() {
+            for (&axis, &raw) in &state.raw_axes {
+                let extent = extents.entry(axis).or_insert(0.0);
+                *extent = extent.max(raw.abs());
+            }
+        }
+    }
+    /// Get the suggested dead zone of an axis, which is a little past its noise at rest
+    pub fn dead_zone(&self, axis: GamepadAxis) -> Option<f32> {
+        self.rest.get(&axis).map(|rest| (rest * 1.2).min(0.9))
+    }
+    /// Get the suggested max of an axis, which is a little short of the furthest it was moved
+    pub fn max(&self, axis: GamepadAxis) -> Option<f32> {
+        self.range
+            .get(&axis)
+            .filter(|&&range| range > 0.0)
+            .map(|range| range * 0.95)
+    }
+    /**
+    Set the dead zones and maxes of sampled axes
+
+    Inversion and curves are kept.
+    */
+    pub fn apply(&self, settings: &mut GamepadSettings) {
+        let axes: HashSet<GamepadAxis> =
+            self.rest.keys().chain(self.range.keys()).copied().collect();
+        for axis in axes {
+            let mut axis_settings = *settings.axis(axis);
+            if let Some(dead_zone) = self.dead_zone(axis) {
+                axis_settings.dead_zone = dead_zone;
+            }
+            if let Some(max) = self.max(axis) {
+                axis_settings.max = max.max(axis_settings.dead_zone);
+            }
+            settings.set_axis(axis, axis_settings);
+        }
+    }
+}
+
+/// The state of a single gamepad
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct GamepadState {
+    pressed: HashSet<GamepadButton>,
+    /// Buttons pressed and released recently, and how many updates ago
+    just_pressed: HashMap<GamepadButton, u32>,
+    just_released: HashMap<GamepadButton, u32>,
+    button_values: HashMap<GamepadButton, f32>,
+    axes: HashMap<GamepadAxis, f32>,
+    raw_axes: HashMap<GamepadAxis, f32>,
+    /// When each held button was pressed
+    #[cfg_attr(feature = "ser", serde(skip))]
+    press_times: HashMap<GamepadButton, Instant>,
+}
+
+impl GamepadState {
+    /// Get whether a button is pressed
+    pub fn button(&self, button: GamepadButton) -> bool {
+        self.pressed.contains(&button)
+    }
+    /// Check if a button was pressed since the last update
+    pub fn button_pressed(&self, button: GamepadButton) -> bool {
+        self.just_pressed.contains_key(&button)
+    }
+    /// Check if a button was released since the last update
+    pub fn button_released(&self, button: GamepadButton) -> bool {
+        self.just_released.contains_key(&button)
+    }
+    /// Forget which buttons were pressed and released longer ago than the input buffer
+    pub(crate) fn end_update(&mut self, buffer: u32) {
+        age_inputs(&mut self.just_pressed, buffer);
+        age_inputs(&mut self.just_released, buffer);
+    }
+    /// Forget that a button was pressed, returning whether it was pressed recently
+    pub(crate) fn consume_press(&mut self, button: GamepadButton) -> bool {
+        self.just_pressed.remove(&button).is_some()
+    }
+    /// Get how long a button has been held in seconds, or `0.0` if it is not held
+    pub fn held_time(&self, button: GamepadButton) -> f32 {
+        self.press_times
+            .get(&button)
+            .map_or(0.0, |time| time.elapsed().as_secs_f32())
+    }
+    /**
+    Get how far a button is pressed, from `0.0` to `1.0`
+
+    This is useful for analog triggers.
+    */
+    pub fn button_value(&self, button: GamepadButton) -> f32 {
+        self.button_values
+            .get(&button)
+            .copied()
+            .unwrap_or_else(|| self.button(button) as u8 as f32)
+    }
+    /// Get the value of an axis, from `-1.0` to `1.0`
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+    /// Get the value of an axis before the gamepad settings are applied
+    pub fn raw_axis(&self, axis: GamepadAxis) -> f32 {
+        self.raw_axes.get(&axis).copied().unwrap_or(0.0)
+    }
+}
+
+impl StateTracker {
+    /// Update the tracked state of a gamepad from an event
+    pub(crate) fn apply_gamepad(&mut self, id: usize, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Button {
+                button,
+                state: ButtonState::Pressed,
+            } => {
+                let gamepad = self.gamepads.entry(id).or_default();
+                if gamepad.pressed.insert(button) {
+                    gamepad.just_pressed.insert(button, 0);
+                    gamepad.press_times.insert(button, Instant::now());
+                    self.capture_press(button.into());
+                }
+            }
+            GamepadEvent::Button {
+                button,
+                state: ButtonState::Released,
+            } => {
+                let gamepad = self.gamepads.entry(id).or_default();
+                if gamepad.pressed.remove(&button) {
+                    gamepad.just_released.insert(button, 0);
+                }
+                gamepad.press_times.remove(&button);
+            }
+            GamepadEvent::Axis { axis, value } => {
+                self.input_map.move_axis(axis, value);
+                self.gamepads
+                    .entry(id)
+                    .or_default()
+                    .axes
+                    .insert(axis, value);
+            }
+            GamepadEvent::Connected => {
+                self.gamepads.entry(id).or_default();
+            }
+            GamepadEvent::Disconnected => {
+                self.gamepads.remove(&id);
+            }
+        }
+    }
+    /// Get the ids of the connected gamepads
+    pub fn gamepads(&self) -> impl Iterator<Item = usize> + '_ {
+        self.gamepads.keys().copied()
+    }
+    /// Get the state of a connected gamepad
+    pub fn gamepad(&self, id: usize) -> Option<&GamepadState> {
+        self.gamepads.get(&id)
+    }
+    /// Get whether a button is pressed on any gamepad
+    pub fn gamepad_button(&self, button: GamepadButton) -> bool {
+        self.gamepads.values().any(|state| state.button(button))
+    }
+    /// Check if a button was pressed on any gamepad since the last update
+    pub fn gamepad_button_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepads
+            .values()
+            .any(|state| state.button_pressed(button))
+    }
+    /// Check if a button was released on any gamepad since the last update
+    pub fn gamepad_button_released(&self, button: GamepadButton) -> bool {
+        self.gamepads
+            .values()
+            .any(|state| state.button_released(button))
+    }
+    /**
+    Get the value of a gamepad axis, from `-1.0` to `1.0`
+
+    If multiple gamepads are connected, the value furthest from `0.0` is used.
+    */
+    pub fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .values()
+            .map(|state| state.axis(axis))
+            .fold(0.0, |a, b| if b.abs() > a.abs() { b } else { a })
+    }
+}
+
+/// A haptic feedback effect for gamepads
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rumble {
+    /// The strength of the strong, low frequency motor, from `0.0` to `1.0`
+    pub strong: f32,
+    /// The strength of the weak, high frequency motor, from `0.0` to `1.0`
+    pub weak: f32,
+    /// The duration in seconds
+    pub duration: f32,
+}
+
+impl Rumble {
+    /// Create a new `Rumble` that runs both motors at the same strength
+    pub const fn new(strength: f32, duration: f32) -> Self {
+        Rumble {
+            strong: strength,
+            weak: strength,
+            duration,
+        }
+    }
+    /// Set the strength of the strong motor
+    pub const fn strong(self, strong: f32) -> Self {
+        Rumble { strong, ..self }
+    }
+    /// Set the strength of the weak motor
+    pub const fn weak(self, weak: f32) -> Self {
+        Rumble { weak, ..self }
+    }
+}
+
+impl<R> Context<R>
+where
+    R: Resources,
+{
+    /// Check if a gamepad is connected and supports rumble
+    pub fn gamepad_supports_rumble(&self, id: usize) -> bool {
+        self.gilrs.iter().any(|gilrs| {
+            gilrs
+                .gamepads()
+                .any(|(gid, gamepad)| Into::<usize>::into(gid) == id && gamepad.is_ff_supported())
+        })
+    }
+    /**
+    Rumble a gamepad
+
+    This does nothing if the gamepad is not connected or does not support rumble.
+    */
+    pub fn rumble(&mut self, id: usize, rumble: Rumble) -> KuleResult<()> {
+        self.rumble_gamepads(rumble, |gid| Into::<usize>::into(gid) == id)
+    }
+    /// Rumble every connected gamepad that supports it
+    pub fn rumble_all(&mut self, rumble: Rumble) -> KuleResult<()> {
+        self.rumble_gamepads(rumble, |_| true)
+    }
+    /// Stop all rumbles
+    pub fn stop_rumble(&mut self) {
+        self.rumbles.clear();
+    }
+    fn rumble_gamepads<F>(&mut self, rumble: Rumble, filter: F) -> KuleResult<()>
+    where
+        F: Fn(GamepadId) -> bool,
+    {
+        // Effects stop when they are dropped, so they are kept until they finish
+        let now = Instant::now();
+        self.rumbles.retain(|(_, end)| *end > now);
+        let gilrs = if let Some(gilrs) = &mut self.gilrs {
+            gilrs
+        } else {
+            return Ok(());
+        };
+        let ids: Vec<GamepadId> = gilrs
+            .gamepads()
+            .filter(|(id, gamepad)| gamepad.is_ff_supported() && filter(*id))
+            .map(|(id, _)| id)
+            .collect();
+        if ids.is_empty() || rumble.duration <= 0.0 {
+            return Ok(());
+        }
+        let duration = Ticks::from_ms((rumble.duration * 1000.0) as u32);
+        let magnitude = |strength: f32| (strength.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        let scheduling = Replay {
+            play_for: duration,
+            ..Default::default()
+        };
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude(rumble.strong),
+                },
+                scheduling,
+                envelope: Default::default(),
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude(rumble.weak),
+                },
+                scheduling,
+                envelope: Default::default(),
+            })
+            .repeat(Repeat::For(duration))
+            .gamepads(&ids)
+            .finish(gilrs)?;
+        effect.play()?;
+        let end = now + Duration::from_secs_f32(rumble.duration);
+        self.rumbles.push((effect, end));
+        Ok(())
+    }
+}
+
+/// Get the pending gamepad events and update the tracker with them
+pub(crate) fn gamepad_events(
+    gilrs: &mut Gilrs,
+    settings: &GamepadSettings,
+    tracker: &mut StateTracker,
+) -> Vec<Event> {
+    let mut events = Vec::new();
+    while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+        let id: usize = id.into();
+        let event = match event {
+            EventType::ButtonPressed(button, _) | EventType::ButtonReleased(button, _) => {
+                let button = GamepadButton::from_gilrs(button);
+                let state = if let EventType::ButtonPressed(..) = event {
+                    ButtonState::Pressed
+                } else {
+                    ButtonState::Released
+                };
+                GamepadEvent::Button { button, state }
+            }
+            EventType::ButtonChanged(button, value, _) => {
+                let button = GamepadButton::from_gilrs(button);
+                let gamepad = tracker.gamepads.entry(id).or_default();
+                gamepad.button_values.insert(button, value);
+                continue;
+            }
+            EventType::AxisChanged(axis, raw, _) => {
+                let axis = GamepadAxis::from_gilrs(axis);
+                let value = settings.axis(axis).apply(raw);
+                let gamepad = tracker.gamepads.entry(id).or_default();
+                gamepad.raw_axes.insert(axis, raw);
+                GamepadEvent::Axis { axis, value }
+            }
+            EventType::Connected => GamepadEvent::Connected,
+            EventType::Disconnected => GamepadEvent::Disconnected,
+            EventType::ButtonRepeated(..) | EventType::Dropped => continue,
+        };
+        tracker.apply_gamepad(id, event);
+        events.push(Event::Gamepad { id, event });
+    }
+    events
+}
@@ -0,0 +1,174 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use vector2math::*;
+
+use crate::{Camera, Event, TouchPhase, Vec2};
+
+/// A gesture made with touches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// A finger touched the screen briefly without moving
+    Tap {
+        /// The position in window space
+        pos: Vec2,
+    },
+    /// A single finger moved across the screen
+    Pan {
+        /// The position of the finger in window space
+        pos: Vec2,
+        /// How far the finger moved since the last pan
+        delta: Vec2,
+    },
+    /// Two fingers moved toward or away from each other
+    Pinch {
+        /// The point between the fingers in window space
+        center: Vec2,
+        /// The ratio of the distance between the fingers to the distance at the last pinch
+        scale: f32,
+        /// How far the center moved since the last pinch
+        delta: Vec2,
+    },
+}
+
+impl Gesture {
+    /**
+    Move a camera so that the world follows the gesture
+
+    Pans drag the view, and pinches zoom on the point between the fingers
+    while dragging the view with it. Taps do not move the camera.
+    */
+    pub fn move_camera(self, camera: Camera) -> Camera {
+        let drag = |camera: Camera, pos: Vec2, delta: Vec2| {
+            let old = camera.pos_to_coords(pos.sub(delta));
+            let new = camera.pos_to_coords(pos);
+            camera.translate(old.sub(new))
+        };
+        match self {
+            Gesture::Tap { .. } => camera,
+            Gesture::Pan { pos, delta } => drag(camera, pos, delta),
+            Gesture::Pinch {
+                center,
+                scale,
+                delta,
+            } => drag(camera.zoom_on(center, scale), center, delta),
+        }
+    }
+}
+
+/// A finger that is being tracked by a gesture recognizer
+#[derive(Debug, Clone, Copy)]
+struct TrackedTouch {
+    start: Vec2,
+    pos: Vec2,
+    time: Instant,
+    /// Whether the touch can no longer be a tap
+    moved: bool,
+}
+
+/**
+Recognizes gestures from touch events
+
+Pass every event to [`GestureRecognizer::event`](struct.GestureRecognizer.html#method.event).
+*/
+#[derive(Debug, Clone)]
+pub struct GestureRecognizer {
+    /// The longest time in seconds that a touch can last and still be a tap
+    pub tap_time: f32,
+    /// The distance in pixels that a finger must move before it starts panning
+    pub slop: f32,
+    touches: BTreeMap<u64, TrackedTouch>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        GestureRecognizer::new()
+    }
+}
+
+impl GestureRecognizer {
+    /// Create a new `GestureRecognizer` with a tap time of `0.3` seconds and a slop of `10` pixels
+    pub fn new() -> Self {
+        GestureRecognizer {
+            tap_time: 0.3,
+            slop: 10.0,
+            touches: BTreeMap::new(),
+        }
+    }
+    /// Get the gesture that an event continues or completes, if any
+    pub fn event(&mut self, event: &Event) -> Option<Gesture> {
+        let (id, phase, pos) = match *event {
+            Event::Touch { id, phase, pos } => (id, phase, pos),
+            _ => return None,
+        };
+        match phase {
+            TouchPhase::Started => {
+                let multiple = !self.touches.is_empty();
+                self.touches.insert(
+                    id,
+                    TrackedTouch {
+                        start: pos,
+                        pos,
+                        time: Instant::now(),
+                        moved: multiple,
+                    },
+                );
+                // Touches that are part of a multi-finger gesture are not taps
+                if multiple {
+                    for touch in self.touches.values_mut() {
+                        touch.moved = true;
+                    }
+                }
+                None
+            }
+            TouchPhase::Moved => {
+                let old_pair = self.pair();
+                let slop = self.slop;
+                let touch = self.touches.get_mut(&id)?;
+                let prev = touch.pos;
+                touch.pos = pos;
+                if touch.start.dist(pos) > slop {
+                    touch.moved = true;
+                }
+                let moved = touch.moved;
+                match (self.touches.len(), old_pair, self.pair()) {
+                    (1, ..) if moved => Some(Gesture::Pan {
+                        pos,
+                        delta: pos.sub(prev),
+                    }),
+                    (2, Some((old_center, old_dist)), Some((center, dist))) => {
+                        Some(Gesture::Pinch {
+                            center,
+                            // Fingers that meet would zoom to nothing
+                            scale: if old_dist > 0.0 && dist > 0.0 {
+                                dist / old_dist
+                            } else {
+                                1.0
+                            },
+                            delta: center.sub(old_center),
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            TouchPhase::Ended => {
+                let touch = self.touches.remove(&id)?;
+                if !touch.moved && touch.time.elapsed().as_secs_f32() <= self.tap_time {
+                    Some(Gesture::Tap { pos })
+                } else {
+                    None
+                }
+            }
+            TouchPhase::Cancelled => {
+                self.touches.remove(&id);
+                None
+            }
+        }
+    }
+    /// Get the center of and distance between the first two touches
+    fn pair(&self) -> Option<(Vec2, f32)> {
+        let mut touches = self.touches.values();
+        let a = touches.next()?.pos;
+        let b = touches.next()?.pos;
+        Some((a.add(b).div(2.0), a.dist(b)))
+    }
+}
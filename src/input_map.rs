@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+#[cfg(feature = "script")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "script")]
+use crate::KuleResult;
+#[cfg(feature = "gamepad")]
+use crate::{GamepadAxis, GamepadButton};
+use crate::{Key, MouseButton, StateTracker};
+
+/// A physical button that can be bound to an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+#[cfg_attr(feature = "ser", serde(tag = "type", content = "input"))]
+pub enum Input {
+    /// A keyboard key
+    Key(Key),
+    /// A mouse button
+    MouseButton(MouseButton),
+    /// A button on any gamepad
+    #[cfg(feature = "gamepad")]
+    GamepadButton(GamepadButton),
+}
+
+impl From<Key> for Input {
+    fn from(key: Key) -> Self {
+        Input::Key(key)
+    }
+}
+
+impl From<MouseButton> for Input {
+    fn from(mb: MouseButton) -> Self {
+        Input::MouseButton(mb)
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl From<GamepadButton> for Input {
+    fn from(button: GamepadButton) -> Self {
+        Input::GamepadButton(button)
+    }
+}
+
+/// A physical input that can be bound to an axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+#[cfg_attr(feature = "ser", serde(tag = "type", content = "input"))]
+pub enum AxisInput {
+    /// A pair of buttons, one for each direction
+    Buttons {
+        /// The button for the negative direction
+        neg: Input,
+        /// The button for the positive direction
+        pos: Input,
+    },
+    /// An axis on any gamepad
+    #[cfg(feature = "gamepad")]
+    GamepadAxis(GamepadAxis),
+}
+
+impl AxisInput {
+    /// Create a new axis input from a pair of buttons
+    pub fn buttons<N, P>(neg: N, pos: P) -> Self
+    where
+        N: Into<Input>,
+        P: Into<Input>,
+    {
+        AxisInput::Buttons {
+            neg: neg.into(),
+            pos: pos.into(),
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl From<GamepadAxis> for AxisInput {
+    fn from(axis: GamepadAxis) -> Self {
+        AxisInput::GamepadAxis(axis)
+    }
+}
+
+/// How far a gamepad axis must be moved to be captured
+#[cfg(feature = "gamepad")]
+const AXIS_CAPTURE_THRESHOLD: f32 = 0.5;
+
+/// What the next pressed input will be bound to
+#[derive(Debug, Clone, PartialEq)]
+enum Capture {
+    /// An action
+    Action(String),
+    /// An axis, along with its negative button if it has already been pressed
+    Axis { axis: String, neg: Option<Input> },
+}
+
+/**
+Bindings of named actions and axes to physical inputs
+
+Game logic can query actions and axes by name through the
+[`StateTracker`](struct.StateTracker.html), which keeps it independent of
+which keys, mouse buttons, or gamepad inputs the player has bound.
+
+An action or axis can have any number of bindings. An action is active if
+any of its inputs is held, and an axis's value is the sum of its inputs'
+values, clamped between `-1.0` and `1.0`.
+
+With the `ser` feature, input maps can be serialized so that games can save
+the player's controls. With the `script` feature, they can be saved to and
+loaded from toml files directly.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct InputMap {
+    actions: HashMap<String, Vec<Input>>,
+    axes: HashMap<String, Vec<AxisInput>>,
+    /// What the next pressed input will be bound to
+    #[cfg_attr(feature = "ser", serde(skip))]
+    capturing: Option<Capture>,
+}
+
+impl InputMap {
+    /// Create a new empty `InputMap`
+    pub fn new() -> Self {
+        InputMap::default()
+    }
+    /// Add a binding to an action
+    pub fn bind<S, I>(&mut self, action: S, input: I)
+    where
+        S: Into<String>,
+        I: Into<Input>,
+    {
+        let input = input.into();
+        let bindings = self.actions.entry(action.into()).or_default();
+        if !bindings.contains(&input) {
+            bindings.push(input);
+        }
+    }
+    /// Add a binding to an axis
+    pub fn bind_axis<S, A>(&mut self, axis: S, input: A)
+    where
+        S: Into<String>,
+        A: Into<AxisInput>,
+    {
+        let input = input.into();
+        let bindings = self.axes.entry(axis.into()).or_default();
+        if !bindings.contains(&input) {
+            bindings.push(input);
+        }
+    }
+    /// Replace all of an action's bindings with a single input
+    pub fn rebind<S, I>(&mut self, action: S, input: I)
+    where
+        S: Into<String>,
+        I: Into<Input>,
+    {
+        self.actions.insert(action.into(), vec![input.into()]);
+    }
+    /// Replace all of an axis's bindings with a single input
+    pub fn rebind_axis<S, A>(&mut self, axis: S, input: A)
+    where
+        S: Into<String>,
+        A: Into<AxisInput>,
+    {
+        self.axes.insert(axis.into(), vec![input.into()]);
+    }
+    /// Remove all of an action's bindings
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+    /// Remove all of an axis's bindings
+    pub fn unbind_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+    }
+    /// Get the inputs bound to an action
+    pub fn bindings(&self, action: &str) -> &[Input] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+    /// Get the inputs bound to an axis
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisInput] {
+        self.axes.get(axis).map(Vec::as_slice).unwrap_or(&[])
+    }
+    /// Get the names of all actions with bindings
+    pub fn actions(&self) -> impl Iterator<Item = &str> + '_ {
+        self.actions.keys().map(String::as_str)
+    }
+    /// Get the names of all axes with bindings
+    pub fn axes(&self) -> impl Iterator<Item = &str> + '_ {
+        self.axes.keys().map(String::as_str)
+    }
+    /**
+    Bind the next pressed key, mouse button, or gamepad button to an action
+
+    The captured input replaces all of the action's bindings. This is useful
+    for controls menus that let the player press the input they want to use.
+    The captured press is consumed, so it does not also press the action.
+    Only one action or axis can be captured at a time.
+    */
+    pub fn capture<S>(&mut self, action: S)
+    where
+        S: Into<String>,
+    {
+        self.capturing = Some(Capture::Action(action.into()));
+    }
+    /**
+    Bind the next two pressed buttons, or the next moved gamepad axis, to an axis
+
+    The first button is bound to the negative direction and the second to the
+    positive direction. The captured input replaces all of the axis's bindings,
+    and the captured presses are consumed like with
+    [`InputMap::capture`](struct.InputMap.html#method.capture).
+    */
+    pub fn capture_axis<S>(&mut self, axis: S)
+    where
+        S: Into<String>,
+    {
+        self.capturing = Some(Capture::Axis {
+            axis: axis.into(),
+            neg: None,
+        });
+    }
+    /// Get the action or axis that the next pressed input will be bound to, if any
+    pub fn capturing(&self) -> Option<&str> {
+        self.capturing.as_ref().map(|capture| match capture {
+            Capture::Action(action) => action.as_str(),
+            Capture::Axis { axis, .. } => axis.as_str(),
+        })
+    }
+    /// Stop waiting for an input to bind
+    pub fn cancel_capture(&mut self) {
+        self.capturing = None;
+    }
+    /**
+    Bind a pressed input to the action or axis being captured, if there is one
+
+    Returns whether the input was captured.
+    */
+    pub(crate) fn press(&mut self, input: Input) -> bool {
+        match self.capturing.take() {
+            Some(Capture::Action(action)) => self.rebind(action, input),
+            Some(Capture::Axis { axis, neg: None }) => {
+                self.capturing = Some(Capture::Axis {
+                    axis,
+                    neg: Some(input),
+                })
+            }
+            // Pressing the negative button again does not bind it to both directions
+            Some(Capture::Axis {
+                axis,
+                neg: Some(neg),
+            }) if neg == input => {
+                self.capturing = Some(Capture::Axis {
+                    axis,
+                    neg: Some(neg),
+                })
+            }
+            Some(Capture::Axis {
+                axis,
+                neg: Some(neg),
+            }) => self.rebind_axis(axis, AxisInput::Buttons { neg, pos: input }),
+            None => return false,
+        }
+        true
+    }
+    /// Bind a moved gamepad axis to the axis being captured, if there is one
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn move_axis(&mut self, axis: GamepadAxis, value: f32) {
+        if value.abs() < AXIS_CAPTURE_THRESHOLD {
+            return;
+        }
+        if let Some(Capture::Axis { axis: name, .. }) = &self.capturing {
+            let name = name.clone();
+            self.capturing = None;
+            self.rebind_axis(name, axis);
+        }
+    }
+    #[cfg(feature = "script")]
+    /// Save the input map to a toml file
+    pub fn save<P>(&self, path: P) -> KuleResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+    #[cfg(feature = "script")]
+    /// Load an input map from a toml file
+    pub fn load<P>(path: P) -> KuleResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+impl StateTracker {
+    /// Get the input map
+    pub fn input_map(&self) -> &InputMap {
+        &self.input_map
+    }
+    /// Get a mutable reference to the input map
+    pub fn input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
+    /// Set the input map
+    pub fn set_input_map(&mut self, input_map: InputMap) {
+        self.input_map = input_map;
+    }
+    /// Let the input map capture a pressed input, consuming the press if it is captured
+    pub(crate) fn capture_press(&mut self, input: Input) {
+        if self.input_map.press(input) {
+            self.consume_press(input);
+        }
+    }
+    /// Get whether an input is held
+    pub fn input(&self, input: Input) -> bool {
+        match input {
+            Input::Key(key) => self.key(key),
+            Input::MouseButton(mb) => self.mouse_button(mb),
+            #[cfg(feature = "gamepad")]
+            Input::GamepadButton(button) => self.gamepad_button(button),
+        }
+    }
+    /// Check if an input was pressed since the last update
+    pub fn input_pressed(&self, input: Input) -> bool {
+        match input {
+            Input::Key(key) => self.key_pressed(key),
+            Input::MouseButton(mb) => self.mouse_button_pressed(mb),
+            #[cfg(feature = "gamepad")]
+            Input::GamepadButton(button) => self.gamepad_button_pressed(button),
+        }
+    }
+    /// Check if an input was released since the last update
+    pub fn input_released(&self, input: Input) -> bool {
+        match input {
+            Input::Key(key) => self.key_released(key),
+            Input::MouseButton(mb) => self.mouse_button_released(mb),
+            #[cfg(feature = "gamepad")]
+            Input::GamepadButton(button) => self.gamepad_button_released(button),
+        }
+    }
+    /// Get how far an input is pressed, from `0.0` to `1.0`
+    fn input_value(&self, input: Input) -> f32 {
+        match input {
+            #[cfg(feature = "gamepad")]
+            Input::GamepadButton(button) => self
+                .gamepads
+                .values()
+                .map(|state| state.button_value(button))
+                .fold(0.0, f32::max),
+            input => self.input(input) as u8 as f32,
+        }
+    }
+    /// Get whether any of an action's inputs are held
+    pub fn action(&self, action: &str) -> bool {
+        let bindings = self.input_map.bindings(action);
+        bindings.iter().any(|&input| self.input(input))
+    }
+    /**
+    Check if an action was pressed since the last update
+
+    This is `false` if another of the action's inputs was already held.
+    */
+    pub fn action_pressed(&self, action: &str) -> bool {
+        let bindings = self.input_map.bindings(action);
+        bindings.iter().any(|&input| self.input_pressed(input))
+            && bindings
+                .iter()
+                .all(|&input| self.input_pressed(input) || !self.input(input))
+    }
+    /**
+    Check if an action was released since the last update
+
+    This is `false` if another of the action's inputs is still held.
+    */
+    pub fn action_released(&self, action: &str) -> bool {
+        let bindings = self.input_map.bindings(action);
+        bindings.iter().any(|&input| self.input_released(input)) && !self.action(action)
+    }
+    /**
+    Forget that an action was pressed so that it is not acted on again
+
+    This is useful with an [input buffer](struct.StateTracker.html#method.set_input_buffer).
+    Returns whether the action was pressed recently.
+    */
+    pub fn consume_action(&mut self, action: &str) -> bool {
+        let mut consumed = false;
+        for input in self.input_map.bindings(action).to_vec() {
+            consumed |= self.consume_press(input);
+        }
+        consumed
+    }
+    /// Get the value of an axis, from `-1.0` to `1.0`
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.input_map
+            .axis_bindings(axis)
+            .iter()
+            .map(|&input| match input {
+                AxisInput::Buttons { neg, pos } => self.input_value(pos) - self.input_value(neg),
+                #[cfg(feature = "gamepad")]
+                AxisInput::GamepadAxis(axis) => self.gamepad_axis(axis),
+            })
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+}
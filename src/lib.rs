@@ -2,10 +2,11 @@
 #![warn(missing_docs)]
 
 /*!
-A textureless 2d game engine
+A vector-focused 2d game engine
 
-Kule is a game engine with a focus on rendering vector graphics. It has no support for textures or sprites.
-This makes making games easier for the unartistic programmer, but restricts art style.
+Kule is a game engine with a focus on rendering vector graphics. This makes making games
+easier for the unartistic programmer. Simple [`Texture`](struct.Texture.html) support is
+available for when images are needed.
 
 # Usage
 
@@ -29,11 +30,15 @@ glyph geometry, 2D meshes, and sound buffers. The id types defined by `Resources
 
 ## The `Drawer` struct
 
-The [`Drawer`](struct.Drawer.html) struct is used to render 2D geometry.
+The [`Drawer`](struct.Drawer.html) struct is used to render 2D geometry and images.
 */
 
 mod app;
 pub use app::*;
+mod batch;
+pub use batch::DrawStats;
+mod boolean;
+pub use boolean::*;
 mod context;
 pub use context::*;
 mod error;
@@ -47,10 +52,44 @@ mod color;
 pub use color::*;
 mod font;
 pub use font::*;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+#[cfg(feature = "gamepad")]
+pub use gamepad::*;
+mod gesture;
+pub use gesture::*;
+mod input_map;
+pub use input_map::*;
+mod lighting;
+pub use lighting::*;
+mod mesh;
+pub use mesh::*;
+mod path;
+pub use path::*;
+mod plot;
+pub use plot::*;
+mod post;
+pub use post::Bloom;
+mod recording;
+pub use recording::*;
+mod shader;
+pub use shader::*;
+mod shortcut;
+pub use shortcut::*;
+#[cfg(feature = "shaping")]
+mod shaping;
+mod system_font;
+pub use system_font::*;
+mod texture;
+pub use texture::*;
 #[cfg(feature = "sound")]
 mod sound;
 #[cfg(feature = "sound")]
 pub use sound::*;
+#[cfg(feature = "svg")]
+mod svg;
+#[cfg(feature = "svg")]
+pub use svg::*;
 #[cfg(feature = "script")]
 mod script;
 #[cfg(feature = "script")]
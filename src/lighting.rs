@@ -0,0 +1,280 @@
+use std::iter::once;
+
+use glium::backend::Facade;
+use vector2math::*;
+
+use crate::{
+    BlendMode, Camera, CanFail, Canvas, Col, Color, Context, Drawer, Gradient, GradientShape,
+    KuleResult, RenderTarget, Resources, Transformable, Vec2,
+};
+
+/// The number of segments used to draw the edge of a light
+const LIGHT_RESOLUTION: u16 = 32;
+
+/// The direction and spread of a cone light
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cone {
+    /// The angle in radians that the cone points in
+    pub direction: f32,
+    /// The angle in radians between the edges of the cone
+    pub spread: f32,
+}
+
+/**
+A light that illuminates the area around it
+
+The light is brightest at its position and fades out to nothing at its radius.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    /// The position of the light
+    pub pos: Vec2,
+    /// The distance at which the light fades out completely
+    pub radius: f32,
+    /// The color of the light
+    pub color: Col,
+    /// The brightness of the light, which multiplies its color
+    pub intensity: f32,
+    /// The cone that limits the light, if any
+    pub cone: Option<Cone>,
+}
+
+impl Light {
+    /// Create a new white point light
+    pub const fn point(pos: Vec2, radius: f32) -> Self {
+        Light {
+            pos,
+            radius,
+            color: [1.0; 4],
+            intensity: 1.0,
+            cone: None,
+        }
+    }
+    /// Create a new white cone light
+    pub const fn cone(pos: Vec2, radius: f32, direction: f32, spread: f32) -> Self {
+        Light {
+            cone: Some(Cone { direction, spread }),
+            ..Light::point(pos, radius)
+        }
+    }
+    /// Set the color
+    pub const fn color(self, color: Col) -> Self {
+        Light { color, ..self }
+    }
+    /// Set the intensity
+    pub const fn intensity(self, intensity: f32) -> Self {
+        Light { intensity, ..self }
+    }
+}
+
+/**
+A texture that lights are accumulated into
+
+Lights are added together on top of an ambient color. When the light map
+is drawn with [`Drawer::light_map`](struct.Drawer.html#method.light_map),
+it is multiplied over everything that has already been drawn, so areas
+without light take the ambient color and lit areas are brightened.
+
+The light map does not have to be the same size as the window. Because
+lighting is usually smooth, a smaller light map is often indistinguishable
+from a full size one.
+
+Occluder polygons cast hard shadows from every light.
+The insides of the occluders are in shadow as well.
+*/
+#[derive(Debug)]
+pub struct LightMap {
+    target: RenderTarget,
+    /// The color of unlit areas
+    pub ambient: Col,
+    /// The lights
+    pub lights: Vec<Light>,
+    /// The polygons that block light
+    pub occluders: Vec<Vec<Vec2>>,
+}
+
+impl LightMap {
+    /// Create a new light map with the given size in pixels and a black ambient color
+    pub fn new<F>(facade: &F, size: [u32; 2]) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        Ok(LightMap {
+            target: RenderTarget::new(facade, size)?,
+            ambient: Col::black(),
+            lights: Vec::new(),
+            occluders: Vec::new(),
+        })
+    }
+    /// Add a light
+    pub fn add(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+    /// Remove all lights
+    pub fn clear(&mut self) {
+        self.lights.clear();
+    }
+    /// Add a polygon that blocks light
+    pub fn add_occluder<'p, V, P>(&mut self, vertices: P)
+    where
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        self.occluders
+            .push(vertices.into_iter().map(|v| v.map()).collect());
+    }
+    /// Remove all occluders
+    pub fn clear_occluders(&mut self) {
+        self.occluders.clear();
+    }
+    /// Get the render target that the lights are drawn to
+    pub fn target(&self) -> &RenderTarget {
+        &self.target
+    }
+    /**
+    Draw the lights to the light map
+
+    The camera should be the same one that the scene is drawn with
+    so that the lights line up with it.
+    */
+    pub fn render<R>(&self, ctx: &Context<R>, camera: Camera) -> CanFail
+    where
+        R: Resources,
+    {
+        ctx.draw_to(&self.target, |draw| {
+            let size: Vec2 = self.target.size().map_with(|d| d as f32);
+            // The light map is stretched over the window, so each axis is scaled separately
+            let stretch = size.div2(camera.window_size());
+            draw.camera = Camera {
+                projection: camera.projection.scale(stretch),
+                window_size: size,
+                scale: 1.0,
+                ..camera
+            };
+            draw.clear(self.ambient);
+            draw.with_blend_mode(BlendMode::Add, |draw| {
+                for light in &self.lights {
+                    if self.occluders.is_empty() {
+                        draw.light(light);
+                    } else {
+                        draw.mask_outside(
+                            |draw| {
+                                for occluder in &self.occluders {
+                                    for shadow in shadow_quads(light, occluder) {
+                                        draw.polygon(Col::white(), &shadow);
+                                    }
+                                }
+                            },
+                            |draw| {
+                                draw.light(light);
+                            },
+                        );
+                    }
+                }
+            });
+            Ok(())
+        })
+    }
+}
+
+/// The distance from a light within which occluder vertices cast no shadow
+const MIN_SHADOW_DIST: f32 = 1e-4;
+
+/**
+Get the shadow that each edge of an occluder casts from a light
+
+Each shadow is the quad between the edge and the edge's projection
+away from the light. The projection is far enough that the far side
+of the quad is entirely outside the light's radius. Edges that touch
+the light cast no shadow, as they have no direction to project in.
+*/
+fn shadow_quads<'a>(
+    light: &'a Light,
+    occluder: &'a [Vec2],
+) -> impl Iterator<Item = [Vec2; 4]> + 'a {
+    (0..occluder.len())
+        .map(move |i| (occluder[i], occluder[(i + 1) % occluder.len()]))
+        .filter(move |&(a, b)| {
+            segment_dist(light.pos, a, b) < light.radius
+                && a.dist(light.pos) > MIN_SHADOW_DIST
+                && b.dist(light.pos) > MIN_SHADOW_DIST
+        })
+        .map(move |(a, b)| {
+            let da = a.sub(light.pos).unit();
+            let db = b.sub(light.pos).unit();
+            // The far side is a chord whose closest point to the light is
+            // at the projected distance times the cosine of half the angle
+            let half_cos = ((1.0 + da.dot(db)) / 2.0).max(0.0).sqrt().max(0.01);
+            let far = (light.radius / half_cos)
+                .max(a.dist(light.pos))
+                .max(b.dist(light.pos));
+            [a, b, light.pos.add(db.mul(far)), light.pos.add(da.mul(far))]
+        })
+}
+
+/// Get the distance from a point to a line segment
+fn segment_dist(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b.sub(a);
+    let len2 = ab.dot(ab);
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (p.sub(a).dot(ab) / len2).clamp(0.0, 1.0)
+    };
+    p.dist(a.add(ab.mul(t)))
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /**
+    Draw a single light
+
+    This is used to draw the lights of a [`LightMap`](struct.LightMap.html),
+    but it can be used with additive blending to make simple glows as well.
+    */
+    pub fn light(&mut self, light: &Light) -> Transformable<'ctx, '_, T, R> {
+        let color = light.color.mul(light.intensity);
+        let gradient = Gradient::new(color, Col::black()).stop(0.5, color.mul(0.25));
+        let shape = GradientShape::Radial {
+            center: light.pos,
+            radius: light.radius,
+        };
+        if let Some(cone) = light.cone {
+            let start = cone.direction - cone.spread / 2.0;
+            let vertices: Vec<Vec2> = once(light.pos)
+                .chain((0..=LIGHT_RESOLUTION).map(|i| {
+                    let angle = start + cone.spread * i as f32 / LIGHT_RESOLUTION as f32;
+                    light.pos.add(angle.angle_as_vector().mul(light.radius))
+                }))
+                .collect();
+            self.polygon_gradient(&vertices, &gradient, shape)
+        } else {
+            self.circle_gradient(
+                (light.pos, light.radius),
+                LIGHT_RESOLUTION,
+                &gradient,
+                shape,
+            )
+        }
+    }
+    /**
+    Draw a light map over everything that has been drawn
+
+    The light map's lights should be drawn with
+    [`LightMap::render`](struct.LightMap.html#method.render) first.
+    */
+    pub fn light_map(&mut self, light_map: &LightMap) {
+        let window_size = self.camera.window_size();
+        self.with_absolute_camera(|draw| {
+            draw.with_blend_mode(BlendMode::Multiply, |draw| {
+                draw.image(
+                    light_map.target.texture(),
+                    [0.0, 0.0, window_size[0], window_size[1]],
+                );
+            })
+        });
+    }
+}
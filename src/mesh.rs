@@ -0,0 +1,521 @@
+use std::{
+    cell::{Ref, RefCell},
+    iter::once,
+    rc::Rc,
+};
+
+use vector2math::*;
+
+use crate::{
+    bounded_vertices, fan_indices, line_transform, mesh_outline, polygon_indices, rect_transform,
+    regular_vertices, Canvas, Col, Color, DrawItem, DrawType, Drawer, GlyphCache, GlyphSize,
+    GlyphSpec, Gradient, GradientShape, Outline, Rect, Resources, Trans, Transformable, Vec2,
+    Vertex,
+};
+
+/**
+Geometry that is built once and can be drawn many times
+
+Building a mesh does all of the work of generating vertices and indices
+up front, so drawing it is cheap. Meshes are cheap to clone, as clones
+share the same geometry.
+
+The vertices of a mesh use the same coordinates as everything else that is drawn,
+and their colors are multiplied by the color the mesh is drawn with.
+*/
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    vertices: Rc<[Vertex]>,
+    indices: Rc<[u32]>,
+    /// The outline used for borders, which is found the first time it is needed
+    outline: Rc<RefCell<Option<Outline>>>,
+}
+
+impl Mesh {
+    /**
+    Create a new mesh from vertices and indices
+
+    Every 3 indices form a triangle. Indices are 32-bit, so meshes are not
+    limited to 65536 vertices.
+
+    # Panics
+
+    Panics if the number of indices is not a multiple of 3 or if any index
+    is out of bounds
+    */
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        assert_eq!(indices.len() % 3, 0, "Mesh indices do not form triangles");
+        assert!(
+            indices.iter().all(|&i| (i as usize) < vertices.len()),
+            "Mesh index out of bounds"
+        );
+        Mesh {
+            vertices: vertices.into(),
+            indices: indices.into(),
+            outline: Rc::default(),
+        }
+    }
+    /// Create a new mesh from a polygon
+    pub fn polygon<'p, V, P>(vertices: P) -> Self
+    where
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        let positions: Vec<Vec2> = vertices.into_iter().map(|v| v.map()).collect();
+        let indices = polygon_indices(&positions);
+        let vertices = bounded_vertices(positions);
+        Mesh::new(vertices, indices)
+    }
+    /// Create a new mesh from a polygon where each vertex has its own color
+    pub fn polygon_colored<V, C, P>(vertices: P) -> Self
+    where
+        V: Vector2<Scalar = f32>,
+        C: Color,
+        P: IntoIterator<Item = (V, C)>,
+    {
+        let (positions, colors): (Vec<_>, Vec<_>) = vertices
+            .into_iter()
+            .map(|(v, c)| (v.map::<Vec2>(), c.map::<Col>()))
+            .unzip();
+        let indices = polygon_indices(&positions);
+        let vertices: Vec<Vertex> = bounded_vertices(positions)
+            .into_iter()
+            .zip(colors)
+            .map(|(v, color)| Vertex { color, ..v })
+            .collect();
+        Mesh::new(vertices, indices)
+    }
+    /**
+    Create a new mesh from a convex polygon filled with a gradient
+
+    Colors are computed per-vertex. Linear gradients are split along their color stops,
+    so they are exact. Radial gradients are approximated by subdividing the polygon.
+    */
+    pub fn polygon_gradient<'p, V, P>(
+        vertices: P,
+        gradient: &Gradient,
+        shape: GradientShape,
+    ) -> Self
+    where
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        let polygon: Vec<Vec2> = vertices.into_iter().map(|v| v.map()).collect();
+        let triangles: Vec<[Vec2; 3]> = match shape {
+            GradientShape::Linear { .. } => {
+                let mut cuts: Vec<f32> = gradient.stops().iter().map(|(s, _)| *s).collect();
+                cuts.dedup();
+                let bands: Vec<f32> = once(f32::NEG_INFINITY)
+                    .chain(cuts)
+                    .chain(once(f32::INFINITY))
+                    .collect();
+                bands
+                    .windows(2)
+                    .flat_map(|band| {
+                        let clipped = clip_polygon(&polygon, |p| shape.position(p) - band[0]);
+                        let clipped = clip_polygon(&clipped, |p| band[1] - shape.position(p));
+                        fan_triangles(&clipped)
+                    })
+                    .collect()
+            }
+            GradientShape::Radial { radius, .. } => {
+                let diagonal = Rect::bounding(polygon.iter().copied())
+                    .map_or(0.0, |bounds| bounds.size().mag());
+                let segments = diagonal / radius.abs() * RADIAL_GRADIENT_SEGMENTS;
+                let depth = segments.log2().ceil().max(0.0).min(MAX_SUBDIVISIONS as f32) as u32;
+                fan_triangles(&polygon)
+                    .into_iter()
+                    .flat_map(|tri| subdivide(tri, depth))
+                    .collect()
+            }
+        };
+        let mut vertices = bounded_vertices(triangles.into_iter().flatten());
+        for v in &mut vertices {
+            v.color = gradient.sample(shape.position(v.pos));
+        }
+        let indices = (0..vertices.len() as u32).collect();
+        Mesh::new(vertices, indices)
+    }
+    /// Create a new mesh from a string of text
+    pub fn text<S>(glyphs: &GlyphCache, string: &str, size: S) -> Self
+    where
+        S: Into<GlyphSize>,
+    {
+        let size = size.into();
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (transform, id) in glyphs.layout(string, &GlyphSpec::new((), size)) {
+            let (_, geometry) = &*glyphs.glyph(id, size.resolution);
+            let offset = vertices.len() as u32;
+            vertices.extend(
+                bounded_vertices(geometry.vertices.iter().copied())
+                    .into_iter()
+                    .map(|v| Vertex {
+                        pos: v.pos.transform(transform),
+                        ..v
+                    }),
+            );
+            indices.extend(geometry.indices.iter().map(|&i| i as u32 + offset));
+        }
+        Mesh::new(vertices, indices)
+    }
+    /// Get the vertices
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+    /// Get the indices
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+    /// Get the outline of the mesh
+    pub(crate) fn outline(&self) -> Ref<'_, Outline> {
+        if self.outline.borrow().is_none() {
+            *self.outline.borrow_mut() = Some(mesh_outline(&self.vertices, &self.indices));
+        }
+        Ref::map(self.outline.borrow(), |outline| outline.as_ref().unwrap())
+    }
+}
+
+/// The number of grid lines between each line of the next coarser grid
+pub const GRID_SUBDIVISIONS: f32 = 10.0;
+/// The minimum number of pixels between grid lines before they start to fade out
+const MIN_GRID_PIXELS: f32 = 8.0;
+
+/// The number of segments per radius that radial gradients are subdivided into
+const RADIAL_GRADIENT_SEGMENTS: f32 = 16.0;
+/// The maximum number of times a triangle is subdivided for a radial gradient
+const MAX_SUBDIVISIONS: u32 = 6;
+
+/// Clip a convex polygon to where a linear function is not negative
+fn clip_polygon<F>(polygon: &[Vec2], f: F) -> Vec<Vec2>
+where
+    F: Fn(Vec2) -> f32,
+{
+    let mut clipped = Vec::new();
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let (fa, fb) = (f(a), f(b));
+        if fa >= 0.0 {
+            clipped.push(a);
+        }
+        if (fa >= 0.0) != (fb >= 0.0) {
+            clipped.push(a.lerp(b, fa / (fa - fb)));
+        }
+    }
+    clipped
+}
+
+/// Split a convex polygon into triangles
+fn fan_triangles(polygon: &[Vec2]) -> Vec<[Vec2; 3]> {
+    (1..polygon.len().saturating_sub(1))
+        .map(|i| [polygon[0], polygon[i], polygon[i + 1]])
+        .collect()
+}
+
+/// Split a triangle into 4 smaller triangles some number of times
+fn subdivide(tri: [Vec2; 3], depth: u32) -> Vec<[Vec2; 3]> {
+    if depth == 0 {
+        return vec![tri];
+    }
+    let [a, b, c] = tri;
+    let [ab, bc, ca] = [a.lerp(b, 0.5), b.lerp(c, 0.5), c.lerp(a, 0.5)];
+    [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+        .iter()
+        .flat_map(|&tri| subdivide(tri, depth - 1))
+        .collect()
+}
+
+/**
+A utility for composing many shapes into a single [`Mesh`](struct.Mesh.html)
+
+Each shape is given its own color. Shapes are transformed on the cpu as they are added,
+so the resulting mesh can be drawn with a single draw call.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct MeshBuilder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    /// Create a new empty `MeshBuilder`
+    pub fn new() -> Self {
+        MeshBuilder::default()
+    }
+    /// Add transformed geometry
+    fn push(&mut self, color: Col, transform: Trans, vertices: Vec<Vertex>, indices: &[u32]) {
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend(vertices.into_iter().map(|v| Vertex {
+            pos: v.pos.transform(transform),
+            color,
+            ..v
+        }));
+        self.indices.extend(indices.iter().map(|i| i + offset));
+    }
+    /// Add a rectangle
+    pub fn rectangle<C, E>(&mut self, color: C, rect: E) -> &mut Self
+    where
+        C: Color,
+        E: Rectangle<Scalar = f32>,
+    {
+        self.push(
+            color.map(),
+            rect_transform(rect.map()),
+            regular_vertices(4),
+            &fan_indices(4),
+        );
+        self
+    }
+    /// Add a circle
+    pub fn circle<C, E>(&mut self, color: C, circ: E, resolution: u16) -> &mut Self
+    where
+        C: Color,
+        E: Circle<Scalar = f32>,
+    {
+        self.push(
+            color.map(),
+            Trans::identity()
+                .zoom(circ.radius())
+                .translate(circ.center()),
+            regular_vertices(resolution),
+            &fan_indices(resolution as u32),
+        );
+        self
+    }
+    /// Add a polygon
+    pub fn polygon<'p, C, V, P>(&mut self, color: C, vertices: P) -> &mut Self
+    where
+        C: Color,
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        let positions: Vec<Vec2> = vertices.into_iter().map(|v| v.map()).collect();
+        let indices = polygon_indices(&positions);
+        let vertices = bounded_vertices(positions);
+        self.push(color.map(), Trans::identity(), vertices, &indices);
+        self
+    }
+    /// Add a line
+    pub fn line<C, P>(&mut self, color: C, a: P, b: P, thickness: f32) -> &mut Self
+    where
+        C: Color,
+        P: Vector2<Scalar = f32>,
+    {
+        self.push(
+            color.map(),
+            line_transform(a.map(), b.map(), thickness),
+            regular_vertices(4),
+            &fan_indices(4),
+        );
+        self
+    }
+    /// Add another mesh
+    pub fn mesh(&mut self, mesh: &Mesh) -> &mut Self {
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(mesh.vertices());
+        self.indices
+            .extend(mesh.indices().iter().map(|i| i + offset));
+        self
+    }
+    /// Build the mesh
+    pub fn build(&self) -> Mesh {
+        Mesh::new(self.vertices.clone(), self.indices.clone())
+    }
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /// Draw a mesh
+    pub fn mesh<C>(&mut self, color: C, mesh: &Mesh) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+    {
+        Transformable::multi(
+            self,
+            color.map(),
+            std::iter::once(DrawItem {
+                ty: DrawType::Empty,
+                transform: Trans::identity(),
+                color: None,
+                texture: None,
+                src: None,
+                mesh: Some(mesh.clone()),
+                border: None,
+            }),
+            Trans::identity(),
+        )
+    }
+    /**
+    Draw an arrow
+
+    The arrow's head is a triangle whose length and width are `head_size`.
+    The shaft's thickness is a quarter of the `head_size`.
+    */
+    pub fn arrow<C, P>(
+        &mut self,
+        color: C,
+        from: P,
+        to: P,
+        head_size: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        P: Vector2<Scalar = f32>,
+    {
+        let from: Vec2 = from.map();
+        let to: Vec2 = to.map();
+        let diff = to.sub(from);
+        let dir = if diff.mag() == 0.0 {
+            [1.0, 0.0]
+        } else {
+            diff.unit()
+        };
+        let perp = dir.rotate(f32::TAU / 4.0).mul(head_size / 2.0);
+        let head_base = to.sub(dir.mul(head_size));
+        let mut builder = MeshBuilder::new();
+        if diff.mag() > head_size {
+            builder.line(Col::white(), from, head_base, head_size / 4.0);
+        }
+        builder.polygon(
+            Col::white(),
+            &[to, head_base.add(perp), head_base.sub(perp)],
+        );
+        self.mesh(color, &builder.build())
+    }
+    /**
+    Draw grid lines that cover the camera's view
+
+    Lines are one pixel thick. When zooming out makes the lines too dense,
+    every line that is not on a coarser grid with `GRID_SUBDIVISIONS` times
+    the spacing fades out.
+    */
+    pub fn grid<C>(&mut self, color: C, spacing: f32) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+    {
+        let color: Col = color.map();
+        let camera = self.camera;
+        let mut builder = MeshBuilder::new();
+        if spacing > 0.0 && camera.zoom > 0.0 {
+            let mut spacing = spacing;
+            while spacing * camera.zoom * GRID_SUBDIVISIONS < MIN_GRID_PIXELS {
+                spacing *= GRID_SUBDIVISIONS;
+            }
+            let fade = ((spacing * camera.zoom / MIN_GRID_PIXELS - 1.0)
+                / (GRID_SUBDIVISIONS - 1.0))
+                .clamp(0.0, 1.0);
+            let fine_color = color.with_alpha(color.alpha() * fade);
+            let thickness = 1.0 / camera.zoom;
+            let view = camera.view_rect();
+            let range =
+                |min: f32, max: f32| (min / spacing).floor() as i64..=(max / spacing).ceil() as i64;
+            let line_color = |i: i64| {
+                if i % GRID_SUBDIVISIONS as i64 == 0 {
+                    Some(color)
+                } else if fade > 0.0 {
+                    Some(fine_color)
+                } else {
+                    None
+                }
+            };
+            for i in range(view.left(), view.right()) {
+                if let Some(color) = line_color(i) {
+                    let x = i as f32 * spacing;
+                    builder.line(color, [x, view.top()], [x, view.bottom()], thickness);
+                }
+            }
+            for j in range(view.top(), view.bottom()) {
+                if let Some(color) = line_color(j) {
+                    let y = j as f32 * spacing;
+                    builder.line(color, [view.left(), y], [view.right(), y], thickness);
+                }
+            }
+        }
+        self.mesh(Col::white(), &builder.build())
+    }
+    /**
+    Draw many points
+
+    Each point is a square that is `size` pixels wide regardless of the camera's zoom.
+    All of the points are drawn together as a single mesh.
+    */
+    pub fn points<'p, C, V, P>(
+        &mut self,
+        color: C,
+        positions: P,
+        size: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        let half = size / self.camera.zoom / 2.0;
+        let corners = [[-half, -half], [half, -half], [half, half], [-half, half]];
+        let uvs = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for pos in positions {
+            let pos: Vec2 = pos.map();
+            let offset = vertices.len() as u32;
+            vertices.extend(corners.iter().zip(&uvs).map(|(corner, &uv)| Vertex {
+                pos: pos.add(*corner),
+                uv,
+                color: Col::white(),
+            }));
+            indices.extend(fan_indices(4).into_iter().map(|i| i + offset));
+        }
+        self.mesh(color, &Mesh::new(vertices, indices))
+    }
+    /// Draw a plus-shaped marker
+    pub fn plus<C, P>(
+        &mut self,
+        color: C,
+        center: P,
+        size: f32,
+        thickness: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        P: Vector2<Scalar = f32>,
+    {
+        self.marker(color, center.map(), size, thickness, 0.0)
+    }
+    /// Draw an x-shaped marker
+    pub fn cross<C, P>(
+        &mut self,
+        color: C,
+        center: P,
+        size: f32,
+        thickness: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        P: Vector2<Scalar = f32>,
+    {
+        self.marker(color, center.map(), size, thickness, f32::TAU / 8.0)
+    }
+    /// Draw two perpendicular lines that cross at a point
+    fn marker<C>(
+        &mut self,
+        color: C,
+        center: Vec2,
+        size: f32,
+        thickness: f32,
+        rotation: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+    {
+        let a = rotation.angle_as_vector().mul(size / 2.0);
+        let b = a.rotate(f32::TAU / 4.0);
+        let mut builder = MeshBuilder::new();
+        builder
+            .line(Col::white(), center.sub(a), center.add(a), thickness)
+            .line(Col::white(), center.sub(b), center.add(b), thickness);
+        self.mesh(color, &builder.build())
+    }
+}
@@ -0,0 +1,672 @@
+use std::collections::HashMap;
+
+use lyon_tessellation::{
+    geom::math::{point, Point},
+    geometry_builder::{BuffersBuilder, Positions},
+    path::{self as lyon_path, Path as LyonPath},
+    FillOptions, FillTessellator, StrokeOptions, StrokeTessellator, VertexBuffers,
+};
+use vector2math::*;
+
+use crate::{
+    bounded_vertices, regular_vertices, Canvas, Circle, Color, Drawer, KuleError, KuleResult, Mesh,
+    Rectangle, Resources, Trans, Transformable, Vec2, Vertex,
+};
+
+/// The default maximum distance between a curve and the line segments that approximate it
+pub const DEFAULT_TOLERANCE: f32 = 0.1;
+
+/// How the inside of a path is determined when it is filled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FillRule {
+    /// A point is inside if a ray from it crosses the path's edges
+    /// a different number of times in each direction
+    NonZero,
+    /// A point is inside if a ray from it crosses the path's edges an odd number of times
+    EvenOdd,
+}
+
+impl From<FillRule> for lyon_path::FillRule {
+    fn from(rule: FillRule) -> Self {
+        match rule {
+            FillRule::NonZero => lyon_path::FillRule::NonZero,
+            FillRule::EvenOdd => lyon_path::FillRule::EvenOdd,
+        }
+    }
+}
+
+/// Options for filling a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fill {
+    /// The fill rule
+    pub rule: FillRule,
+    /// The maximum distance between a curve and the line segments that approximate it
+    pub tolerance: f32,
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Fill::new()
+    }
+}
+
+impl Fill {
+    /// Create a new `Fill` with the `NonZero` fill rule and the default tolerance
+    pub const fn new() -> Self {
+        Fill {
+            rule: FillRule::NonZero,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+    /// Set the `rule`
+    pub const fn rule(self, rule: FillRule) -> Self {
+        Fill { rule, ..self }
+    }
+    /// Set the `tolerance`
+    pub const fn tolerance(self, tolerance: f32) -> Self {
+        Fill { tolerance, ..self }
+    }
+}
+
+impl From<FillRule> for Fill {
+    fn from(rule: FillRule) -> Self {
+        Fill::new().rule(rule)
+    }
+}
+
+/// The shape of the ends of a stroke
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineCap {
+    /// The stroke ends exactly at its endpoints
+    Butt,
+    /// The stroke extends past its endpoints by half its thickness
+    Square,
+    /// The stroke ends in a semicircle
+    Round,
+}
+
+impl From<LineCap> for lyon_tessellation::LineCap {
+    fn from(cap: LineCap) -> Self {
+        match cap {
+            LineCap::Butt => lyon_tessellation::LineCap::Butt,
+            LineCap::Square => lyon_tessellation::LineCap::Square,
+            LineCap::Round => lyon_tessellation::LineCap::Round,
+        }
+    }
+}
+
+/// The shape of the corners of a stroke
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineJoin {
+    /// The edges are extended until they meet
+    ///
+    /// Joins that would extend past the miter limit are beveled instead
+    Miter,
+    /// The corner is cut off
+    Bevel,
+    /// The corner is rounded
+    Round,
+}
+
+impl From<LineJoin> for lyon_tessellation::LineJoin {
+    fn from(join: LineJoin) -> Self {
+        match join {
+            LineJoin::Miter => lyon_tessellation::LineJoin::Miter,
+            LineJoin::Bevel => lyon_tessellation::LineJoin::Bevel,
+            LineJoin::Round => lyon_tessellation::LineJoin::Round,
+        }
+    }
+}
+
+/// Options for stroking a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stroke {
+    /// The thickness of the stroke
+    pub thickness: f32,
+    /// The shape of the ends
+    pub cap: LineCap,
+    /// The shape of the corners
+    pub join: LineJoin,
+    /// The maximum ratio of a miter join's length to the stroke's thickness
+    pub miter_limit: f32,
+    /// The maximum distance between a curve and the line segments that approximate it
+    pub tolerance: f32,
+}
+
+impl Stroke {
+    /// Create a new `Stroke` with the given `thickness`, `Butt` caps, `Miter` joins,
+    /// a `miter_limit` of `4.0`, and the default tolerance
+    pub const fn new(thickness: f32) -> Self {
+        Stroke {
+            thickness,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.0,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+    /// Set the `cap`
+    pub const fn cap(self, cap: LineCap) -> Self {
+        Stroke { cap, ..self }
+    }
+    /// Set the `join`
+    pub const fn join(self, join: LineJoin) -> Self {
+        Stroke { join, ..self }
+    }
+    /// Set the `miter_limit`
+    pub const fn miter_limit(self, miter_limit: f32) -> Self {
+        Stroke {
+            miter_limit,
+            ..self
+        }
+    }
+    /// Set the `tolerance`
+    pub const fn tolerance(self, tolerance: f32) -> Self {
+        Stroke { tolerance, ..self }
+    }
+}
+
+impl From<f32> for Stroke {
+    fn from(thickness: f32) -> Self {
+        Stroke::new(thickness)
+    }
+}
+
+fn to_point<V>(v: V) -> Point
+where
+    V: Vector2<Scalar = f32>,
+{
+    point(v.x(), v.y())
+}
+
+/**
+A builder for [`Path`](struct.Path.html)s
+
+Each sub-path begins with `move_to`. Drawing commands without a preceding
+`move_to` start a new sub-path at the end of the previous one.
+*/
+pub struct PathBuilder {
+    builder: lyon_path::Builder,
+}
+
+impl Default for PathBuilder {
+    fn default() -> Self {
+        PathBuilder::new()
+    }
+}
+
+impl PathBuilder {
+    /// Create a new `PathBuilder`
+    pub fn new() -> Self {
+        PathBuilder {
+            builder: LyonPath::builder(),
+        }
+    }
+    /// Start a new sub-path at a point
+    pub fn move_to<V>(&mut self, to: V) -> &mut Self
+    where
+        V: Vector2<Scalar = f32>,
+    {
+        self.builder.move_to(to_point(to));
+        self
+    }
+    /// Add a straight line to a point
+    pub fn line_to<V>(&mut self, to: V) -> &mut Self
+    where
+        V: Vector2<Scalar = f32>,
+    {
+        self.builder.line_to(to_point(to));
+        self
+    }
+    /// Add a quadratic bezier curve to a point
+    pub fn quadratic_to<V>(&mut self, ctrl: V, to: V) -> &mut Self
+    where
+        V: Vector2<Scalar = f32>,
+    {
+        self.builder
+            .quadratic_bezier_to(to_point(ctrl), to_point(to));
+        self
+    }
+    /// Add a cubic bezier curve to a point
+    pub fn cubic_to<V>(&mut self, ctrl1: V, ctrl2: V, to: V) -> &mut Self
+    where
+        V: Vector2<Scalar = f32>,
+    {
+        self.builder
+            .cubic_bezier_to(to_point(ctrl1), to_point(ctrl2), to_point(to));
+        self
+    }
+    /// Close the current sub-path with a straight line to its start
+    pub fn close(&mut self) -> &mut Self {
+        self.builder.close();
+        self
+    }
+    /// Build the path
+    pub fn build(self) -> Path {
+        Path(self.builder.build())
+    }
+}
+
+/**
+A 2D vector path made of lines and curves
+
+Paths are built with a [`PathBuilder`](struct.PathBuilder.html) and are
+tessellated into [`Mesh`](struct.Mesh.html)es to be drawn.
+*/
+#[derive(Debug, Clone)]
+pub struct Path(LyonPath);
+
+impl Path {
+    /// Create a new `PathBuilder`
+    pub fn builder() -> PathBuilder {
+        PathBuilder::new()
+    }
+    /// Create a path of straight lines between some points
+    pub fn polyline<'p, V, P>(points: P, closed: bool) -> Self
+    where
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        let mut builder = PathBuilder::new();
+        let mut points = points.into_iter();
+        if let Some(first) = points.next() {
+            builder.move_to(first.map::<Vec2>());
+            for point in points {
+                builder.line_to(point.map::<Vec2>());
+            }
+            if closed {
+                builder.close();
+            }
+        }
+        builder.build()
+    }
+    /**
+    Create a path of closed polygons
+
+    This is useful for drawing the results of
+    [`polygon_boolean`](fn.polygon_boolean.html).
+    */
+    pub fn polygons<P>(polygons: &[P]) -> Self
+    where
+        P: AsRef<[Vec2]>,
+    {
+        let mut builder = PathBuilder::new();
+        for polygon in polygons {
+            let mut points = polygon.as_ref().iter();
+            if let Some(&first) = points.next() {
+                builder.move_to(first);
+                for &point in points {
+                    builder.line_to(point);
+                }
+                builder.close();
+            }
+        }
+        builder.build()
+    }
+    /// Tessellate the inside of the path into a mesh
+    pub fn fill<F>(&self, fill: F) -> KuleResult<Mesh>
+    where
+        F: Into<Fill>,
+    {
+        let fill = fill.into();
+        let mut buffers: VertexBuffers<Point, u32> = VertexBuffers::new();
+        FillTessellator::new()
+            .tessellate_path(
+                &self.0,
+                &FillOptions::tolerance(fill.tolerance).with_fill_rule(fill.rule.into()),
+                &mut BuffersBuilder::new(&mut buffers, Positions),
+            )
+            .map_err(KuleError::Tessellation)?;
+        Ok(buffers_mesh(buffers))
+    }
+    /// Tessellate the outline of the path into a mesh
+    pub fn stroke<S>(&self, stroke: S) -> KuleResult<Mesh>
+    where
+        S: Into<Stroke>,
+    {
+        let stroke = stroke.into();
+        let options = StrokeOptions::tolerance(stroke.tolerance)
+            .with_line_width(stroke.thickness)
+            .with_line_cap(stroke.cap.into())
+            .with_line_join(stroke.join.into())
+            .with_miter_limit(stroke.miter_limit);
+        let mut buffers: VertexBuffers<Point, u32> = VertexBuffers::new();
+        StrokeTessellator::new()
+            .tessellate_path(
+                &self.0,
+                &options,
+                &mut BuffersBuilder::new(&mut buffers, Positions),
+            )
+            .map_err(KuleError::Tessellation)?;
+        Ok(buffers_mesh(buffers))
+    }
+}
+
+/// The maximum ratio of the length of a border's corner to its thickness
+const BORDER_MITER_LIMIT: f32 = 4.0;
+
+/**
+The outline of a triangle mesh
+
+The outline is made of closed loops of points. Each loop
+goes around the mesh such that the mesh is on its left.
+*/
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Outline {
+    points: Vec<Vec2>,
+    /// The end of each loop in `points`
+    ends: Vec<usize>,
+}
+
+impl Outline {
+    /// Get the loops of points that make up the outline
+    pub(crate) fn loops(&self) -> impl Iterator<Item = &[Vec2]> + '_ {
+        let mut start = 0;
+        self.ends.iter().map(move |&end| {
+            let points = &self.points[start..end];
+            start = end;
+            points
+        })
+    }
+    /**
+    Build a border around the outside of the outline
+
+    The outline's points are transformed before the border is built,
+    so the border's thickness is not affected by the transformation.
+    The border's positions and indices are added to the given buffers.
+    */
+    pub(crate) fn border(
+        &self,
+        transform: Trans,
+        thickness: f32,
+        positions: &mut Vec<Vec2>,
+        indices: &mut Vec<u32>,
+    ) {
+        let [[a, b, _], [d, e, _]] = transform;
+        // Mirroring transformations swap which side of the outline is outside
+        let side = if a * e - b * d < 0.0 { -1.0 } else { 1.0 };
+        let outward = |from: Vec2, to: Vec2| {
+            let dir = to.sub(from);
+            let len = dir.mag();
+            if len <= f32::EPSILON {
+                [0.0; 2]
+            } else {
+                [dir[1], -dir[0]].mul(side / len)
+            }
+        };
+        for points in self.loops() {
+            let n = points.len();
+            if n < 2 {
+                continue;
+            }
+            let start = positions.len() as u32;
+            let point = |i: usize| points[i % n].transform(transform);
+            let mut prev = point(n - 1);
+            let mut curr = point(0);
+            for i in 0..n {
+                let next = point(i + 1);
+                let (before, after) = (outward(prev, curr), outward(curr, next));
+                let miter = before.add(after);
+                let len = miter.mag();
+                let offset = if len <= f32::EPSILON {
+                    after.mul(thickness)
+                } else {
+                    let miter = miter.div(len);
+                    // Sharp corners are limited so that they do not extend too far
+                    let cos = miter
+                        .dot(before)
+                        .max(miter.dot(after))
+                        .max(1.0 / BORDER_MITER_LIMIT);
+                    miter.mul(thickness / cos)
+                };
+                positions.push(curr);
+                positions.push(curr.add(offset));
+                let (inner, outer) = (start + 2 * i as u32, start + 2 * i as u32 + 1);
+                let next_inner = start + 2 * ((i as u32 + 1) % n as u32);
+                indices.extend_from_slice(&[
+                    inner,
+                    outer,
+                    next_inner,
+                    next_inner,
+                    outer,
+                    next_inner + 1,
+                ]);
+                prev = curr;
+                curr = next;
+            }
+        }
+    }
+}
+
+/**
+Get the outline of a triangle mesh
+
+Edges that belong to only one triangle are on the outline. Vertices
+are matched by position, so meshes do not need to share vertices.
+*/
+pub(crate) fn mesh_outline(vertices: &[Vertex], indices: &[u32]) -> Outline {
+    let key = |i: u32| {
+        let [x, y] = vertices[i as usize].pos;
+        (x.to_bits(), y.to_bits())
+    };
+    let to_vec = |(x, y): (u32, u32)| [f32::from_bits(x), f32::from_bits(y)];
+    // Count how many triangles each edge belongs to, keeping the
+    // direction that has the triangle on its left
+    let mut edges: HashMap<_, (usize, _)> = HashMap::new();
+    for tri in indices.chunks_exact(3) {
+        let [p, q, r] = [tri[0], tri[1], tri[2]].map(|i| vertices[i as usize].pos);
+        let (u, v) = (q.sub(p), r.sub(p));
+        let area = u[0] * v[1] - u[1] * v[0];
+        if area == 0.0 {
+            continue;
+        }
+        for k in 0..3 {
+            let (a, b) = (key(tri[k]), key(tri[(k + 1) % 3]));
+            if a == b {
+                continue;
+            }
+            let directed = if area > 0.0 { (a, b) } else { (b, a) };
+            edges.entry((a.min(b), a.max(b))).or_insert((0, directed)).0 += 1;
+        }
+    }
+    // Map each vertex on the outline to the next vertices along the outline
+    let mut next: HashMap<_, Vec<_>> = HashMap::new();
+    for &(count, (a, b)) in edges.values() {
+        if count == 1 {
+            next.entry(a).or_default().push(b);
+        }
+    }
+    let mut outline = Outline::default();
+    while let Some(&start) = next.keys().next() {
+        outline.points.push(to_vec(start));
+        let mut curr = start;
+        loop {
+            let following = next.get_mut(&curr).and_then(Vec::pop);
+            if matches!(next.get(&curr), Some(following) if following.is_empty()) {
+                next.remove(&curr);
+            }
+            match following {
+                Some(following) if following != start => {
+                    outline.points.push(to_vec(following));
+                    curr = following;
+                }
+                _ => break,
+            }
+        }
+        outline.ends.push(outline.points.len());
+    }
+    outline
+}
+
+/// Create a mesh from tessellated geometry
+fn buffers_mesh(buffers: VertexBuffers<Point, u32>) -> Mesh {
+    let vertices = bounded_vertices(buffers.vertices.into_iter().map(|p| [p.x, p.y]));
+    Mesh::new(vertices, buffers.indices)
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /**
+    Draw connected lines between some points
+
+    The stroke determines how the lines are joined at each point and
+    how the ends are capped. Unlike drawing individual lines, the joins
+    have no gaps or overlaps. If the lines can not be tessellated, nothing is drawn.
+    */
+    pub fn polyline<'p, C, V, P, S>(
+        &mut self,
+        color: C,
+        points: P,
+        stroke: S,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+        S: Into<Stroke>,
+    {
+        let mesh = Path::polyline(points, false)
+            .stroke(stroke)
+            .unwrap_or_else(|_| Mesh::new(Vec::new(), Vec::new()));
+        self.mesh(color, &mesh)
+    }
+    /// Draw the outline of a closed polygon
+    fn closed_outline<C>(
+        &mut self,
+        color: C,
+        points: &[Vec2],
+        thickness: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+    {
+        let mesh = Path::polyline(points, true)
+            .stroke(thickness)
+            .unwrap_or_else(|_| Mesh::new(Vec::new(), Vec::new()));
+        self.mesh(color, &mesh)
+    }
+    /// Draw the outline of a rectangle
+    ///
+    /// The outline is centered on the rectangle's edges
+    pub fn rectangle_outline<C, E>(
+        &mut self,
+        color: C,
+        rect: E,
+        thickness: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        E: Rectangle<Scalar = f32>,
+    {
+        let points = [
+            rect.top_left().map(),
+            rect.top_right().map(),
+            rect.bottom_right().map(),
+            rect.bottom_left().map(),
+        ];
+        self.closed_outline(color, &points, thickness)
+    }
+    /// Draw the outline of a circle
+    ///
+    /// The outline is centered on the circle's edge
+    pub fn circle_outline<C, E>(
+        &mut self,
+        color: C,
+        circ: E,
+        thickness: f32,
+        resolution: u16,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        E: Circle<Scalar = f32>,
+    {
+        let center: Vec2 = circ.center().map();
+        let points: Vec<Vec2> = regular_vertices(resolution)
+            .into_iter()
+            .map(|v| v.pos.mul(circ.radius()).add(center))
+            .collect();
+        self.closed_outline(color, &points, thickness)
+    }
+    /// Draw the outline of an ellipse
+    ///
+    /// The outline is centered on the ellipse's edge
+    pub fn ellipse_outline<C, E>(
+        &mut self,
+        color: C,
+        ellip: E,
+        thickness: f32,
+        resolution: u16,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        E: Rectangle<Scalar = f32>,
+    {
+        let center: Vec2 = ellip.center().map();
+        let radii: Vec2 = ellip.size().map::<Vec2>().div(2.0);
+        let points: Vec<Vec2> = regular_vertices(resolution)
+            .into_iter()
+            .map(|v| v.pos.mul2(radii).add(center))
+            .collect();
+        self.closed_outline(color, &points, thickness)
+    }
+    /// Draw the outline of a polygon
+    ///
+    /// The outline is centered on the polygon's edges
+    pub fn polygon_outline<'p, C, V, P>(
+        &mut self,
+        color: C,
+        vertices: P,
+        thickness: f32,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        let points: Vec<Vec2> = vertices.into_iter().map(|v| v.map()).collect();
+        self.closed_outline(color, &points, thickness)
+    }
+    /**
+    Draw the inside of a path
+
+    This tessellates the path every time it is called. To draw
+    the same path many times, tessellate it once with `Path::fill`
+    and draw the mesh instead.
+    */
+    pub fn fill_path<C, F>(
+        &mut self,
+        color: C,
+        path: &Path,
+        fill: F,
+    ) -> KuleResult<Transformable<'ctx, '_, T, R>>
+    where
+        C: Color,
+        F: Into<Fill>,
+    {
+        let mesh = path.fill(fill)?;
+        Ok(self.mesh(color, &mesh))
+    }
+    /**
+    Draw the outline of a path
+
+    This tessellates the path every time it is called. To draw
+    the same path many times, tessellate it once with `Path::stroke`
+    and draw the mesh instead.
+    */
+    pub fn stroke_path<C, S>(
+        &mut self,
+        color: C,
+        path: &Path,
+        stroke: S,
+    ) -> KuleResult<Transformable<'ctx, '_, T, R>>
+    where
+        C: Color,
+        S: Into<Stroke>,
+    {
+        let mesh = path.stroke(stroke)?;
+        Ok(self.mesh(color, &mesh))
+    }
+}
@@ -0,0 +1,186 @@
+use vector2math::*;
+
+use crate::{
+    line_transform, rect_transform, Canvas, Col, Color, DrawItem, DrawType, Drawer, Gradient, Rect,
+    Resources, Trans, Transformable, Vec2,
+};
+
+/// The fraction of each bar's slot in a bar chart that is left empty
+const BAR_GAP: f32 = 0.2;
+
+/// A series of data points to be drawn as a line plot
+#[derive(Debug, Clone, Copy)]
+pub struct Series<'a> {
+    /// The data points
+    pub points: &'a [Vec2],
+    /// The color of the line
+    pub color: Col,
+    /// The thickness of the line
+    pub thickness: f32,
+}
+
+impl<'a> Series<'a> {
+    /// Create a new `Series` with the default `thickness` of `1.0`
+    pub fn new<C>(color: C, points: &'a [Vec2]) -> Self
+    where
+        C: Color,
+    {
+        Series {
+            points,
+            color: color.map(),
+            thickness: 1.0,
+        }
+    }
+    /// Set the `thickness`
+    pub fn thickness(self, thickness: f32) -> Self {
+        Series { thickness, ..self }
+    }
+}
+
+/// Map a data point in `bounds` to a point in `rect` with the y axis pointing up
+fn map_point(point: Vec2, bounds: Rect, rect: Rect) -> Vec2 {
+    let size = bounds.size().map_with(|d| if d == 0.0 { 1.0 } else { d });
+    let [x, y] = point.sub(bounds.top_left()).div2(size);
+    [
+        rect.left() + x * rect.width(),
+        rect.bottom() - y * rect.height(),
+    ]
+}
+
+/// Get the minimum and maximum of some values
+fn min_max<I>(values: I) -> Option<[f32; 2]>
+where
+    I: IntoIterator<Item = f32>,
+{
+    values.into_iter().fold(None, |acc, v| {
+        Some(acc.map_or([v, v], |[min, max]| [min.min(v), max.max(v)]))
+    })
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /**
+    Draw a line plot of some data series
+
+    The data is scaled so that the bounds of all the series fit in `rect`.
+    The y axis of the data points up.
+    */
+    pub fn plot<'s, E, S>(&mut self, rect: E, series: S) -> Transformable<'ctx, '_, T, R>
+    where
+        E: Rectangle<Scalar = f32>,
+        S: IntoIterator<Item = Series<'s>>,
+    {
+        let rect: Rect = rect.map();
+        let series: Vec<Series> = series.into_iter().collect();
+        let bounds = Rect::bounding(series.iter().flat_map(|s| s.points.iter().copied()))
+            .unwrap_or_default();
+        let items: Vec<DrawItem<R>> = series
+            .iter()
+            .flat_map(|s| {
+                s.points.windows(2).map(move |w| DrawItem {
+                    ty: DrawType::Regular(4),
+                    transform: line_transform(
+                        map_point(w[0], bounds, rect),
+                        map_point(w[1], bounds, rect),
+                        s.thickness,
+                    ),
+                    color: Some(s.color),
+                    texture: None,
+                    src: None,
+                    mesh: None,
+                    border: None,
+                })
+            })
+            .collect();
+        Transformable::multi(self, Col::white(), items, Trans::identity())
+    }
+    /**
+    Draw a bar chart
+
+    Each value gets a bar of equal width. Bars extend from zero, so negative
+    values produce bars that extend downward.
+    */
+    pub fn bar_chart<C, E>(
+        &mut self,
+        color: C,
+        rect: E,
+        values: &[f32],
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        C: Color,
+        E: Rectangle<Scalar = f32>,
+    {
+        let rect: Rect = rect.map();
+        let [min, max] = min_max(values.iter().copied().chain(Some(0.0))).unwrap();
+        let bounds = [0.0, min, values.len() as f32, max - min];
+        let items: Vec<DrawItem<R>> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| {
+                let left = i as f32 + BAR_GAP / 2.0;
+                let right = i as f32 + 1.0 - BAR_GAP / 2.0;
+                let a = map_point([left, v.max(0.0)], bounds, rect);
+                let b = map_point([right, v.min(0.0)], bounds, rect);
+                DrawItem {
+                    ty: DrawType::Regular(4),
+                    transform: rect_transform([a[0], a[1], b[0] - a[0], b[1] - a[1]]),
+                    color: None,
+                    texture: None,
+                    src: None,
+                    mesh: None,
+                    border: None,
+                }
+            })
+            .collect();
+        Transformable::multi(self, color.map(), items, Trans::identity())
+    }
+    /**
+    Draw a heatmap of a 2D grid of values
+
+    Each inner slice is a row, with the first row at the top. Values are normalized
+    between the minimum and maximum values in the grid and then mapped
+    through the `gradient` to get each cell's color.
+    */
+    pub fn heatmap<E, V>(
+        &mut self,
+        rect: E,
+        rows: &[V],
+        gradient: &Gradient,
+    ) -> Transformable<'ctx, '_, T, R>
+    where
+        E: Rectangle<Scalar = f32>,
+        V: AsRef<[f32]>,
+    {
+        let rect: Rect = rect.map();
+        let [min, max] =
+            min_max(rows.iter().flat_map(|row| row.as_ref().iter().copied())).unwrap_or_default();
+        let range = if max > min { max - min } else { 1.0 };
+        let cell_height = rect.height() / rows.len() as f32;
+        let items: Vec<DrawItem<R>> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(j, row)| {
+                let row = row.as_ref();
+                let cell_width = rect.width() / row.len() as f32;
+                row.iter().enumerate().map(move |(i, &v)| DrawItem {
+                    ty: DrawType::Regular(4),
+                    transform: rect_transform([
+                        rect.left() + i as f32 * cell_width,
+                        rect.top() + j as f32 * cell_height,
+                        cell_width,
+                        cell_height,
+                    ]),
+                    color: Some(gradient.sample((v - min) / range)),
+                    texture: None,
+                    src: None,
+                    mesh: None,
+                    border: None,
+                })
+            })
+            .collect();
+        Transformable::multi(self, Col::white(), items, Trans::identity())
+    }
+}
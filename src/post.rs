@@ -0,0 +1,130 @@
+use std::cell::RefCell;
+
+use glium::{backend::Facade, Program};
+use vector2math::*;
+
+use crate::{
+    shader::compile_program, BlendMode, Context, KuleResult, RenderTarget, Resources, Texture,
+    DEFAULT_VERTEX_SHADER,
+};
+
+/// The source of the fragment shader that extracts the bright parts of the scene
+const BLOOM_THRESHOLD_SHADER: &str = include_str!("shaders/bloom_threshold.frag");
+/// The source of the fragment shader that blurs along one axis
+const BLUR_SHADER: &str = include_str!("shaders/blur.frag");
+
+/**
+Configuration for the bloom post effect
+
+Bloom makes bright parts of the scene glow. Colors brighter than the threshold
+are extracted, blurred, and added back on top of the scene.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    /// The brightness above which colors glow
+    pub threshold: f32,
+    /// How strongly the glow is added to the scene
+    pub intensity: f32,
+    /// The number of times the glow is blurred
+    ///
+    /// More passes make the glow larger and smoother
+    pub passes: u8,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom::new()
+    }
+}
+
+impl Bloom {
+    /// Create a new `Bloom` with a threshold of `0.8`, an intensity of `1.0`, and `2` passes
+    pub const fn new() -> Self {
+        Bloom {
+            threshold: 0.8,
+            intensity: 1.0,
+            passes: 2,
+        }
+    }
+    /// Set the threshold
+    pub const fn threshold(self, threshold: f32) -> Self {
+        Bloom { threshold, ..self }
+    }
+    /// Set the intensity
+    pub const fn intensity(self, intensity: f32) -> Self {
+        Bloom { intensity, ..self }
+    }
+    /// Set the number of blur passes
+    pub const fn passes(self, passes: u8) -> Self {
+        Bloom { passes, ..self }
+    }
+}
+
+/// The gpu state of the bloom effect
+pub(crate) struct BloomPass {
+    pub config: Bloom,
+    threshold_program: Program,
+    blur_program: Program,
+    /// Two half resolution targets that are blurred back and forth
+    targets: RefCell<Option<[RenderTarget; 2]>>,
+}
+
+impl BloomPass {
+    pub fn new<F>(facade: &F, config: Bloom) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        Ok(BloomPass {
+            config,
+            threshold_program: compile_program(
+                facade,
+                DEFAULT_VERTEX_SHADER,
+                BLOOM_THRESHOLD_SHADER,
+            )?,
+            blur_program: compile_program(facade, DEFAULT_VERTEX_SHADER, BLUR_SHADER)?,
+            targets: RefCell::new(None),
+        })
+    }
+    /// Get the glow of a rendered scene, which should be added on top of it
+    pub fn apply<R>(&self, ctx: &Context<R>, scene: &RenderTarget) -> KuleResult<Texture>
+    where
+        R: Resources,
+    {
+        let size = scene.size().map_with(|d| (d / 2).max(1));
+        let mut targets = self.targets.borrow_mut();
+        if !matches!(&*targets, Some([target, _]) if target.size() == size) {
+            *targets = Some([
+                RenderTarget::new(&ctx.window, size)?,
+                RenderTarget::new(&ctx.window, size)?,
+            ]);
+        }
+        let [bright, blurred] = targets.as_ref().unwrap();
+        let full = |target: &RenderTarget| {
+            let [w, h] = target.size();
+            [0.0, 0.0, w as f32, h as f32]
+        };
+        // Extract bright colors
+        ctx.draw_to_with_program(bright, &self.threshold_program, |draw| {
+            draw.set_uniform("threshold", self.config.threshold);
+            draw.with_blend_mode(BlendMode::Replace, |draw| {
+                draw.image(scene.texture(), full(bright));
+            });
+            Ok(())
+        })?;
+        // Blur
+        for _ in 0..self.config.passes {
+            for (source, dest, direction) in
+                [(bright, blurred, [1.0, 0.0]), (blurred, bright, [0.0, 1.0])].iter()
+            {
+                ctx.draw_to_with_program(dest, &self.blur_program, |draw| {
+                    draw.set_uniform("direction", *direction);
+                    draw.with_blend_mode(BlendMode::Replace, |draw| {
+                        draw.image(source.texture(), full(dest));
+                    });
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(bright.texture().clone())
+    }
+}
@@ -0,0 +1,159 @@
+use std::time::Instant;
+
+use crate::{Context, Event, Modifiers, Resources, StateTracker, Vec2};
+
+/// An input event in a recording
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct RecordedEvent {
+    /// The time in seconds since the recording started
+    pub time: f32,
+    /// The number of updates since the recording started when the event was delivered
+    pub update: u64,
+    /// The event
+    pub event: Event,
+    /// The modifier keys that were held when the event happened
+    pub modifiers: Modifiers,
+    /// How far the event scrolled the mouse wheel in lines
+    pub scroll: Vec2,
+}
+
+/**
+A recording of input events that can be played back
+
+Recordings are made with
+[`Context::start_recording`](struct.Context.html#method.start_recording).
+With the `ser` feature, they can be serialized to save demos and bug reproductions.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct InputRecording {
+    /// The recorded events, in order
+    pub events: Vec<RecordedEvent>,
+}
+
+impl InputRecording {
+    /// Get the time in seconds of the last event
+    pub fn duration(&self) -> f32 {
+        self.events
+            .last()
+            .map(|recorded| recorded.time)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Records input events as they happen
+pub(crate) struct Recorder {
+    start: Instant,
+    updates: u64,
+    recording: InputRecording,
+}
+
+impl Recorder {
+    /// Record the input events that came from a single window event
+    pub(crate) fn record(&mut self, events: &[Event], tracker: &StateTracker, scroll: Vec2) {
+        let time = self.start.elapsed().as_secs_f32();
+        for &event in events.iter().filter(|event| event.is_input()) {
+            self.recording.events.push(RecordedEvent {
+                time,
+                update: self.updates,
+                event,
+                modifiers: tracker.modifiers(),
+                scroll: if let Event::Scroll(_) = event {
+                    scroll
+                } else {
+                    [0.0; 2]
+                },
+            });
+        }
+    }
+    /// Count an update, so that later events are delivered before the next one
+    pub(crate) fn end_update(&mut self) {
+        self.updates += 1;
+    }
+}
+
+/// Plays back recorded input events
+pub(crate) struct Playback {
+    updates: u64,
+    recording: InputRecording,
+    next: usize,
+}
+
+impl Playback {
+    /// Get the events that are due to be played before the current update
+    pub(crate) fn due(&mut self) -> Vec<RecordedEvent> {
+        let updates = self.updates;
+        let due: Vec<RecordedEvent> = self.recording.events[self.next..]
+            .iter()
+            .take_while(|recorded| recorded.update <= updates)
+            .copied()
+            .collect();
+        self.next += due.len();
+        due
+    }
+    /// Count an update, so that the next update's events become due
+    pub(crate) fn end_update(&mut self) {
+        self.updates += 1;
+    }
+    /// Check if all the events have been played
+    pub(crate) fn finished(&self) -> bool {
+        self.next >= self.recording.events.len()
+    }
+}
+
+impl<R> Context<R>
+where
+    R: Resources,
+{
+    /**
+    Start recording input events
+
+    This discards any recording that has not been stopped.
+    */
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder {
+            start: Instant::now(),
+            updates: 0,
+            recording: InputRecording::default(),
+        });
+    }
+    /// Check if input events are being recorded
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+    /// Stop recording input events and get the recording
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recorder.take().map(|recorder| recorder.recording)
+    }
+    /**
+    Play back a recording of input events
+
+    Each event is sent to the app and updates the state tracker just before
+    the same update, counted from the start of playback, that it was delivered
+    before when it was recorded. This keeps playback in step with the app's
+    updates regardless of how fast they run. Real input is ignored until the
+    playback finishes or is stopped.
+    */
+    pub fn play_recording(&mut self, recording: InputRecording) {
+        self.playback = Some(Playback {
+            updates: 0,
+            recording,
+            next: 0,
+        });
+    }
+    /// Check if a recording is being played back
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+    /// Stop playing back a recording
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+    }
+}
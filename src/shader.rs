@@ -0,0 +1,310 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use glium::{
+    backend::Facade,
+    program,
+    uniforms::{UniformValue, Uniforms},
+    Program,
+};
+
+use crate::{KuleResult, Texture};
+
+/// The source of the default vertex shader
+pub const DEFAULT_VERTEX_SHADER: &str = include_str!("shaders/vertex.vert");
+/// The source of the default fragment shader
+pub const DEFAULT_FRAGMENT_SHADER: &str = include_str!("shaders/fragment.frag");
+/// The source of the fragment shader for signed distance field text
+const SDF_FRAGMENT_SHADER: &str = include_str!("shaders/sdf.frag");
+
+/// Compile a shader program from vertex and fragment shader source
+pub(crate) fn compile_program<F>(facade: &F, vertex: &str, fragment: &str) -> KuleResult<Program>
+where
+    F: Facade,
+{
+    Ok(Program::new(
+        facade,
+        program::SourceCode {
+            vertex_shader: vertex,
+            fragment_shader: fragment,
+            tessellation_control_shader: None,
+            tessellation_evaluation_shader: None,
+            geometry_shader: None,
+        },
+    )?)
+}
+
+pub(crate) fn default_shaders<F>(facade: &F) -> Program
+where
+    F: Facade,
+{
+    compile_program(facade, DEFAULT_VERTEX_SHADER, DEFAULT_FRAGMENT_SHADER)
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub(crate) fn sdf_shaders<F>(facade: &F) -> Program
+where
+    F: Facade,
+{
+    compile_program(facade, DEFAULT_VERTEX_SHADER, SDF_FRAGMENT_SHADER)
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+/**
+A cache of custom shader programs
+
+Custom shaders receive the same vertex attributes and uniforms as the
+default shaders, as well as the uniforms that describe each draw.
+See [`Uniform`](enum.Uniform.html) for details. The default shader source is available as
+[`DEFAULT_VERTEX_SHADER`](constant.DEFAULT_VERTEX_SHADER.html) and
+[`DEFAULT_FRAGMENT_SHADER`](constant.DEFAULT_FRAGMENT_SHADER.html) and makes a good
+starting point for writing your own.
+
+Shaders loaded from files with [`Shaders::load_files`](struct.Shaders.html#method.load_files)
+can be recompiled when their source changes with
+[`Shaders::reload_changed`](struct.Shaders.html#method.reload_changed).
+This is done automatically if `ContextBuilder::hot_reload_shaders` is enabled.
+*/
+pub struct Shaders<S = ()> {
+    programs: HashMap<S, Program>,
+    files: HashMap<S, ShaderFiles>,
+}
+
+impl<S> Default for Shaders<S> {
+    fn default() -> Self {
+        Shaders {
+            programs: HashMap::default(),
+            files: HashMap::default(),
+        }
+    }
+}
+
+/// The source files of a shader program
+struct ShaderFiles {
+    vertex: PathBuf,
+    fragment: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ShaderFiles {
+    /// Get the latest modification time of the files
+    fn modified(&self) -> Option<SystemTime> {
+        let vertex = fs::metadata(&self.vertex).and_then(|md| md.modified()).ok();
+        let fragment = fs::metadata(&self.fragment)
+            .and_then(|md| md.modified())
+            .ok();
+        vertex.max(fragment)
+    }
+    /// Read and compile the files
+    fn compile<F>(&self, facade: &F) -> KuleResult<Program>
+    where
+        F: Facade,
+    {
+        let vertex = fs::read_to_string(&self.vertex)?;
+        let fragment = fs::read_to_string(&self.fragment)?;
+        compile_program(facade, &vertex, &fragment)
+    }
+}
+
+impl<S> Shaders<S>
+where
+    S: Eq + std::hash::Hash,
+{
+    /// Compile and insert a shader program from vertex and fragment shader source
+    pub fn load<F>(&mut self, facade: &F, id: S, vertex: &str, fragment: &str) -> KuleResult<()>
+    where
+        F: Facade,
+    {
+        self.insert(id, compile_program(facade, vertex, fragment)?);
+        Ok(())
+    }
+    /**
+    Compile and insert a shader program from vertex and fragment shader files
+
+    The files are remembered so that the program can be recompiled by
+    [`Shaders::reload_changed`](struct.Shaders.html#method.reload_changed),
+    even if the initial compilation fails.
+    */
+    pub fn load_files<F, V, G>(
+        &mut self,
+        facade: &F,
+        id: S,
+        vertex: V,
+        fragment: G,
+    ) -> KuleResult<()>
+    where
+        F: Facade,
+        S: Clone,
+        V: AsRef<Path>,
+        G: AsRef<Path>,
+    {
+        let mut files = ShaderFiles {
+            vertex: vertex.as_ref().into(),
+            fragment: fragment.as_ref().into(),
+            modified: None,
+        };
+        files.modified = files.modified();
+        let program = files.compile(facade);
+        self.files.insert(id.clone(), files);
+        self.programs.insert(id, program?);
+        Ok(())
+    }
+    /**
+    Recompile any shader programs whose source files have changed since they were last loaded
+
+    If a program fails to compile, the previous version is kept and the error is returned.
+    Other changed programs are still recompiled.
+    */
+    pub fn reload_changed<F>(&mut self, facade: &F) -> KuleResult<()>
+    where
+        F: Facade,
+        S: Clone,
+    {
+        let mut res = Ok(());
+        for (id, files) in &mut self.files {
+            let modified = files.modified();
+            if modified <= files.modified {
+                continue;
+            }
+            files.modified = modified;
+            match files.compile(facade) {
+                Ok(program) => {
+                    self.programs.insert(id.clone(), program);
+                }
+                Err(e) => {
+                    if res.is_ok() {
+                        res = Err(e);
+                    }
+                }
+            }
+        }
+        res
+    }
+    /// Insert an already-compiled shader program
+    pub fn insert(&mut self, id: S, program: Program) {
+        self.files.remove(&id);
+        self.programs.insert(id, program);
+    }
+    /// Check if a shader program exists for the given id
+    pub fn contains(&self, id: S) -> bool {
+        self.programs.contains_key(&id)
+    }
+    /// Get a shader program with the given id
+    pub fn get(&self, id: S) -> Option<&Program> {
+        self.programs.get(&id)
+    }
+    /// Remove a shader program
+    pub fn remove(&mut self, id: S) {
+        self.files.remove(&id);
+        self.programs.remove(&id);
+    }
+    /// Clear all shader programs
+    pub fn clear(&mut self) {
+        self.files.clear();
+        self.programs.clear();
+    }
+}
+
+/**
+A value that can be passed to a shader as a uniform
+
+Every vertex has the attributes `pos: vec2`, `uv: vec2`, and `color: vec4`.
+Draws with the default shaders are transformed on the cpu so that they can be batched.
+Draws with custom shaders are not batched. Their vertices are passed untransformed,
+and their per-vertex `color` is not multiplied by the draw color.
+
+In addition to any custom uniforms, every draw receives the following uniforms:
+
+| Name | Type | Description |
+| --- | --- | --- |
+| `tex` | `sampler2D` | The texture, which is a blank white texture for untextured draws |
+| `u_time` | `float` | The time in seconds since the app started |
+| `u_resolution` | `vec2` | The size of the surface being drawn to in pixels |
+| `srgb` | `bool` | Whether colors should be converted from sRGB to linear before being output |
+
+Draws with custom shaders also receive these uniforms:
+
+| Name | Type | Description |
+| --- | --- | --- |
+| `transform` | `mat3` | The full vertex transformation |
+| `color` | `vec4` | The draw color |
+| `tex_rect` | `vec4` | The offset and size of the part of the texture to use |
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum Uniform {
+    /// A `float`
+    Float(f32),
+    /// A `vec2`
+    Vec2([f32; 2]),
+    /// A `vec3`
+    Vec3([f32; 3]),
+    /// A `vec4`
+    Vec4([f32; 4]),
+    /// An `int`
+    Int(i32),
+    /// A `bool`
+    Bool(bool),
+    /// A `mat3`
+    Mat3([[f32; 3]; 3]),
+    /// A `sampler2D`
+    Texture(Texture),
+}
+
+impl Uniform {
+    pub(crate) fn as_uniform_value(&self) -> UniformValue<'_> {
+        match self {
+            Uniform::Float(f) => UniformValue::Float(*f),
+            Uniform::Vec2(v) => UniformValue::Vec2(*v),
+            Uniform::Vec3(v) => UniformValue::Vec3(*v),
+            Uniform::Vec4(v) => UniformValue::Vec4(*v),
+            Uniform::Int(i) => UniformValue::SignedInt(*i),
+            Uniform::Bool(b) => UniformValue::Bool(*b),
+            Uniform::Mat3(m) => UniformValue::Mat3(*m),
+            Uniform::Texture(texture) => texture.uniform_value(),
+        }
+    }
+}
+
+macro_rules! uniform_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for Uniform {
+                fn from(val: $ty) -> Self {
+                    Uniform::$variant(val)
+                }
+            }
+        )*
+    };
+}
+
+uniform_from!(
+    f32 => Float,
+    [f32; 2] => Vec2,
+    [f32; 3] => Vec3,
+    [f32; 4] => Vec4,
+    i32 => Int,
+    bool => Bool,
+    [[f32; 3]; 3] => Mat3,
+    Texture => Texture,
+);
+
+impl<'a> From<&'a Texture> for Uniform {
+    fn from(texture: &'a Texture) -> Self {
+        Uniform::Texture(texture.clone())
+    }
+}
+
+/// A list of named uniform values
+pub(crate) struct UniformList<'a>(pub Vec<(&'a str, UniformValue<'a>)>);
+
+impl<'a> Uniforms for UniformList<'a> {
+    fn visit_values<'b, F: FnMut(&str, UniformValue<'b>)>(&'b self, mut f: F) {
+        for (name, value) in &self.0 {
+            f(name, *value);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+#version 140
+
+uniform sampler2D tex;
+uniform float threshold;
+
+in vec4 vertex_color;
+in vec2 vertex_uv;
+out vec4 fragment_color;
+
+void main() {
+  vec4 tex_color = texture(tex, vertex_uv);
+  float brightness = max(tex_color.r, max(tex_color.g, tex_color.b));
+  float factor = max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+  fragment_color = vec4(tex_color.rgb * factor, 1.0);
+}
\ No newline at end of file
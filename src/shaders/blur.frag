@@ -0,0 +1,20 @@
+#version 140
+
+uniform sampler2D tex;
+uniform vec2 direction;
+
+in vec4 vertex_color;
+in vec2 vertex_uv;
+out vec4 fragment_color;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+  vec2 step = direction / vec2(textureSize(tex, 0));
+  vec3 sum = texture(tex, vertex_uv).rgb * weights[0];
+  for (int i = 1; i < 5; i++) {
+    sum += texture(tex, vertex_uv + step * float(i)).rgb * weights[i];
+    sum += texture(tex, vertex_uv - step * float(i)).rgb * weights[i];
+  }
+  fragment_color = vec4(sum, 1.0);
+}
\ No newline at end of file
@@ -1,6 +1,20 @@
 #version 140
 
+uniform sampler2D tex;
+uniform bool srgb;
+
 in vec4 vertex_color;
+in vec2 vertex_uv;
 out vec4 fragment_color;
 
-void main() { fragment_color = vertex_color; }
\ No newline at end of file
+vec3 srgb_to_linear(vec3 c) {
+  return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), step(0.04045, c));
+}
+
+void main() {
+  vec4 tex_color = texture(tex, vertex_uv);
+  if (srgb) {
+    tex_color.rgb = srgb_to_linear(tex_color.rgb);
+  }
+  fragment_color = vertex_color * tex_color;
+}
\ No newline at end of file
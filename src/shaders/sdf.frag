@@ -0,0 +1,28 @@
+#version 140
+
+uniform sampler2D tex;
+uniform float outline_width;
+uniform vec4 outline_color;
+uniform float glow_width;
+uniform vec4 glow_color;
+
+in vec4 vertex_color;
+in vec2 vertex_uv;
+out vec4 fragment_color;
+
+void main() {
+  float dist = texture(tex, vertex_uv).a;
+  float smoothing = max(fwidth(dist) * 0.5, 0.0001);
+  float edge = 0.5 - outline_width;
+  float fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, dist);
+  float shape = smoothstep(edge - smoothing, edge + smoothing, dist);
+  vec4 outline = outline_width > 0.0 ? outline_color : vertex_color;
+  outline.a *= vertex_color.a;
+  vec4 color = mix(outline, vertex_color, fill);
+  color.a *= shape;
+  float glow = glow_width > 0.0 ? smoothstep(edge - glow_width, edge, dist) : 0.0;
+  float glow_alpha = glow_color.a * vertex_color.a * glow * (1.0 - color.a);
+  float alpha = color.a + glow_alpha;
+  vec3 rgb = (color.rgb * color.a + glow_color.rgb * glow_alpha) / max(alpha, 0.0001);
+  fragment_color = vec4(rgb, alpha);
+}
\ No newline at end of file
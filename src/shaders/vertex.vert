@@ -1,13 +1,20 @@
 #version 140
 
-uniform mat3 transform;
-uniform vec4 color;
+uniform bool srgb;
 
 in vec2 pos;
+in vec2 uv;
+in vec4 color;
 
 out vec4 vertex_color;
+out vec2 vertex_uv;
+
+vec3 srgb_to_linear(vec3 c) {
+  return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), step(0.04045, c));
+}
 
 void main() {
-  gl_Position = vec4(vec3(pos, 1.0) * transform, 1.0);
-  vertex_color = color;
+  gl_Position = vec4(pos, 0.0, 1.0);
+  vertex_color = srgb ? vec4(srgb_to_linear(color.rgb), color.a) : color;
+  vertex_uv = uv;
 }
\ No newline at end of file
@@ -0,0 +1,69 @@
+use rustybuzz::{Direction, Face, Feature, Tag, UnicodeBuffer, Variation};
+use unicode_bidi::BidiInfo;
+
+use crate::{FontVariation, GlyphId, ShapedGlyph};
+
+/**
+Shape a line of text with a font's OpenType tables
+
+The line is split into runs of the same direction, which are shaped
+separately and returned in visual order. The scale converts font units
+to the glyph resolution. The variation sets the axes of variable fonts.
+Advances are rounded up like unshaped ones. Returns `None` if the font
+cannot be parsed.
+*/
+pub(crate) fn shape_line(
+    data: &[u8],
+    variation: &FontVariation,
+    text: &str,
+    scale: f32,
+    kerning: bool,
+) -> Option<Vec<ShapedGlyph>> {
+    let mut face = Face::from_slice(data, 0)?;
+    let variations: Vec<Variation> = variation
+        .axes
+        .iter()
+        .map(|&(tag, value)| Variation {
+            tag: Tag::from_bytes(&tag),
+            value,
+        })
+        .collect();
+    face.set_variations(&variations);
+    let features = if kerning {
+        Vec::new()
+    } else {
+        vec![Feature::new(Tag::from_bytes(b"kern"), 0, ..)]
+    };
+    let bidi = BidiInfo::new(text, None);
+    let mut glyphs = Vec::new();
+    for paragraph in &bidi.paragraphs {
+        let (levels, runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+        for run in runs {
+            let rtl = levels[run.start].is_rtl();
+            let mut buffer = UnicodeBuffer::new();
+            buffer.push_str(&text[run.clone()]);
+            buffer.set_direction(if rtl {
+                Direction::RightToLeft
+            } else {
+                Direction::LeftToRight
+            });
+            let shaped = rustybuzz::shape(&face, &features, buffer);
+            for (info, pos) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+                let index = run.start + info.cluster as usize;
+                // Control characters are kept so that they are not drawn
+                let id = match text[index..].chars().next() {
+                    Some(ch) if ch as u32 <= 0x1F => GlyphId::Char(ch),
+                    _ => GlyphId::Index(info.codepoint as u16),
+                };
+                glyphs.push(ShapedGlyph {
+                    index,
+                    id,
+                    advance: (pos.x_advance as f32 * scale).ceil(),
+                    offset: [pos.x_offset as f32 * scale, pos.y_offset as f32 * scale],
+                    rtl,
+                });
+            }
+        }
+    }
+    Some(glyphs)
+}
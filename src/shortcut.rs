@@ -0,0 +1,149 @@
+use std::{fmt, str::FromStr};
+
+use crate::{ButtonState, Event, Key, KuleError, Modifiers, StateTracker};
+
+/**
+A keyboard shortcut made of a key and the modifiers held with it
+
+Shortcuts can be parsed from strings like `"Ctrl+Shift+P"` or `"Ctrl + ,"`.
+The last part is the key, and the other parts are modifiers: `Ctrl`, `Shift`,
+`Alt`, and `Super`. Names are not case-sensitive, and `Control`, `Option`,
+`Cmd`, and `Logo` are also accepted. Keys are parsed the same way as
+with `Key`'s `FromStr` implementation, so they can be written as the
+character they type, like `1` or `,`.
+
+Shortcuts are displayed the way they should be shown to users, like `"Ctrl + ,"`.
+
+The modifiers must match exactly, so `"Ctrl+S"` does not match
+when Shift is also held.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    /// The modifiers that must be held
+    pub modifiers: Modifiers,
+    /// The key that must be pressed
+    pub key: Key,
+}
+
+impl Shortcut {
+    /// Create a new shortcut from modifiers and a key
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        Shortcut { modifiers, key }
+    }
+    /**
+    Check if an event triggers the shortcut
+
+    Held keys repeat their press events, so holding the shortcut
+    triggers it repeatedly.
+    */
+    pub fn matches(&self, event: &Event, tracker: &StateTracker) -> bool {
+        matches!(*event, Event::Key { key, state: ButtonState::Pressed, .. } if key == self.key)
+            && tracker.modifiers() == self.modifiers
+    }
+    /// Check if the shortcut's key was pressed since the last update with its modifiers held
+    pub fn pressed(&self, tracker: &StateTracker) -> bool {
+        tracker.key_pressed(self.key) && tracker.modifiers() == self.modifiers
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = KuleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || KuleError::InvalidShortcut(s.into());
+        let s = s.trim();
+        // The key comes after the last plus, unless the key ends with a plus,
+        // like "Numpad +"
+        let (modifier_str, key) = if let Some(rest) = s.strip_suffix('+') {
+            let rest = rest.trim_end();
+            if let Some(modifier_str) = rest.strip_suffix('+') {
+                (modifier_str, "+".into())
+            } else {
+                let (modifier_str, word) = rest
+                    .rfind('+')
+                    .map_or(("", rest), |i| (&rest[..i], rest[i + 1..].trim()));
+                (modifier_str, format!("{} +", word))
+            }
+        } else if let Some(i) = s.rfind('+') {
+            (&s[..i], s[i + 1..].trim().into())
+        } else {
+            ("", s.into())
+        };
+        let key: Key = key.trim().parse().map_err(|_| invalid())?;
+        let mut modifiers = Modifiers::empty();
+        let parts = modifier_str.split('+').map(str::trim);
+        for part in parts.filter(|part| !part.is_empty()) {
+            modifiers |= match part.to_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CTRL,
+                "shift" => Modifiers::SHIFT,
+                "alt" | "option" => Modifiers::ALT,
+                "super" | "cmd" | "command" | "logo" => Modifiers::LOGO,
+                _ => return Err(invalid()),
+            };
+        }
+        Ok(Shortcut::new(modifiers, key))
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in &[
+            (Modifiers::CTRL, "Ctrl"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::LOGO, "Super"),
+        ] {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{} + ", name)?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let parse = |s: &str| s.parse::<Shortcut>().ok();
+        assert_eq!(
+            parse("Ctrl+S"),
+            Some(Shortcut::new(Modifiers::CTRL, Key::S))
+        );
+        assert_eq!(
+            parse(" control + shift + p "),
+            Some(Shortcut::new(Modifiers::CTRL | Modifiers::SHIFT, Key::P))
+        );
+        assert_eq!(
+            parse("Cmd+,"),
+            Some(Shortcut::new(Modifiers::LOGO, Key::Comma))
+        );
+        assert_eq!(
+            parse("1"),
+            Some(Shortcut::new(Modifiers::empty(), Key::Num1))
+        );
+        assert_eq!(
+            parse("Alt + Numpad +"),
+            Some(Shortcut::new(Modifiers::ALT, Key::Add))
+        );
+        assert_eq!(parse("Ctrl+"), None);
+        assert_eq!(parse("Hyper+S"), None);
+        assert_eq!(parse("Ctrl+Nothing"), None);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for shortcut in &[
+            Shortcut::new(Modifiers::CTRL | Modifiers::ALT, Key::Delete),
+            Shortcut::new(Modifiers::CTRL, Key::Comma),
+            Shortcut::new(Modifiers::SHIFT, Key::Add),
+            Shortcut::new(Modifiers::empty(), Key::Space),
+        ] {
+            assert_eq!(
+                shortcut.to_string().parse::<Shortcut>().ok(),
+                Some(*shortcut)
+            );
+        }
+    }
+}
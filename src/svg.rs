@@ -0,0 +1,171 @@
+use usvg::{NodeExt, NodeKind, Paint, PathSegment, Transform, Tree, Visibility};
+
+use crate::{
+    Canvas, Col, Color, Drawer, Fill, FillRule, KuleResult, LineCap, LineJoin, Mesh, MeshBuilder,
+    PathBuilder, Resources, Stroke, Transformable, Vec2, Vertex,
+};
+
+/**
+A vector image loaded from an SVG file
+
+The image is tessellated into a single [`Mesh`](struct.Mesh.html) when it is loaded,
+so drawing it is as cheap as drawing any other mesh. The image's coordinates are the
+same as the SVG's, with the origin at the top left.
+
+Only solid color fills and strokes are supported. Paths painted with gradients
+or patterns, as well as raster images, are skipped.
+*/
+#[derive(Debug, Clone)]
+pub struct Svg {
+    mesh: Mesh,
+    size: Vec2,
+}
+
+impl Svg {
+    /// Load an SVG file
+    pub fn load<P>(path: P) -> KuleResult<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Svg::decode(&std::fs::read(path)?)
+    }
+    /// Decode SVG data
+    ///
+    /// The data may be gzip compressed
+    pub fn decode(bytes: &[u8]) -> KuleResult<Self> {
+        let tree = Tree::from_data(bytes, &usvg::Options::default())?;
+        let size = tree.svg_node().size;
+        let mut builder = MeshBuilder::new();
+        add_node(&mut builder, &tree.root())?;
+        Ok(Svg {
+            mesh: builder.build(),
+            size: [size.width() as f32, size.height() as f32],
+        })
+    }
+    /// Get the mesh
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+    /// Get the size of the image
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+}
+
+/// Add the paths of a node and its children to a mesh
+fn add_node(builder: &mut MeshBuilder, node: &usvg::Node) -> KuleResult<()> {
+    match &*node.borrow() {
+        NodeKind::Svg(_) | NodeKind::Group(_) => {}
+        NodeKind::Path(path) => {
+            if path.visibility != Visibility::Visible {
+                return Ok(());
+            }
+            let mut transform = node.abs_transform();
+            transform.append(&path.transform);
+            let kule_path = convert_path(&path.data, &transform);
+            if let Some(fill) = &path.fill {
+                if let Paint::Color(color) = fill.paint {
+                    let rule = match fill.rule {
+                        usvg::FillRule::NonZero => FillRule::NonZero,
+                        usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+                    };
+                    let mesh = kule_path.fill(Fill::new().rule(rule))?;
+                    add_colored(builder, &mesh, convert_color(color, fill.opacity.value()));
+                }
+            }
+            if let Some(stroke) = &path.stroke {
+                if let Paint::Color(color) = stroke.paint {
+                    let (sx, sy) = transform.get_scale();
+                    let scale = (sx * sy).abs().sqrt();
+                    let cap = match stroke.linecap {
+                        usvg::LineCap::Butt => LineCap::Butt,
+                        usvg::LineCap::Round => LineCap::Round,
+                        usvg::LineCap::Square => LineCap::Square,
+                    };
+                    let join = match stroke.linejoin {
+                        usvg::LineJoin::Miter => LineJoin::Miter,
+                        usvg::LineJoin::Round => LineJoin::Round,
+                        usvg::LineJoin::Bevel => LineJoin::Bevel,
+                    };
+                    let mesh = kule_path.stroke(
+                        Stroke::new((stroke.width.value() * scale) as f32)
+                            .cap(cap)
+                            .join(join)
+                            .miter_limit(stroke.miterlimit.value() as f32),
+                    )?;
+                    add_colored(builder, &mesh, convert_color(color, stroke.opacity.value()));
+                }
+            }
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+    for child in node.children() {
+        add_node(builder, &child)?;
+    }
+    Ok(())
+}
+
+/// Convert SVG path data into a path
+fn convert_path(data: &usvg::PathData, transform: &Transform) -> crate::Path {
+    let point = |x: f64, y: f64| {
+        let (x, y) = transform.apply(x, y);
+        [x as f32, y as f32]
+    };
+    let mut builder = PathBuilder::new();
+    for segment in data.iter() {
+        match *segment {
+            PathSegment::MoveTo { x, y } => {
+                builder.move_to(point(x, y));
+            }
+            PathSegment::LineTo { x, y } => {
+                builder.line_to(point(x, y));
+            }
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                builder.cubic_to(point(x1, y1), point(x2, y2), point(x, y));
+            }
+            PathSegment::ClosePath => {
+                builder.close();
+            }
+        }
+    }
+    builder.build()
+}
+
+/// Convert an SVG color and opacity into a color
+fn convert_color(color: usvg::Color, opacity: f64) -> Col {
+    Col::rgba(
+        color.red as f32 / 255.0,
+        color.green as f32 / 255.0,
+        color.blue as f32 / 255.0,
+        opacity as f32,
+    )
+}
+
+/// Add a mesh to a builder with all of its vertices set to a color
+fn add_colored(builder: &mut MeshBuilder, mesh: &Mesh, color: Col) {
+    let vertices = mesh
+        .vertices()
+        .iter()
+        .map(|v| Vertex { color, ..*v })
+        .collect();
+    builder.mesh(&Mesh::new(vertices, mesh.indices().to_vec()));
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /// Draw an SVG image
+    pub fn svg(&mut self, svg: &Svg) -> Transformable<'ctx, '_, T, R> {
+        self.mesh(Col::white(), svg.mesh())
+    }
+}
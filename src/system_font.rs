@@ -0,0 +1,368 @@
+use std::{
+    collections::HashSet,
+    env,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use ttf_parser::name_id;
+
+/// The tag at the start of font collection files
+const COLLECTION_TAG: &[u8; 4] = b"ttcf";
+
+/// The fonts to use when a font cannot be found, in order of preference
+const DEFAULT_FONTS: &[&str] = if cfg!(target_os = "windows") {
+    &["Segoe UI", "Arial", "Tahoma"]
+} else if cfg!(target_os = "macos") {
+    &["Helvetica Neue", "Helvetica", "Arial"]
+} else {
+    &["DejaVu Sans", "Noto Sans", "Liberation Sans", "FreeSans"]
+};
+
+/// A face of an installed font
+#[derive(Debug)]
+struct SystemFace {
+    path: PathBuf,
+    /// The offset of the face's table directory in the file
+    offset: u32,
+    /// Whether the file is a font collection
+    collection: bool,
+    full_names: Vec<String>,
+    families: Vec<String>,
+    regular: bool,
+}
+
+/// An entry in a font's table directory
+#[derive(Debug, Clone, Copy)]
+struct TableRecord {
+    tag: [u8; 4],
+    checksum: u32,
+    offset: u32,
+    len: u32,
+}
+
+/// The directories that fonts are installed in on the current platform
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let home = env::var_os("HOME").map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        if let Some(windir) = env::var_os("WINDIR") {
+            dirs.push(PathBuf::from(windir).join("Fonts"));
+        }
+        if let Some(local) = env::var_os("LOCALAPPDATA") {
+            dirs.push(PathBuf::from(local).join("Microsoft/Windows/Fonts"));
+        }
+    } else if cfg!(target_os = "macos") {
+        dirs.push("/System/Library/Fonts".into());
+        dirs.push("/Library/Fonts".into());
+        if let Some(home) = &home {
+            dirs.push(home.join("Library/Fonts"));
+        }
+    } else {
+        dirs.push("/usr/share/fonts".into());
+        dirs.push("/usr/local/share/fonts".into());
+        if let Some(home) = &home {
+            dirs.push(home.join(".fonts"));
+        }
+        match env::var_os("XDG_DATA_HOME") {
+            Some(data) => dirs.push(PathBuf::from(data).join("fonts")),
+            None => {
+                if let Some(home) = &home {
+                    dirs.push(home.join(".local/share/fonts"));
+                }
+            }
+        }
+    }
+    dirs
+}
+
+/// Read a big-endian `u16` from some bytes
+fn read_u16(bytes: &[u8], i: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*bytes.get(i)?, *bytes.get(i + 1)?]))
+}
+
+/// Read a big-endian `u32` from some bytes
+fn read_u32(bytes: &[u8], i: usize) -> Option<u32> {
+    Some(u32::from_be_bytes([
+        *bytes.get(i)?,
+        *bytes.get(i + 1)?,
+        *bytes.get(i + 2)?,
+        *bytes.get(i + 3)?,
+    ]))
+}
+
+/// Read some number of bytes from a position in a file, or `None` if they are not all in the file
+fn read_at(file: &mut File, offset: u64, len: usize) -> Option<Vec<u8>> {
+    let file_len = file.metadata().ok()?.len();
+    if offset.checked_add(len as u64)? > file_len {
+        return None;
+    }
+    let mut bytes = vec![0; len];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// Parse the records of a table directory that starts at the beginning of some bytes
+fn table_records(dir: &[u8]) -> Option<Vec<TableRecord>> {
+    let count = read_u16(dir, 4)? as usize;
+    (0..count)
+        .map(|i| {
+            let i = 12 + i * 16;
+            let tag = dir.get(i..i + 4)?;
+            Some(TableRecord {
+                tag: [tag[0], tag[1], tag[2], tag[3]],
+                checksum: read_u32(dir, i + 4)?,
+                offset: read_u32(dir, i + 8)?,
+                len: read_u32(dir, i + 12)?,
+            })
+        })
+        .collect()
+}
+
+/// Read a table of a face from a font file
+fn read_table(file: &mut File, records: &[TableRecord], tag: &[u8; 4]) -> Option<Vec<u8>> {
+    let record = records.iter().find(|record| &record.tag == tag)?;
+    read_at(file, record.offset as u64, record.len as usize)
+}
+
+/// Parse the strings of a `name` table along with their name ids
+fn parse_names(table: &[u8]) -> Vec<(u16, String)> {
+    let count = read_u16(table, 2).unwrap_or(0) as usize;
+    let storage = read_u16(table, 4).unwrap_or(0) as usize;
+    (0..count)
+        .filter_map(|i| {
+            let i = 6 + i * 12;
+            let platform = read_u16(table, i)?;
+            let id = read_u16(table, i + 6)?;
+            let len = read_u16(table, i + 8)? as usize;
+            let start = storage + read_u16(table, i + 10)? as usize;
+            let bytes = table.get(start..start + len)?;
+            let name = match platform {
+                // Unicode and Windows names are UTF-16
+                0 | 3 => {
+                    let units: Vec<u16> = bytes
+                        .chunks_exact(2)
+                        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                        .collect();
+                    String::from_utf16(&units).ok()?
+                }
+                // Macintosh names are usually ASCII
+                1 if bytes.is_ascii() => bytes.iter().map(|&b| b as char).collect(),
+                _ => return None,
+            };
+            Some((id, name))
+        })
+        .collect()
+}
+
+/// Read the names and style of a face in a font file without reading the rest of the file
+fn index_face(file: &mut File, path: &Path, offset: u32, collection: bool) -> Option<SystemFace> {
+    let header = read_at(file, offset as u64, 12)?;
+    let count = read_u16(&header, 4)? as usize;
+    let dir = read_at(file, offset as u64, 12 + count * 16)?;
+    let records = table_records(&dir)?;
+    let names = parse_names(&read_table(file, &records, b"name")?);
+    let named = |id: u16| -> Vec<String> {
+        names
+            .iter()
+            .filter(|(name_id, _)| *name_id == id)
+            .map(|(_, name)| name.clone())
+            .collect()
+    };
+    let mut families = named(name_id::TYPOGRAPHIC_FAMILY);
+    families.extend(named(name_id::FAMILY));
+    // Faces with the regular bit are regular, as are faces without the italic or bold bits
+    let selection = read_table(file, &records, b"OS/2").and_then(|os2| read_u16(&os2, 62));
+    let regular = !matches!(selection, Some(bits) if bits & 0x40 == 0 && bits & 0x21 != 0);
+    Some(SystemFace {
+        path: path.into(),
+        offset,
+        collection,
+        full_names: named(name_id::FULL_NAME),
+        families,
+        regular,
+    })
+}
+
+/// Read the names of every face in a font file
+fn index_file(path: &Path, faces: &mut Vec<SystemFace>) {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return,
+    };
+    let header = match read_at(&mut file, 0, 12) {
+        Some(header) => header,
+        None => return,
+    };
+    if header.starts_with(COLLECTION_TAG) {
+        let len = file.metadata().map_or(0, |meta| meta.len());
+        let count = read_u32(&header, 8).unwrap_or(0) as u64;
+        // Make sure the offsets fit in the file before reading them
+        if 12 + count * 4 > len {
+            return;
+        }
+        let offsets = read_at(&mut file, 12, count as usize * 4).unwrap_or_default();
+        for offset in offsets.chunks_exact(4) {
+            let offset = u32::from_be_bytes([offset[0], offset[1], offset[2], offset[3]]);
+            faces.extend(index_face(&mut file, path, offset, true));
+        }
+    } else {
+        faces.extend(index_face(&mut file, path, 0, false));
+    }
+}
+
+/**
+Recursively index the font files in a directory
+
+Directories are canonicalized so that symbolic links
+that lead to a directory that was already visited are skipped.
+*/
+fn index_dir(dir: &Path, visited: &mut HashSet<PathBuf>, faces: &mut Vec<SystemFace>) {
+    match fs::canonicalize(dir) {
+        Ok(canonical) => {
+            if !visited.insert(canonical) {
+                return;
+            }
+        }
+        Err(_) => return,
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let is_font = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => ["ttf", "otf", "ttc", "otc"]
+                .iter()
+                .any(|font_ext| ext.eq_ignore_ascii_case(font_ext)),
+            None => false,
+        };
+        if path.is_dir() {
+            index_dir(&path, visited, faces);
+        } else if is_font {
+            index_file(&path, faces);
+        }
+    }
+}
+
+/// Get the faces of every installed font, which are indexed the first time this is called
+fn system_faces() -> &'static [SystemFace] {
+    static FACES: OnceLock<Vec<SystemFace>> = OnceLock::new();
+    FACES.get_or_init(|| {
+        let mut visited = HashSet::new();
+        let mut faces = Vec::new();
+        for dir in system_font_dirs() {
+            index_dir(&dir, &mut visited, &mut faces);
+        }
+        faces
+    })
+}
+
+/**
+Get how well a font face matches a name
+
+A face matches if its full name or family name is the same as the name,
+ignoring case. Exact full name matches are best, followed by the regular
+face of a family.
+*/
+fn match_score(face: &SystemFace, name: &str) -> Option<u8> {
+    let named = |names: &[String]| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+    if named(&face.full_names) {
+        Some(3)
+    } else if named(&face.families) {
+        Some(if face.regular { 2 } else { 1 })
+    } else {
+        None
+    }
+}
+
+/// Find the installed face that best matches a name
+fn find_face(name: &str) -> Option<&'static SystemFace> {
+    system_faces()
+        .iter()
+        .filter_map(|face| match_score(face, name).map(|score| (score, face)))
+        // Prefer the first face with the best score
+        .fold(
+            None,
+            |best: Option<(u8, &SystemFace)>, (score, face)| match best {
+                Some((best_score, _)) if best_score >= score => best,
+                _ => Some((score, face)),
+            },
+        )
+        .map(|(_, face)| face)
+}
+
+/**
+Read the data of a face
+
+Faces in collections are copied out into their own font data,
+as fonts are always loaded from the first face of their data.
+*/
+fn read_face(face: &SystemFace) -> Option<Vec<u8>> {
+    let data = fs::read(&face.path).ok()?;
+    if !face.collection {
+        return Some(data);
+    }
+    let dir = data.get(face.offset as usize..)?;
+    let records = table_records(dir)?;
+    let header_len = 12 + records.len() * 16;
+    let mut font = dir.get(..12)?.to_vec();
+    let mut tables = Vec::new();
+    for record in &records {
+        let start = record.offset as usize;
+        let table = data.get(start..start.checked_add(record.len as usize)?)?;
+        font.extend_from_slice(&record.tag);
+        font.extend_from_slice(&record.checksum.to_be_bytes());
+        font.extend_from_slice(&((header_len + tables.len()) as u32).to_be_bytes());
+        font.extend_from_slice(&record.len.to_be_bytes());
+        tables.extend_from_slice(table);
+        // Tables are aligned to 4 bytes
+        tables.resize(tables.len().next_multiple_of(4), 0);
+    }
+    font.extend(tables);
+    Some(font)
+}
+
+/// Find an installed font by name and read its data, without falling back to a default font
+pub(crate) fn find_named_system_font(name: &str) -> Option<Vec<u8>> {
+    read_face(find_face(name)?)
+}
+
+/**
+Read the data of the platform's default font
+
+This is a common sans-serif font for the platform, such as Segoe UI
+on Windows, Helvetica on macOS, or DejaVu Sans on Linux. If none of
+those are installed, the first regular installed face is used.
+
+Returns `None` only if no fonts are installed.
+*/
+pub fn default_system_font() -> Option<Vec<u8>> {
+    let face = DEFAULT_FONTS
+        .iter()
+        .find_map(|name| find_face(name))
+        .or_else(|| system_faces().iter().find(|face| face.regular))
+        .or_else(|| system_faces().first())?;
+    read_face(face)
+}
+
+/**
+Find an installed font by name and read its data
+
+The name can be a family name, such as `"Arial"`, or the full name of a
+specific face, such as `"Arial Bold"`. When a family name is given, the
+regular face is preferred. Case is ignored. If no installed font matches
+the name, the [default font](fn.default_system_font.html) is used instead.
+
+Fonts are searched for in the platform's standard font directories.
+Faces in font collection files are found as well. The directories are
+only searched the first time a font is looked up, so fonts that are
+installed while the program is running are not found.
+*/
+pub fn find_system_font(name: &str) -> Option<Vec<u8>> {
+    find_named_system_font(name).or_else(default_system_font)
+}
@@ -0,0 +1,455 @@
+use std::{fmt, rc::Rc};
+
+use glium::{
+    backend::Facade,
+    framebuffer::StencilRenderBuffer,
+    texture::{MipmapsOption, RawImage2d, StencilFormat},
+    uniforms::{
+        MagnifySamplerFilter, MinifySamplerFilter, Sampler, SamplerWrapFunction, UniformValue,
+    },
+    Texture2d,
+};
+
+use crate::{CanFail, KuleError, KuleResult};
+
+/**
+A 2D image stored on the gpu
+
+Textures are cheap to clone, as clones share the same gpu memory.
+
+Textures can be created using anything that implements glium's `Facade` trait,
+including the context's [`Window`](struct.Window.html).
+*/
+#[derive(Clone)]
+pub struct Texture {
+    texture: Rc<Texture2d>,
+    options: TextureOptions,
+}
+
+impl Texture {
+    /// Create a new texture from an image file
+    #[cfg(feature = "image")]
+    pub fn load<F, P>(facade: &F, path: P) -> KuleResult<Self>
+    where
+        F: Facade,
+        P: AsRef<std::path::Path>,
+    {
+        Texture::load_with(facade, path, TextureOptions::default())
+    }
+    /// Create a new texture from an image file with the given options
+    #[cfg(feature = "image")]
+    pub fn load_with<F, P>(facade: &F, path: P, options: TextureOptions) -> KuleResult<Self>
+    where
+        F: Facade,
+        P: AsRef<std::path::Path>,
+    {
+        Texture::decode_with(facade, &std::fs::read(path)?, options)
+    }
+    /**
+    Create a new texture from encoded image data
+
+    Supports PNG and JPEG
+    */
+    #[cfg(feature = "image")]
+    pub fn decode<F>(facade: &F, bytes: &[u8]) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        Texture::decode_with(facade, bytes, TextureOptions::default())
+    }
+    /**
+    Create a new texture from encoded image data with the given options
+
+    Supports PNG and JPEG
+    */
+    #[cfg(feature = "image")]
+    pub fn decode_with<F>(facade: &F, bytes: &[u8], options: TextureOptions) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        let dimensions = image.dimensions();
+        let raw = RawImage2d::from_raw_rgba(image.into_raw(), dimensions);
+        let mipmaps = if options.mipmaps.is_some() {
+            MipmapsOption::AutoGeneratedMipmaps
+        } else {
+            MipmapsOption::NoMipmap
+        };
+        Ok(Texture {
+            texture: Rc::new(Texture2d::with_mipmaps(facade, raw, mipmaps)?),
+            options,
+        })
+    }
+    /**
+    Create a new texture from raw RGBA data
+
+    There must be 4 bytes for every pixel. Rows go from top to bottom.
+    */
+    pub fn from_rgba<F>(facade: &F, width: u32, height: u32, bytes: &[u8]) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        Texture::from_rgba_with(facade, width, height, bytes, TextureOptions::default())
+    }
+    /// Create a new texture from raw RGBA data with the given options
+    pub fn from_rgba_with<F>(
+        facade: &F,
+        width: u32,
+        height: u32,
+        bytes: &[u8],
+        options: TextureOptions,
+    ) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        check_rgba_len(width, height, bytes)?;
+        let raw = RawImage2d::from_raw_rgba(bytes.to_vec(), (width, height));
+        let mipmaps = if options.mipmaps.is_some() {
+            MipmapsOption::AutoGeneratedMipmaps
+        } else {
+            MipmapsOption::NoMipmap
+        };
+        Ok(Texture {
+            texture: Rc::new(Texture2d::with_mipmaps(facade, raw, mipmaps)?),
+            options,
+        })
+    }
+    /**
+    Replace the contents of the texture with raw RGBA data
+
+    The data must be the same size as the texture. This is cheap enough to do
+    every frame, so it can be used to display procedurally generated images.
+    Clones of the texture share its contents, so they are updated as well.
+
+    Mipmaps are not regenerated.
+    */
+    pub fn write_rgba(&self, bytes: &[u8]) -> CanFail {
+        self.write_rgba_region([0, 0, self.width(), self.height()], bytes)
+    }
+    /**
+    Replace the contents of a region of the texture with raw RGBA data
+
+    The region is given as `[left, top, width, height]` in pixels
+    and must be inside the texture.
+    */
+    pub fn write_rgba_region(&self, [left, top, width, height]: [u32; 4], bytes: &[u8]) -> CanFail {
+        check_rgba_len(width, height, bytes)?;
+        if left as u64 + width as u64 > self.width() as u64
+            || top as u64 + height as u64 > self.height() as u64
+        {
+            return Err(KuleError::TextureRegion {
+                region: [left, top, width, height],
+                size: [self.width(), self.height()],
+            });
+        }
+        let raw = RawImage2d::from_raw_rgba(bytes.to_vec(), (width, height));
+        self.texture.write(
+            glium::Rect {
+                left,
+                bottom: top,
+                width,
+                height,
+            },
+            raw,
+        );
+        Ok(())
+    }
+    /// Create a 1x1 white texture
+    pub(crate) fn blank<F>(facade: &F) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        let raw = RawImage2d::from_raw_rgba(vec![255u8; 4], (1, 1));
+        Ok(Texture {
+            texture: Rc::new(Texture2d::new(facade, raw)?),
+            options: TextureOptions::default(),
+        })
+    }
+    /// Get the texture's options
+    pub fn options(&self) -> TextureOptions {
+        self.options
+    }
+    /**
+    Get a handle to the same texture with different options
+
+    The returned texture shares gpu memory with this one.
+
+    Mipmaps are only generated when a texture is created, so setting
+    `TextureOptions::mipmaps` here has no effect if the texture has none.
+    */
+    pub fn with_options(&self, options: TextureOptions) -> Self {
+        Texture {
+            texture: Rc::clone(&self.texture),
+            options,
+        }
+    }
+    /// Get a sampler for the texture that uses its options
+    pub(crate) fn sampled(&self) -> Sampler<'_, Texture2d> {
+        let mipmaps = self
+            .options
+            .mipmaps
+            .filter(|_| self.texture.get_mipmap_levels() > 1);
+        let minify = match (self.options.min_filter, mipmaps) {
+            (filter, None) => filter.into(),
+            (Filter::Nearest, Some(Filter::Nearest)) => MinifySamplerFilter::NearestMipmapNearest,
+            (Filter::Nearest, Some(Filter::Linear)) => MinifySamplerFilter::NearestMipmapLinear,
+            (Filter::Linear, Some(Filter::Nearest)) => MinifySamplerFilter::LinearMipmapNearest,
+            (Filter::Linear, Some(Filter::Linear)) => MinifySamplerFilter::LinearMipmapLinear,
+        };
+        self.texture
+            .sampled()
+            .magnify_filter(self.options.filter.into())
+            .minify_filter(minify)
+            .wrap_function(self.options.wrap.into())
+    }
+    /// Get the uniform value used to pass the texture to a shader
+    pub(crate) fn uniform_value(&self) -> UniformValue<'_> {
+        UniformValue::Texture2d(&self.texture, Some(self.sampled().1))
+    }
+    /// Get whether the texture has mipmaps
+    pub fn has_mipmaps(&self) -> bool {
+        self.texture.get_mipmap_levels() > 1
+    }
+    /// Get the width of the texture in pixels
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+    /// Get the height of the texture in pixels
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+    /// Get the size of the texture in pixels
+    pub fn size(&self) -> [u32; 2] {
+        [self.width(), self.height()]
+    }
+    /// Get a reference to the inner glium texture
+    pub fn inner(&self) -> &Texture2d {
+        &self.texture
+    }
+}
+
+/// Check that raw RGBA data is the right length for an image size
+fn check_rgba_len(width: u32, height: u32, bytes: &[u8]) -> CanFail {
+    let expected = width as usize * height as usize * 4;
+    if bytes.len() == expected {
+        Ok(())
+    } else {
+        Err(KuleError::ImageDataLength {
+            expected,
+            found: bytes.len(),
+        })
+    }
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Texture({}x{})", self.width(), self.height())
+    }
+}
+
+impl PartialEq for Texture {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.texture, &other.texture) && self.options == other.options
+    }
+}
+
+impl Eq for Texture {}
+
+/// How a texture is sampled when it is scaled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// Use the color of the nearest pixel
+    ///
+    /// This keeps pixel art crisp
+    Nearest,
+    /// Linearly interpolate between the nearest pixels
+    Linear,
+}
+
+impl From<Filter> for MagnifySamplerFilter {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => MagnifySamplerFilter::Nearest,
+            Filter::Linear => MagnifySamplerFilter::Linear,
+        }
+    }
+}
+
+impl From<Filter> for MinifySamplerFilter {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => MinifySamplerFilter::Nearest,
+            Filter::Linear => MinifySamplerFilter::Linear,
+        }
+    }
+}
+
+/// How a texture is sampled outside of its bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wrap {
+    /// Use the color of the nearest edge pixel
+    Clamp,
+    /// Repeat the texture
+    Repeat,
+    /// Repeat the texture, mirroring every other repetition
+    Mirror,
+}
+
+impl From<Wrap> for SamplerWrapFunction {
+    fn from(wrap: Wrap) -> Self {
+        match wrap {
+            Wrap::Clamp => SamplerWrapFunction::Clamp,
+            Wrap::Repeat => SamplerWrapFunction::Repeat,
+            Wrap::Mirror => SamplerWrapFunction::Mirror,
+        }
+    }
+}
+
+/// Options for how a texture is sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureOptions {
+    /// The filter used when the texture is magnified
+    pub filter: Filter,
+    /// The filter used when the texture is minified
+    pub min_filter: Filter,
+    /**
+    Whether mipmaps should be generated and how to blend between them
+
+    Mipmaps prevent shimmering when a texture is drawn much smaller than its actual size,
+    such as when the camera is zoomed far out.
+    If this is `None`, no mipmaps are generated.
+    */
+    pub mipmaps: Option<Filter>,
+    /// How the texture is sampled outside of its bounds
+    pub wrap: Wrap,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        TextureOptions::new()
+    }
+}
+
+impl TextureOptions {
+    /// Create new `TextureOptions` with `Linear` filtering, no mipmaps, and `Clamp` wrapping
+    pub const fn new() -> Self {
+        TextureOptions {
+            filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmaps: None,
+            wrap: Wrap::Clamp,
+        }
+    }
+    /// Create new `TextureOptions` suitable for pixel art
+    ///
+    /// This uses `Nearest` filtering
+    pub const fn pixelated() -> Self {
+        TextureOptions::new().filter(Filter::Nearest)
+    }
+    /// Set both the `filter` and `min_filter`
+    pub const fn filter(self, filter: Filter) -> Self {
+        TextureOptions {
+            filter,
+            min_filter: filter,
+            ..self
+        }
+    }
+    /// Set the `min_filter`
+    pub const fn min_filter(self, min_filter: Filter) -> Self {
+        TextureOptions { min_filter, ..self }
+    }
+    /// Generate mipmaps and blend between them with the given filter
+    pub const fn mipmaps(self, filter: Filter) -> Self {
+        TextureOptions {
+            mipmaps: Some(filter),
+            ..self
+        }
+    }
+    /// Set the `wrap`
+    pub const fn wrap(self, wrap: Wrap) -> Self {
+        TextureOptions { wrap, ..self }
+    }
+}
+
+/**
+An offscreen surface that can be drawn to
+
+Draw to it with [`Context::draw_to`](struct.Context.html#method.draw_to),
+then draw its texture like any other.
+*/
+pub struct RenderTarget {
+    texture: Texture,
+    pub(crate) stencil: StencilRenderBuffer,
+}
+
+impl RenderTarget {
+    /// Create a new render target with the given size in pixels
+    pub fn new<F>(facade: &F, size: [u32; 2]) -> KuleResult<Self>
+    where
+        F: Facade,
+    {
+        let [width, height] = size;
+        Ok(RenderTarget {
+            texture: Texture {
+                texture: Rc::new(Texture2d::empty(facade, width, height)?),
+                options: TextureOptions::default(),
+            },
+            stencil: StencilRenderBuffer::new(facade, StencilFormat::I8, width, height)?,
+        })
+    }
+    /// Get the texture that is drawn to
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+    /// Get the size of the render target in pixels
+    pub fn size(&self) -> [u32; 2] {
+        self.texture.size()
+    }
+}
+
+impl fmt::Debug for RenderTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "RenderTarget({}x{})",
+            self.texture.width(),
+            self.texture.height()
+        )
+    }
+}
+
+/**
+A texture that can be stretched while keeping its corners fixed
+
+The texture is split into a 3x3 grid by the insets. When drawn, the corners
+keep their size, the edges stretch along one axis, and the center stretches
+along both. This is useful for scalable UI panels and buttons.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct NineSlice {
+    /// The texture
+    pub texture: Texture,
+    /// The insets of the slices in pixels in the order `[left, right, top, bottom]`
+    pub insets: [f32; 4],
+    /// The world-space size of a single texture pixel in the corners and edges
+    pub scale: f32,
+}
+
+impl NineSlice {
+    /// Create a new `NineSlice` with the default `scale` of `1.0`
+    pub fn new(texture: Texture, insets: [f32; 4]) -> Self {
+        NineSlice {
+            texture,
+            insets,
+            scale: 1.0,
+        }
+    }
+    /// Create a new `NineSlice` with the same inset on all sides
+    pub fn uniform(texture: Texture, inset: f32) -> Self {
+        NineSlice::new(texture, [inset; 4])
+    }
+    /// Set the `scale`
+    pub fn scale(self, scale: f32) -> Self {
+        NineSlice { scale, ..self }
+    }
+}